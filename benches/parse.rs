@@ -0,0 +1,92 @@
+//! Compare the parser backends on the OWL Functional files of `tests/data`.
+//!
+//! Run with `cargo bench`; a subset of the files can be selected by giving
+//! their names as arguments, e.g. `cargo bench -- bfo go`.
+
+extern crate curie;
+extern crate horned_functional;
+extern crate horned_owl;
+
+use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
+
+use curie::PrefixMapping;
+use horned_functional::Backend;
+use horned_functional::Context;
+use horned_functional::FromFunctional;
+use horned_owl::ontology::set::SetOntology;
+
+/// The minimum time spent parsing each file with each backend.
+const TARGET: Duration = Duration::from_millis(500);
+
+/// Parse `text` repeatedly with `backend`, and return the average time.
+fn measure(text: &str, backend: Backend) -> Option<Duration> {
+    let ctx = Context::default().with_backend(backend);
+    let mut runs = 0;
+    let start = Instant::now();
+    while runs == 0 || start.elapsed() < TARGET {
+        <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(text, &ctx).ok()?;
+        runs += 1;
+    }
+    Some(start.elapsed() / runs)
+}
+
+fn main() {
+    let filters = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect::<Vec<_>>();
+
+    let data = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data");
+    let mut paths = std::fs::read_dir(&data)
+        .expect("failed to read `tests/data`")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map(|ext| ext == "ofn").unwrap_or(false))
+        .filter(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy();
+            filters.is_empty() || filters.iter().any(|f| name == f.as_str())
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    println!(
+        "{:<16} {:>10} {:>12} {:>12} {:>8}",
+        "file", "size", "pest", "descent", "speedup"
+    );
+
+    let mut total = (Duration::default(), Duration::default());
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let text = std::fs::read_to_string(&path).unwrap();
+        match (
+            measure(&text, Backend::Pest),
+            measure(&text, Backend::Descent),
+        ) {
+            (Some(pest), Some(descent)) => {
+                println!(
+                    "{:<16} {:>9}K {:>10.2}ms {:>10.2}ms {:>7.2}x",
+                    name,
+                    text.len() / 1024,
+                    pest.as_secs_f64() * 1000.0,
+                    descent.as_secs_f64() * 1000.0,
+                    pest.as_secs_f64() / descent.as_secs_f64(),
+                );
+                total.0 += pest;
+                total.1 += descent;
+            }
+            _ => println!("{:<16} (skipped, not supported)", name),
+        }
+    }
+
+    println!(
+        "{:<16} {:>10} {:>10.2}ms {:>10.2}ms {:>7.2}x",
+        "total",
+        "",
+        total.0.as_secs_f64() * 1000.0,
+        total.1.as_secs_f64() * 1000.0,
+        total.0.as_secs_f64() / total.1.as_secs_f64(),
+    );
+}
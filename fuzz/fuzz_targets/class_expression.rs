@@ -0,0 +1,25 @@
+#![no_main]
+
+use curie::PrefixMapping;
+use horned_functional::Backend;
+use horned_functional::Context;
+use horned_functional::FromFunctional;
+use horned_owl::model::ClassExpression;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let mut prefixes = PrefixMapping::default();
+        prefixes.set_default("http://example.com/");
+        prefixes
+            .add_prefix("obo", "http://purl.obolibrary.org/obo/")
+            .unwrap();
+        prefixes
+            .add_prefix("xsd", "http://www.w3.org/2001/XMLSchema#")
+            .unwrap();
+        for backend in [Backend::Pest, Backend::Descent] {
+            let ctx = Context::new(None, &prefixes).with_backend(backend);
+            let _ = ClassExpression::<String>::from_ofn_ctx(s, &ctx);
+        }
+    }
+});
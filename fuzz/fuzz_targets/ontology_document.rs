@@ -0,0 +1,22 @@
+#![no_main]
+
+use curie::PrefixMapping;
+use horned_functional::AxiomReader;
+use horned_functional::Backend;
+use horned_functional::Context;
+use horned_functional::FromFunctional;
+use horned_owl::ontology::set::SetOntology;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        for backend in [Backend::Pest, Backend::Descent] {
+            let ctx = Context::default().with_backend(backend);
+            let _ = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(s, &ctx);
+        }
+        let _ = horned_functional::from_str_lenient::<String, SetOntology<String>, _>(s);
+        if let Ok(reader) = AxiomReader::<String, _>::new(s.as_bytes()) {
+            reader.for_each(drop);
+        }
+    }
+});
@@ -3,11 +3,25 @@ use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
 
+use curie::Curie;
 use horned_owl::model::*;
 use horned_owl::ontology::axiom_mapped::AxiomMappedOntology;
 use horned_owl::ontology::indexed::ForIndex;
+use horned_owl::ontology::set::SetOntology;
 use horned_owl::vocab::WithIRI;
 
+use super::descent::split_pname;
+use super::dg::DGNode;
+use super::dg::DescriptionGraph;
+use super::dg::DescriptionGraphRule;
+use super::dg::EdgeAssertion;
+use super::dg::NodeAssertion;
+use super::from_pair::anonymous_node_id;
+use super::swrl::Atom;
+use super::swrl::DArg;
+use super::swrl::DLSafeRule;
+use super::swrl::IArg;
+use super::swrl::Variable;
 use super::Context;
 
 /// Write a string literal while escaping `"` and `\` characters.
@@ -26,6 +40,14 @@ fn quote(mut s: &str, f: &mut Formatter<'_>) -> Result<(), Error> {
     f.write_str("\"")
 }
 
+/// Write comments on their own lines.
+fn write_comments(comments: &[String], f: &mut Formatter<'_>) -> Result<(), Error> {
+    for comment in comments {
+        writeln!(f, "#{}", comment)?;
+    }
+    Ok(())
+}
+
 /// A trait for OWL elements that can be serialized to OWL Functional syntax.
 pub trait AsFunctional<A: ForIRI> {
     /// Get a handle for displaying the element in functional syntax.
@@ -48,7 +70,7 @@ pub trait AsFunctional<A: ForIRI> {
     /// );
     /// ```
     fn as_ofn<'t>(&'t self) -> Functional<'t, Self, A> {
-        Functional(&self, None, None)
+        Functional(self, None, None)
     }
 
     /// Get a handle for displaying the element, using the given context.
@@ -74,7 +96,7 @@ pub trait AsFunctional<A: ForIRI> {
     /// );
     /// ```
     fn as_ofn_ctx<'t>(&'t self, context: &'t Context<'t, A>) -> Functional<'t, Self, A> {
-        Functional(&self, Some(context), None)
+        Functional(self, Some(context), None)
     }
 }
 
@@ -86,9 +108,28 @@ pub struct Functional<'t, T: ?Sized, A: ForIRI>(
     // an eventual context to use (for IRI prefixes)
     Option<&'t Context<'t, A>>,
     // an eventual set of annotations (to render inside axioms)
-    Option<&'t BTreeSet<Annotation<A>>>,
+    Option<Scope<'t, A>>,
 );
 
+/// The annotations to write inside an element, with their position.
+#[derive(Debug)]
+struct Scope<'t, A: ForIRI> {
+    /// The annotations of the element.
+    annotations: &'t BTreeSet<Annotation<A>>,
+    /// The axiom containing the element.
+    axiom: &'t AnnotatedAxiom<A>,
+    /// The annotations leading from the axiom to the element.
+    path: &'t [Annotation<A>],
+}
+
+impl<'t, A: ForIRI> Clone for Scope<'t, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'t, A: ForIRI> Copy for Scope<'t, A> {}
+
 // ---------------------------------------------------------------------------
 
 macro_rules! derive_vec {
@@ -114,6 +155,11 @@ derive_vec!(A, ObjectPropertyExpression<A>);
 derive_vec!(A, FacetRestriction<A>);
 derive_vec!(A, Literal<A>);
 derive_vec!(A, DataProperty<A>);
+derive_vec!(A, Atom<A>);
+derive_vec!(A, DArg<A>);
+derive_vec!(A, NodeAssertion<A>);
+derive_vec!(A, EdgeAssertion<A>);
+derive_vec!(A, Class<A>);
 
 // ---------------------------------------------------------------------------
 
@@ -195,7 +241,7 @@ impl<'a, A: ForIRI> Display for Functional<'a, BTreeSet<Annotation<A>>, A> {
             if i != 0 {
                 f.write_str(" ")?;
             }
-            write!(f, "{}", Functional(x, self.1, None))?;
+            write!(f, "{}", Functional(x, self.1, self.2))?;
         }
         Ok(())
     }
@@ -247,7 +293,7 @@ macro_rules! derive_wrapper {
     ($A:ident, $ty:ty) => {
         impl<'a, $A: ForIRI> Display for Functional<'a, $ty, $A> {
             fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-                write!(f, "{}", Functional(&self.0 .0, self.1, None))
+                write!(f, "{}", Functional(&self.0 .0, self.1, self.2))
             }
         }
 
@@ -269,11 +315,11 @@ macro_rules! derive_axiom {
     ($A:ident, $ty:ty, $name:ident ( $($field:tt),* )) => {
         impl<'a, $A: ForIRI> Display for Functional<'a, $ty, $A> {
             fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-                if let Some(annotations) = self.2 {
+                if let Some(scope) = self.2.filter(|s| !s.annotations.is_empty()) {
                     write!(
                         f,
                         concat!(stringify!($name), "({} {})"),
-                        Functional(annotations, self.1, None),
+                        Functional(scope.annotations, self.1, Some(scope)),
                         Functional(&($(&self.0.$field,)*), self.1, None)
                     )
                 } else {
@@ -290,7 +336,39 @@ macro_rules! derive_axiom {
     };
 }
 
-derive_axiom!(A, Annotation<A>, Annotation(ap, av));
+impl<'a, A: ForIRI> Display for Functional<'a, Annotation<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        // nested annotations are looked up in the context by position
+        let table = self.1.and_then(|ctx| ctx.nested_annotations);
+        if let (Some(scope), Some(table)) = (self.2, table) {
+            let mut path = scope.path.to_vec();
+            path.push(self.0.clone());
+            if let Some(annotations) = table.get(scope.axiom, &path).filter(|a| !a.is_empty()) {
+                let scope = Scope {
+                    annotations,
+                    axiom: scope.axiom,
+                    path: &path,
+                };
+                return write!(
+                    f,
+                    "Annotation({} {} {})",
+                    Functional(annotations, self.1, Some(scope)),
+                    Functional(&self.0.ap, self.1, None),
+                    Functional(&self.0.av, self.1, None),
+                );
+            }
+        }
+        write!(
+            f,
+            "Annotation({} {})",
+            Functional(&self.0.ap, self.1, None),
+            Functional(&self.0.av, self.1, None),
+        )
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for Annotation<A> {}
+
 derive_axiom!(
     A,
     AnnotationPropertyRange<A>,
@@ -371,7 +449,12 @@ derive_axiom!(A, TransitiveObjectProperty<A>, TransitiveObjectProperty(0));
 
 impl<'a, A: ForIRI> Display for Functional<'a, AnnotatedAxiom<A>, A> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        Functional(&self.0.axiom, self.1, Some(&self.0.ann)).fmt(f)
+        let scope = Scope {
+            annotations: &self.0.ann,
+            axiom: self.0,
+            path: &[],
+        };
+        Functional(&self.0.axiom, self.1, Some(scope)).fmt(f)
     }
 }
 
@@ -412,6 +495,9 @@ impl<A: ForIRI> AsFunctional<A> for AnnotationSubject<A> {}
 impl<'a, A: ForIRI> Display for Functional<'a, AnnotationValue<A>, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         use AnnotationValue::*;
+        if let Some(id) = anonymous_node_id(self.0) {
+            return write!(f, "_:{}", id);
+        }
         match &self.0 {
             Literal(lit) => Functional(lit, self.1, None).fmt(f),
             IRI(iri) => Functional(iri, self.1, None).fmt(f),
@@ -425,7 +511,12 @@ impl<A: ForIRI> AsFunctional<A> for AnnotationValue<A> {}
 
 impl<'a, A: ForIRI> Display for Functional<'a, AnonymousIndividual<A>, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        self.0.fmt(f)
+        let id: &str = self.0.as_ref();
+        if id.starts_with("_:") {
+            f.write_str(id)
+        } else {
+            write!(f, "_:{}", id)
+        }
     }
 }
 
@@ -738,9 +829,18 @@ impl<A: ForIRI> AsFunctional<A> for HasKey<A> {}
 impl<'a, A: ForIRI> Display for Functional<'a, IRI<A>, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         if let Some(prefixes) = self.1.as_ref().and_then(|ctx| ctx.prefixes) {
-            match prefixes.shrink_iri(self.0) {
-                Ok(curie) => write!(f, "{}", curie),
-                Err(_) => write!(f, "<{}>", self.0),
+            let curie = match prefixes.shrink_iri(self.0) {
+                // `Curie` omits the colon when using the default prefix
+                Ok(curie) if curie == Curie::new(None, &curie.to_string()) => {
+                    format!(":{}", curie)
+                }
+                Ok(curie) => curie.to_string(),
+                Err(_) => return write!(f, "<{}>", self.0),
+            };
+            // only use the CURIE if it can be parsed back
+            match split_pname(&curie) {
+                Some(_) => f.write_str(&curie),
+                None => write!(f, "<{}>", self.0),
             }
         } else {
             write!(f, "<{}>", self.0)
@@ -769,16 +869,16 @@ impl<A: ForIRI> AsFunctional<A> for Individual<A> {}
 impl<'a, A: ForIRI> Display for Functional<'a, Literal<A>, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match self.0 {
-            Literal::Simple { literal } => quote(&literal, f),
+            Literal::Simple { literal } => quote(literal, f),
             Literal::Language { literal, lang } => {
-                quote(&literal, f)?;
+                quote(literal, f)?;
                 write!(f, "@{}", lang)
             }
             Literal::Datatype {
                 literal,
                 datatype_iri,
             } => {
-                quote(&literal, f)?;
+                quote(literal, f)?;
                 write!(f, "^^{}", Functional(datatype_iri, self.1, None))
             }
         }
@@ -825,6 +925,181 @@ impl<A: ForIRI> AsFunctional<A> for SubObjectPropertyExpression<A> {}
 
 // ---------------------------------------------------------------------------
 
+impl<'a, A: ForIRI> Display for Functional<'a, Variable<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "Variable({})", Functional(&self.0 .0, self.1, None))
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for Variable<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, IArg<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.0 {
+            IArg::Variable(v) => Functional(v, self.1, None).fmt(f),
+            IArg::Individual(i) => Functional(i, self.1, None).fmt(f),
+        }
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for IArg<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, DArg<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.0 {
+            DArg::Variable(v) => Functional(v, self.1, None).fmt(f),
+            DArg::Literal(l) => Functional(l, self.1, None).fmt(f),
+        }
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for DArg<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, Atom<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let ctx = self.1;
+        match self.0 {
+            Atom::ClassAtom { pred, arg } => write!(
+                f,
+                "ClassAtom({} {})",
+                Functional(pred, ctx, None),
+                Functional(arg, ctx, None)
+            ),
+            Atom::DataRangeAtom { pred, arg } => write!(
+                f,
+                "DataRangeAtom({} {})",
+                Functional(pred, ctx, None),
+                Functional(arg, ctx, None)
+            ),
+            Atom::ObjectPropertyAtom { pred, args } => write!(
+                f,
+                "ObjectPropertyAtom({} {} {})",
+                Functional(pred, ctx, None),
+                Functional(&args.0, ctx, None),
+                Functional(&args.1, ctx, None)
+            ),
+            Atom::DataPropertyAtom { pred, args } => write!(
+                f,
+                "DataPropertyAtom({} {} {})",
+                Functional(pred, ctx, None),
+                Functional(&args.0, ctx, None),
+                Functional(&args.1, ctx, None)
+            ),
+            Atom::BuiltInAtom { pred, args } => write!(
+                f,
+                "BuiltInAtom({} {})",
+                Functional(pred, ctx, None),
+                Functional(args, ctx, None)
+            ),
+            Atom::SameIndividualAtom(a, b) => write!(
+                f,
+                "SameIndividualAtom({} {})",
+                Functional(a, ctx, None),
+                Functional(b, ctx, None)
+            ),
+            Atom::DifferentIndividualsAtom(a, b) => write!(
+                f,
+                "DifferentIndividualsAtom({} {})",
+                Functional(a, ctx, None),
+                Functional(b, ctx, None)
+            ),
+        }
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for Atom<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, DLSafeRule<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str("DLSafeRule(")?;
+        if !self.0.ann.is_empty() {
+            write!(f, "{} ", Functional(&self.0.ann, self.1, None))?;
+        }
+        write!(
+            f,
+            "Body({}) Head({}))",
+            Functional(&self.0.body, self.1, None),
+            Functional(&self.0.head, self.1, None)
+        )
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for DLSafeRule<A> {}
+
+// ---------------------------------------------------------------------------
+
+impl<'a, A: ForIRI> Display for Functional<'a, DGNode<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        Functional(&self.0 .0, self.1, None).fmt(f)
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for DGNode<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, NodeAssertion<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "NodeAssertion({} {})",
+            Functional(&self.0.class, self.1, None),
+            Functional(&self.0.node, self.1, None)
+        )
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for NodeAssertion<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, EdgeAssertion<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "EdgeAssertion({} {} {})",
+            Functional(&self.0.property, self.1, None),
+            Functional(&self.0.from, self.1, None),
+            Functional(&self.0.to, self.1, None)
+        )
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for EdgeAssertion<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, DescriptionGraph<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str("DescriptionGraph(")?;
+        if !self.0.ann.is_empty() {
+            write!(f, "{} ", Functional(&self.0.ann, self.1, None))?;
+        }
+        write!(
+            f,
+            "Nodes({}) Edges({}) MainClasses({}))",
+            Functional(&self.0.nodes, self.1, None),
+            Functional(&self.0.edges, self.1, None),
+            Functional(&self.0.main_classes, self.1, None)
+        )
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for DescriptionGraph<A> {}
+
+impl<'a, A: ForIRI> Display for Functional<'a, DescriptionGraphRule<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.write_str("DescriptionGraphRule(")?;
+        if !self.0.ann.is_empty() {
+            write!(f, "{} ", Functional(&self.0.ann, self.1, None))?;
+        }
+        write!(
+            f,
+            "Body({}) Head({}))",
+            Functional(&self.0.body, self.1, None),
+            Functional(&self.0.head, self.1, None)
+        )
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for DescriptionGraphRule<A> {}
+
+// ---------------------------------------------------------------------------
+
 impl<'a, A: ForIRI> Display for Functional<'a, curie::PrefixMapping, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         for (name, value) in self.0.mappings() {
@@ -838,46 +1113,102 @@ impl<A: ForIRI> AsFunctional<A> for curie::PrefixMapping {}
 
 // ---------------------------------------------------------------------------
 
+/// Write an ontology from its ID and its axioms.
+///
+/// The imports and ontology annotations are written first, then the other
+/// axioms in the order they are iterated by `axioms`.
+fn write_ontology<'x, A, F, I>(
+    id: &OntologyID<A>,
+    axioms: F,
+    ctx: Option<&Context<'_, A>>,
+    f: &mut Formatter<'_>,
+) -> Result<(), Error>
+where
+    A: ForIRI + 'x,
+    F: Fn() -> I,
+    I: Iterator<Item = &'x AnnotatedAxiom<A>>,
+{
+    let comments = ctx.and_then(|ctx| ctx.comments);
+    let write_axiom = |axiom: &AnnotatedAxiom<A>, f: &mut Formatter<'_>| {
+        if let Some(c) = comments.and_then(|c| c.axioms.get(axiom)) {
+            write_comments(c, f)?;
+        }
+        writeln!(f, "{}", Functional(axiom, ctx, None))
+    };
+    // write the comments of the header before the Ontology element
+    if let Some(c) = comments {
+        write_comments(&c.header, f)?;
+    }
+    // open the Ontology element
+    f.write_str("Ontology(")?;
+    // write the IRI and Version IRI if any
+    if let Some(iri) = &id.iri {
+        write!(f, "{}", Functional(iri, ctx, None))?;
+        if let Some(viri) = &id.viri {
+            writeln!(f, " {}", Functional(viri, ctx, None))?;
+        } else {
+            writeln!(f)?;
+        }
+    }
+    // write imports first
+    for axiom in axioms().filter(|a| a.kind() == AxiomKind::Import) {
+        write_axiom(axiom, f)?;
+    }
+    // then write ontology annotations
+    for axiom in axioms().filter(|a| a.kind() == AxiomKind::OntologyAnnotation) {
+        write_axiom(axiom, f)?;
+    }
+    // then write the rest
+    for axiom in axioms() {
+        let kind = axiom.kind();
+        if kind != AxiomKind::OntologyAnnotation && kind != AxiomKind::Import {
+            write_axiom(axiom, f)?;
+        }
+    }
+    // then write the SWRL rules, which are not stored in the ontology
+    for rule in ctx.and_then(|ctx| ctx.rules).unwrap_or_default() {
+        writeln!(f, "{}", Functional(rule, ctx, None))?;
+    }
+    // and the description graphs, which are not stored either
+    if let Some(graphs) = ctx.and_then(|ctx| ctx.description_graphs) {
+        for graph in &graphs.graphs {
+            writeln!(f, "{}", Functional(graph, ctx, None))?;
+        }
+        for rule in &graphs.rules {
+            writeln!(f, "{}", Functional(rule, ctx, None))?;
+        }
+    }
+    if let Some(c) = comments {
+        write_comments(&c.trailer, f)?;
+    }
+    // and close the Ontology element
+    f.write_str(")")
+}
+
 impl<'a, A: ForIRI, AA: ForIndex<A>> Display for Functional<'a, AxiomMappedOntology<A, AA>, A> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        // open the Ontology element
-        f.write_str("Ontology(")?;
-        // write the IRI and Version IRI if any
-        let id = self.0.id();
-        if let Some(iri) = &id.iri {
-            write!(f, "{}", Functional(iri, self.1, None))?;
-            if let Some(viri) = &id.viri {
-                writeln!(f, " {}", Functional(viri, self.1, None))?;
-            } else {
-                writeln!(f)?;
-            }
-        }
-        // write imports first
-        for axiom in self.0.i().import() {
-            writeln!(f, "{}", Functional(axiom, self.1, None))?;
-        }
-        // then write ontology annotations
-        for axiom in self.0.i().ontology_annotation() {
-            writeln!(f, "{}", Functional(axiom, self.1, None))?;
-        }
-        // then write the rest
-        for axiom in self.0.i().iter() {
-            let kind = axiom.axiom.kind();
-            if kind != AxiomKind::OntologyAnnotation && kind != AxiomKind::Import {
-                writeln!(f, "{}", Functional(axiom, self.1, None))?;
-            }
-        }
-        // and close the Ontology element
-        f.write_str(")")
+        write_ontology(self.0.id(), || self.0.i().iter(), self.1, f)
     }
 }
 
 impl<A: ForIRI, AA: ForIndex<A>> AsFunctional<A> for AxiomMappedOntology<A, AA> {}
 
+impl<'a, A: ForIRI> Display for Functional<'a, SetOntology<A>, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        // sort the axioms so that the output does not depend on hashing
+        let mut axioms = self.0.iter().collect::<Vec<_>>();
+        axioms.sort_unstable();
+        write_ontology(self.0.id(), || axioms.iter().copied(), self.1, f)
+    }
+}
+
+impl<A: ForIRI> AsFunctional<A> for SetOntology<A> {}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::FromFunctional;
 
     #[test]
     fn test_ofn_declareclass() {
@@ -890,6 +1221,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ofn_anonymous() {
+        let build = Build::<String>::new();
+        let assertion = ClassAssertion {
+            ce: build.class("http://example.com/A").into(),
+            i: build.anon("b0").into(),
+        };
+        assert_eq!(
+            assertion.as_ofn().to_string(),
+            "ClassAssertion(<http://example.com/A> _:b0)"
+        );
+
+        let doc = "AnnotationAssertion(<http://example.com/p> <http://example.com/s> _:b0)";
+        let axiom = Axiom::<String>::from_ofn(doc).unwrap();
+        assert_eq!(axiom.as_ofn().to_string(), doc);
+
+        // IRIs looking like node IDs are not anonymous annotation values
+        let annotation = Annotation {
+            ap: build.annotation_property("http://example.com/p"),
+            av: build.iri("_:b0").into(),
+        };
+        assert_eq!(
+            annotation.as_ofn().to_string(),
+            "Annotation(<http://example.com/p> <_:b0>)"
+        );
+    }
+
     #[test]
     fn test_ofn_literal_simple() {
         let lit = Literal::<String>::Simple {
@@ -0,0 +1,2014 @@
+//! A typed syntax tree mirroring the productions of the OWL2 functional grammar.
+//!
+//! Converting a document into the `horned-owl` model loses some facts about
+//! its syntax: annotations are sorted and deduplicated, nested annotations
+//! are dropped, and optional arguments such as the class expression of a
+//! cardinality restriction are replaced with their default value. The nodes
+//! of this module keep the elements as they were written, in source order,
+//! with abbreviated IRIs left unexpanded.
+//!
+//! Use [`Node::from_ofn`] to parse a node, and [`Lower::lower`] to convert
+//! it into the `horned-owl` model.
+//!
+//! # Example
+//! ```rust
+//! # use horned_owl::model::Build;
+//! use horned_functional::ast::*;
+//!
+//! let axiom = Axiom::from_ofn(
+//!     "SubClassOf(Annotation(Annotation(:source \"x\") :comment \"y\") :A :A)"
+//! ).unwrap();
+//! if let Axiom::SubClassOf(annotations, _, _) = &axiom {
+//!     assert_eq!(annotations[0].annotations.len(), 1);
+//! }
+//! ```
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use curie::PrefixMapping;
+use horned_owl::model as owl;
+use horned_owl::model::ForIRI;
+use horned_owl::ontology::set::SetOntology;
+use horned_owl::vocab::OWL2Datatype;
+use horned_owl::vocab::WithIRI;
+use horned_owl::vocab::OWL;
+use pest::iterators::Pair;
+use pest::iterators::Pairs;
+
+use crate::descent::split_pname;
+use crate::dg;
+use crate::error::Error;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::from_pair::anonymous_value;
+use crate::from_pair::expand_curie_at;
+use crate::from_pair::facet;
+use crate::from_pair::unquote;
+use crate::limits::Limits;
+use crate::parser::Rule;
+use crate::sink::OntologySink;
+use crate::swrl;
+use crate::warning::check_kept;
+use crate::warning::SeenAxioms;
+use crate::Context;
+use crate::WarningKind;
+
+/// A node of the syntax tree, for a production of the OWL2 grammar.
+pub trait Node: Sized {
+    /// The production rule of the node.
+    const RULE: Rule;
+
+    /// Parse a node from a string in OWL functional syntax.
+    fn from_ofn(s: &str) -> Result<Self> {
+        Self::from_ofn_limits(s, &Limits::default())
+    }
+
+    /// Parse a node from a string, with the given resource limits.
+    fn from_ofn_limits(s: &str, limits: &Limits) -> Result<Self> {
+        parse_exact(Self::RULE, s, limits).and_then(Self::from_pair)
+    }
+
+    /// Create a node from a `Pair` for its production rule.
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>;
+}
+
+/// A node of the syntax tree that can be converted into the `horned-owl` model.
+pub trait Lower<A: ForIRI> {
+    /// The `horned-owl` type of the converted node.
+    type Target;
+
+    /// Convert the node, expanding abbreviated IRIs with the context prefixes.
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target>;
+}
+
+/// The children of a pair, converted one at a time.
+struct Children<'i>(Pairs<'i, Rule>);
+
+impl<'i> Children<'i> {
+    fn new(pair: Pair<'i, Rule>) -> Self {
+        Children(pair.into_inner())
+    }
+
+    fn pair(&mut self) -> Pair<'i, Rule> {
+        self.0.next().unwrap()
+    }
+
+    fn next<T: Node>(&mut self) -> Result<T> {
+        T::from_pair(self.pair())
+    }
+
+    /// Convert the child of the next child, for rules wrapping another rule.
+    fn next_inner<T: Node>(&mut self) -> Result<T> {
+        T::from_pair(self.pair().into_inner().next().unwrap())
+    }
+
+    fn optional<T: Node>(&mut self) -> Result<Option<T>> {
+        self.0.next().map(T::from_pair).transpose()
+    }
+
+    fn rest<T: Node>(self) -> Result<Vec<T>> {
+        self.0.map(T::from_pair).collect()
+    }
+}
+
+fn lower_all<A: ForIRI, T: Lower<A>, C: FromIterator<T::Target>>(
+    nodes: &[T],
+    ctx: &Context<'_, A>,
+) -> Result<C> {
+    nodes.iter().map(|node| node.lower(ctx)).collect()
+}
+
+// --- IRIs and entities -----------------------------------------------------
+
+/// An IRI, as written in the document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IRI {
+    /// A full IRI, without its angle brackets.
+    Full(String),
+    /// An abbreviated IRI, such as `obo:BFO_0000001`.
+    Abbreviated(String),
+}
+
+impl Node for IRI {
+    const RULE: Rule = Rule::IRI;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::FullIRI => Ok(IRI::Full(
+                inner.into_inner().next().unwrap().as_str().to_string(),
+            )),
+            Rule::AbbreviatedIRI => Ok(IRI::Abbreviated(inner.as_str().to_string())),
+            rule => unreachable!("unexpected rule in IRI::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for IRI {
+    type Target = owl::IRI<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            IRI::Full(iri) => Ok(ctx.iri(iri.as_str())),
+            IRI::Abbreviated(curie) => match split_pname(curie) {
+                Some((prefix, local)) => {
+                    expand_curie_at(prefix, local, curie, || None, ctx).map(|iri| ctx.iri(iri))
+                }
+                None => Err(Error::Unsupported(format!(
+                    "invalid abbreviated IRI `{}`",
+                    curie
+                ))),
+            },
+        }
+    }
+}
+
+macro_rules! entity {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(pub IRI);
+
+        impl Node for $name {
+            const RULE: Rule = Rule::$name;
+            fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+                Children::new(pair).next().map($name)
+            }
+        }
+
+        impl<A: ForIRI> Lower<A> for $name {
+            type Target = owl::$name<A>;
+            fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+                self.0.lower(ctx).map(owl::$name)
+            }
+        }
+    };
+}
+
+entity!(
+    /// A class.
+    Class
+);
+entity!(
+    /// A datatype.
+    Datatype
+);
+entity!(
+    /// An object property.
+    ObjectProperty
+);
+entity!(
+    /// A data property.
+    DataProperty
+);
+entity!(
+    /// An annotation property.
+    AnnotationProperty
+);
+entity!(
+    /// A named individual.
+    NamedIndividual
+);
+
+/// An anonymous individual, with its node ID such as `_:b0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AnonymousIndividual(pub String);
+
+impl Node for AnonymousIndividual {
+    const RULE: Rule = Rule::AnonymousIndividual;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let nodeid = pair.into_inner().next().unwrap();
+        Ok(AnonymousIndividual(nodeid.as_str().to_string()))
+    }
+}
+
+impl<A: ForIRI> Lower<A> for AnonymousIndividual {
+    type Target = owl::AnonymousIndividual<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        let label = self.0.strip_prefix("_:").unwrap_or(&self.0);
+        Ok(owl::AnonymousIndividual(ctx.iri(label).underlying()))
+    }
+}
+
+/// An individual.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Individual {
+    /// A named individual.
+    Named(NamedIndividual),
+    /// An anonymous individual.
+    Anonymous(AnonymousIndividual),
+}
+
+impl Node for Individual {
+    const RULE: Rule = Rule::Individual;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::NamedIndividual => Node::from_pair(inner).map(Individual::Named),
+            Rule::AnonymousIndividual => Node::from_pair(inner).map(Individual::Anonymous),
+            rule => unreachable!("unexpected rule in Individual::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Individual {
+    type Target = owl::Individual<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            Individual::Named(i) => i.lower(ctx).map(owl::Individual::Named),
+            Individual::Anonymous(i) => i.lower(ctx).map(owl::Individual::Anonymous),
+        }
+    }
+}
+
+/// The entity of a declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Entity {
+    /// A class declaration.
+    Class(Class),
+    /// A datatype declaration.
+    Datatype(Datatype),
+    /// An object property declaration.
+    ObjectProperty(ObjectProperty),
+    /// A data property declaration.
+    DataProperty(DataProperty),
+    /// An annotation property declaration.
+    AnnotationProperty(AnnotationProperty),
+    /// A named individual declaration.
+    NamedIndividual(NamedIndividual),
+}
+
+impl Node for Entity {
+    const RULE: Rule = Rule::Entity;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        let mut children = Children::new(inner);
+        match rule {
+            Rule::ClassDeclaration => children.next().map(Entity::Class),
+            Rule::DatatypeDeclaration => children.next().map(Entity::Datatype),
+            Rule::ObjectPropertyDeclaration => children.next().map(Entity::ObjectProperty),
+            Rule::DataPropertyDeclaration => children.next().map(Entity::DataProperty),
+            Rule::AnnotationPropertyDeclaration => children.next().map(Entity::AnnotationProperty),
+            Rule::NamedIndividualDeclaration => children.next().map(Entity::NamedIndividual),
+            rule => unreachable!("unexpected rule in Entity::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Entity {
+    type Target = owl::Axiom<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(match self {
+            Entity::Class(e) => owl::DeclareClass(e.lower(ctx)?).into(),
+            Entity::Datatype(e) => owl::DeclareDatatype(e.lower(ctx)?).into(),
+            Entity::ObjectProperty(e) => owl::DeclareObjectProperty(e.lower(ctx)?).into(),
+            Entity::DataProperty(e) => owl::DeclareDataProperty(e.lower(ctx)?).into(),
+            Entity::AnnotationProperty(e) => owl::DeclareAnnotationProperty(e.lower(ctx)?).into(),
+            Entity::NamedIndividual(e) => owl::DeclareNamedIndividual(e.lower(ctx)?).into(),
+        })
+    }
+}
+
+// --- Literals ----------------------------------------------------------------
+
+impl Node for u32 {
+    const RULE: Rule = Rule::NonNegativeInteger;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        u32::from_str(pair.as_str())
+            .map_err(|_| Error::custom("integer is too large", pair.as_span()))
+    }
+}
+
+impl Node for String {
+    const RULE: Rule = Rule::QuotedString;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        Ok(unquote(pair.as_str()))
+    }
+}
+
+/// A literal, with the escape sequences of its value resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Literal {
+    /// A literal with a datatype, such as `"1"^^xsd:integer`.
+    Typed(String, Datatype),
+    /// A string literal with a language tag, such as `"chat"@fr`.
+    WithLanguage(String, String),
+    /// A string literal without a language tag.
+    NoLanguage(String),
+}
+
+impl Node for Literal {
+    const RULE: Rule = Rule::Literal;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        let mut children = Children::new(inner);
+        match rule {
+            Rule::TypedLiteral => Ok(Literal::Typed(children.next()?, children.next()?)),
+            Rule::StringLiteralWithLanguage => {
+                let value = children.next()?;
+                let lang = children.pair().as_str()[1..].trim().to_string();
+                Ok(Literal::WithLanguage(value, lang))
+            }
+            Rule::StringLiteralNoLanguage => children.next().map(Literal::NoLanguage),
+            rule => unreachable!("unexpected rule in Literal::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Literal {
+    type Target = owl::Literal<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(match self {
+            Literal::Typed(literal, datatype) => owl::Literal::Datatype {
+                literal: literal.clone(),
+                datatype_iri: datatype.0.lower(ctx)?,
+            },
+            Literal::WithLanguage(literal, lang) => owl::Literal::Language {
+                literal: literal.clone(),
+                lang: lang.clone(),
+            },
+            Literal::NoLanguage(literal) => owl::Literal::Simple {
+                literal: literal.clone(),
+            },
+        })
+    }
+}
+
+// --- Annotations -------------------------------------------------------------
+
+/// An annotation, with its own annotations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Annotation {
+    /// The annotations of the annotation, in source order.
+    pub annotations: Vec<Annotation>,
+    /// The annotation property.
+    pub property: AnnotationProperty,
+    /// The annotation value.
+    pub value: AnnotationValue,
+}
+
+impl Node for Annotation {
+    const RULE: Rule = Rule::Annotation;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(Annotation {
+            annotations: children.next()?,
+            property: children.next()?,
+            value: children.next()?,
+        })
+    }
+}
+
+impl Node for Vec<Annotation> {
+    const RULE: Rule = Rule::Annotations;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        Children::new(pair).rest()
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Annotation {
+    type Target = owl::Annotation<A>;
+    /// Convert the annotation, dropping its own annotations with a warning.
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        if !self.annotations.is_empty() {
+            ctx.warn(
+                WarningKind::DroppedAnnotation,
+                || String::from("annotations of an annotation are not kept"),
+                || None,
+            );
+        }
+        Ok(owl::Annotation {
+            ap: self.property.lower(ctx)?,
+            av: self.value.lower(ctx)?,
+        })
+    }
+}
+
+/// The subject of an annotation assertion.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AnnotationSubject {
+    /// An IRI.
+    IRI(IRI),
+    /// An anonymous individual.
+    AnonymousIndividual(AnonymousIndividual),
+}
+
+impl Node for AnnotationSubject {
+    const RULE: Rule = Rule::AnnotationSubject;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::IRI => Node::from_pair(inner).map(AnnotationSubject::IRI),
+            Rule::AnonymousIndividual => {
+                Node::from_pair(inner).map(AnnotationSubject::AnonymousIndividual)
+            }
+            rule => unreachable!(
+                "unexpected rule in AnnotationSubject::from_pair: {:?}",
+                rule
+            ),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for AnnotationSubject {
+    type Target = owl::AnnotationSubject<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            AnnotationSubject::IRI(iri) => iri.lower(ctx).map(owl::AnnotationSubject::IRI),
+            AnnotationSubject::AnonymousIndividual(i) => i
+                .lower(ctx)
+                .map(owl::AnnotationSubject::AnonymousIndividual),
+        }
+    }
+}
+
+/// The value of an annotation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AnnotationValue {
+    /// An anonymous individual.
+    AnonymousIndividual(AnonymousIndividual),
+    /// An IRI.
+    IRI(IRI),
+    /// A literal.
+    Literal(Literal),
+}
+
+impl Node for AnnotationValue {
+    const RULE: Rule = Rule::AnnotationValue;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::AnonymousIndividual => {
+                Node::from_pair(inner).map(AnnotationValue::AnonymousIndividual)
+            }
+            Rule::IRI => Node::from_pair(inner).map(AnnotationValue::IRI),
+            Rule::Literal => Node::from_pair(inner).map(AnnotationValue::Literal),
+            rule => unreachable!("unexpected rule in AnnotationValue::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for AnnotationValue {
+    type Target = owl::AnnotationValue<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            AnnotationValue::AnonymousIndividual(_) if ctx.strict => Err(Error::Unsupported(
+                "anonymous annotation targets are not supported".to_string(),
+            )),
+            AnnotationValue::AnonymousIndividual(i) => {
+                i.lower(ctx).map(|anon| anonymous_value(&anon, ctx))
+            }
+            AnnotationValue::IRI(iri) => iri.lower(ctx).map(owl::AnnotationValue::IRI),
+            AnnotationValue::Literal(l) => l.lower(ctx).map(owl::AnnotationValue::Literal),
+        }
+    }
+}
+
+// --- Property expressions and data ranges ------------------------------------
+
+/// An object property expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectPropertyExpression {
+    /// An object property.
+    ObjectProperty(ObjectProperty),
+    /// An `ObjectInverseOf` expression.
+    InverseObjectProperty(ObjectProperty),
+}
+
+impl Node for ObjectPropertyExpression {
+    const RULE: Rule = Rule::ObjectPropertyExpression;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::ObjectProperty => {
+                Node::from_pair(inner).map(ObjectPropertyExpression::ObjectProperty)
+            }
+            Rule::InverseObjectProperty => Children::new(inner)
+                .next()
+                .map(ObjectPropertyExpression::InverseObjectProperty),
+            rule => unreachable!(
+                "unexpected rule in ObjectPropertyExpression::from_pair: {:?}",
+                rule
+            ),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for ObjectPropertyExpression {
+    type Target = owl::ObjectPropertyExpression<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            ObjectPropertyExpression::ObjectProperty(op) => op
+                .lower(ctx)
+                .map(owl::ObjectPropertyExpression::ObjectProperty),
+            ObjectPropertyExpression::InverseObjectProperty(op) => op
+                .lower(ctx)
+                .map(owl::ObjectPropertyExpression::InverseObjectProperty),
+        }
+    }
+}
+
+/// The subproperty of a `SubObjectPropertyOf` axiom.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SubObjectPropertyExpression {
+    /// An object property expression.
+    ObjectPropertyExpression(ObjectPropertyExpression),
+    /// An `ObjectPropertyChain` expression.
+    PropertyExpressionChain(Vec<ObjectPropertyExpression>),
+}
+
+impl Node for SubObjectPropertyExpression {
+    const RULE: Rule = Rule::SubObjectPropertyExpression;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::ObjectPropertyExpression => {
+                Node::from_pair(inner).map(SubObjectPropertyExpression::ObjectPropertyExpression)
+            }
+            Rule::PropertyExpressionChain => Children::new(inner)
+                .rest()
+                .map(SubObjectPropertyExpression::PropertyExpressionChain),
+            rule => unreachable!(
+                "unexpected rule in SubObjectPropertyExpression::from_pair: {:?}",
+                rule
+            ),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for SubObjectPropertyExpression {
+    type Target = owl::SubObjectPropertyExpression<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            SubObjectPropertyExpression::ObjectPropertyExpression(ope) => ope
+                .lower(ctx)
+                .map(owl::SubObjectPropertyExpression::ObjectPropertyExpression),
+            SubObjectPropertyExpression::PropertyExpressionChain(chain) => {
+                lower_all(chain, ctx).map(owl::SubObjectPropertyExpression::ObjectPropertyChain)
+            }
+        }
+    }
+}
+
+/// A data range.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DataRange {
+    /// A datatype.
+    Datatype(Datatype),
+    /// A `DataIntersectionOf` data range.
+    DataIntersectionOf(Vec<DataRange>),
+    /// A `DataUnionOf` data range.
+    DataUnionOf(Vec<DataRange>),
+    /// A `DataComplementOf` data range.
+    DataComplementOf(Box<DataRange>),
+    /// A `DataOneOf` data range.
+    DataOneOf(Vec<Literal>),
+    /// A `DatatypeRestriction`, with its facet restrictions.
+    DatatypeRestriction(Datatype, Vec<FacetRestriction>),
+}
+
+impl Node for DataRange {
+    const RULE: Rule = Rule::DataRange;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        if rule == Rule::Datatype {
+            return Node::from_pair(inner).map(DataRange::Datatype);
+        }
+        let mut children = Children::new(inner);
+        match rule {
+            Rule::DataIntersectionOf => children.rest().map(DataRange::DataIntersectionOf),
+            Rule::DataUnionOf => children.rest().map(DataRange::DataUnionOf),
+            Rule::DataComplementOf => children
+                .next()
+                .map(Box::new)
+                .map(DataRange::DataComplementOf),
+            Rule::DataOneOf => children.rest().map(DataRange::DataOneOf),
+            Rule::DatatypeRestriction => Ok(DataRange::DatatypeRestriction(
+                children.next()?,
+                children.rest()?,
+            )),
+            rule => unreachable!("unexpected rule in DataRange::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for DataRange {
+    type Target = owl::DataRange<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(match self {
+            DataRange::Datatype(dt) => owl::DataRange::Datatype(dt.lower(ctx)?),
+            DataRange::DataIntersectionOf(drs) => {
+                owl::DataRange::DataIntersectionOf(lower_all(drs, ctx)?)
+            }
+            DataRange::DataUnionOf(drs) => owl::DataRange::DataUnionOf(lower_all(drs, ctx)?),
+            DataRange::DataComplementOf(dr) => {
+                owl::DataRange::DataComplementOf(Box::new(dr.lower(ctx)?))
+            }
+            DataRange::DataOneOf(literals) => owl::DataRange::DataOneOf(lower_all(literals, ctx)?),
+            DataRange::DatatypeRestriction(dt, frs) => {
+                owl::DataRange::DatatypeRestriction(dt.lower(ctx)?, lower_all(frs, ctx)?)
+            }
+        })
+    }
+}
+
+/// A facet restriction of a datatype restriction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FacetRestriction {
+    /// The IRI of the constraining facet.
+    pub facet: IRI,
+    /// The restricting value of the facet.
+    pub literal: Literal,
+}
+
+impl Node for FacetRestriction {
+    const RULE: Rule = Rule::FacetRestriction;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(FacetRestriction {
+            facet: children.next_inner()?,
+            literal: children.next()?,
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for FacetRestriction {
+    type Target = owl::FacetRestriction<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(owl::FacetRestriction {
+            f: facet(&self.facet.lower(ctx)?, || None)?,
+            l: self.literal.lower(ctx)?,
+        })
+    }
+}
+
+// --- Class expressions -------------------------------------------------------
+
+/// A class expression.
+///
+/// The optional class expression or data range of a cardinality restriction
+/// is `None` when it was omitted in the document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ClassExpression {
+    /// A named class.
+    Class(Class),
+    /// An `ObjectIntersectionOf` expression.
+    ObjectIntersectionOf(Vec<ClassExpression>),
+    /// An `ObjectUnionOf` expression.
+    ObjectUnionOf(Vec<ClassExpression>),
+    /// An `ObjectComplementOf` expression.
+    ObjectComplementOf(Box<ClassExpression>),
+    /// An `ObjectOneOf` expression.
+    ObjectOneOf(Vec<Individual>),
+    /// An `ObjectSomeValuesFrom` expression.
+    ObjectSomeValuesFrom(ObjectPropertyExpression, Box<ClassExpression>),
+    /// An `ObjectAllValuesFrom` expression.
+    ObjectAllValuesFrom(ObjectPropertyExpression, Box<ClassExpression>),
+    /// An `ObjectHasValue` expression.
+    ObjectHasValue(ObjectPropertyExpression, Individual),
+    /// An `ObjectHasSelf` expression.
+    ObjectHasSelf(ObjectPropertyExpression),
+    /// An `ObjectMinCardinality` restriction.
+    ObjectMinCardinality(u32, ObjectPropertyExpression, Option<Box<ClassExpression>>),
+    /// An `ObjectMaxCardinality` restriction.
+    ObjectMaxCardinality(u32, ObjectPropertyExpression, Option<Box<ClassExpression>>),
+    /// An `ObjectExactCardinality` restriction.
+    ObjectExactCardinality(u32, ObjectPropertyExpression, Option<Box<ClassExpression>>),
+    /// A `DataSomeValuesFrom` expression.
+    DataSomeValuesFrom(Vec<DataProperty>, DataRange),
+    /// A `DataAllValuesFrom` expression.
+    DataAllValuesFrom(Vec<DataProperty>, DataRange),
+    /// A `DataHasValue` expression.
+    DataHasValue(DataProperty, Literal),
+    /// A `DataMinCardinality` restriction.
+    DataMinCardinality(u32, DataProperty, Option<DataRange>),
+    /// A `DataMaxCardinality` restriction.
+    DataMaxCardinality(u32, DataProperty, Option<DataRange>),
+    /// A `DataExactCardinality` restriction.
+    DataExactCardinality(u32, DataProperty, Option<DataRange>),
+}
+
+impl Node for ClassExpression {
+    const RULE: Rule = Rule::ClassExpression;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        use ClassExpression::*;
+
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        if rule == Rule::Class {
+            return Node::from_pair(inner).map(Class);
+        }
+
+        let mut c = Children::new(inner);
+        Ok(match rule {
+            Rule::ObjectIntersectionOf => ObjectIntersectionOf(c.rest()?),
+            Rule::ObjectUnionOf => ObjectUnionOf(c.rest()?),
+            Rule::ObjectComplementOf => ObjectComplementOf(Box::new(c.next()?)),
+            Rule::ObjectOneOf => ObjectOneOf(c.rest()?),
+            Rule::ObjectSomeValuesFrom => ObjectSomeValuesFrom(c.next()?, Box::new(c.next()?)),
+            Rule::ObjectAllValuesFrom => ObjectAllValuesFrom(c.next()?, Box::new(c.next()?)),
+            Rule::ObjectHasValue => ObjectHasValue(c.next()?, c.next()?),
+            Rule::ObjectHasSelf => ObjectHasSelf(c.next()?),
+            Rule::ObjectMinCardinality => {
+                ObjectMinCardinality(c.next()?, c.next()?, c.optional()?.map(Box::new))
+            }
+            Rule::ObjectMaxCardinality => {
+                ObjectMaxCardinality(c.next()?, c.next()?, c.optional()?.map(Box::new))
+            }
+            Rule::ObjectExactCardinality => {
+                ObjectExactCardinality(c.next()?, c.next()?, c.optional()?.map(Box::new))
+            }
+            Rule::DataSomeValuesFrom | Rule::DataAllValuesFrom => {
+                let mut pairs = c.0.collect::<Vec<_>>();
+                let dr = DataRange::from_pair(pairs.pop().unwrap())?;
+                let dps = pairs
+                    .into_iter()
+                    .map(DataProperty::from_pair)
+                    .collect::<Result<_>>()?;
+                match rule {
+                    Rule::DataSomeValuesFrom => DataSomeValuesFrom(dps, dr),
+                    _ => DataAllValuesFrom(dps, dr),
+                }
+            }
+            Rule::DataHasValue => DataHasValue(c.next()?, c.next()?),
+            Rule::DataMinCardinality => DataMinCardinality(c.next()?, c.next()?, c.optional()?),
+            Rule::DataMaxCardinality => DataMaxCardinality(c.next()?, c.next()?, c.optional()?),
+            Rule::DataExactCardinality => DataExactCardinality(c.next()?, c.next()?, c.optional()?),
+            rule => unreachable!("unexpected rule in ClassExpression::from_pair: {:?}", rule),
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for ClassExpression {
+    type Target = owl::ClassExpression<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        use owl::ClassExpression as CE;
+
+        // missing class expressions and data ranges of cardinality restrictions
+        // default to `owl:Thing` and `rdfs:Literal`
+        let bce = |ce: &Option<Box<ClassExpression>>| -> Result<Box<CE<A>>> {
+            match ce {
+                Some(ce) => ce.lower(ctx).map(Box::new),
+                None => Ok(Box::new(CE::Class(owl::Class(
+                    ctx.iri(OWL::Thing.iri_str()),
+                )))),
+            }
+        };
+        let dr = |dr: &Option<DataRange>| -> Result<owl::DataRange<A>> {
+            match dr {
+                Some(dr) => dr.lower(ctx),
+                None => Ok(owl::Datatype(ctx.iri(OWL2Datatype::RDFSLiteral.iri_str())).into()),
+            }
+        };
+        let dp = |name: &str, dps: &[DataProperty]| -> Result<owl::DataProperty<A>> {
+            match dps {
+                [dp] => dp.lower(ctx),
+                _ => Err(Error::Unsupported(format!(
+                    "cannot use data property chaining in `{}`",
+                    name
+                ))),
+            }
+        };
+
+        Ok(match self {
+            ClassExpression::Class(c) => CE::Class(c.lower(ctx)?),
+            ClassExpression::ObjectIntersectionOf(ces) => {
+                CE::ObjectIntersectionOf(lower_all(ces, ctx)?)
+            }
+            ClassExpression::ObjectUnionOf(ces) => CE::ObjectUnionOf(lower_all(ces, ctx)?),
+            ClassExpression::ObjectComplementOf(ce) => {
+                CE::ObjectComplementOf(Box::new(ce.lower(ctx)?))
+            }
+            ClassExpression::ObjectOneOf(is) => CE::ObjectOneOf(lower_all(is, ctx)?),
+            ClassExpression::ObjectSomeValuesFrom(ope, ce) => CE::ObjectSomeValuesFrom {
+                ope: ope.lower(ctx)?,
+                bce: Box::new(ce.lower(ctx)?),
+            },
+            ClassExpression::ObjectAllValuesFrom(ope, ce) => CE::ObjectAllValuesFrom {
+                ope: ope.lower(ctx)?,
+                bce: Box::new(ce.lower(ctx)?),
+            },
+            ClassExpression::ObjectHasValue(ope, i) => CE::ObjectHasValue {
+                ope: ope.lower(ctx)?,
+                i: i.lower(ctx)?,
+            },
+            ClassExpression::ObjectHasSelf(ope) => CE::ObjectHasSelf(ope.lower(ctx)?),
+            ClassExpression::ObjectMinCardinality(n, ope, ce) => CE::ObjectMinCardinality {
+                n: *n,
+                ope: ope.lower(ctx)?,
+                bce: bce(ce)?,
+            },
+            ClassExpression::ObjectMaxCardinality(n, ope, ce) => CE::ObjectMaxCardinality {
+                n: *n,
+                ope: ope.lower(ctx)?,
+                bce: bce(ce)?,
+            },
+            ClassExpression::ObjectExactCardinality(n, ope, ce) => CE::ObjectExactCardinality {
+                n: *n,
+                ope: ope.lower(ctx)?,
+                bce: bce(ce)?,
+            },
+            ClassExpression::DataSomeValuesFrom(dps, r) => CE::DataSomeValuesFrom {
+                dp: dp("DataSomeValuesFrom", dps)?,
+                dr: r.lower(ctx)?,
+            },
+            ClassExpression::DataAllValuesFrom(dps, r) => CE::DataAllValuesFrom {
+                dp: dp("DataAllValuesFrom", dps)?,
+                dr: r.lower(ctx)?,
+            },
+            ClassExpression::DataHasValue(p, l) => CE::DataHasValue {
+                dp: p.lower(ctx)?,
+                l: l.lower(ctx)?,
+            },
+            ClassExpression::DataMinCardinality(n, p, r) => CE::DataMinCardinality {
+                n: *n,
+                dp: p.lower(ctx)?,
+                dr: dr(r)?,
+            },
+            ClassExpression::DataMaxCardinality(n, p, r) => CE::DataMaxCardinality {
+                n: *n,
+                dp: p.lower(ctx)?,
+                dr: dr(r)?,
+            },
+            ClassExpression::DataExactCardinality(n, p, r) => CE::DataExactCardinality {
+                n: *n,
+                dp: p.lower(ctx)?,
+                dr: dr(r)?,
+            },
+        })
+    }
+}
+
+// --- Axioms ------------------------------------------------------------------
+
+/// An axiom, with its annotations in source order as the first field.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Axiom {
+    /// A `Declaration` axiom.
+    Declaration(Vec<Annotation>, Entity),
+    /// A `SubClassOf` axiom.
+    SubClassOf(Vec<Annotation>, ClassExpression, ClassExpression),
+    /// An `EquivalentClasses` axiom.
+    EquivalentClasses(Vec<Annotation>, Vec<ClassExpression>),
+    /// A `DisjointClasses` axiom.
+    DisjointClasses(Vec<Annotation>, Vec<ClassExpression>),
+    /// A `DisjointUnion` axiom.
+    DisjointUnion(Vec<Annotation>, Class, Vec<ClassExpression>),
+    /// A `SubObjectPropertyOf` axiom.
+    SubObjectPropertyOf(
+        Vec<Annotation>,
+        SubObjectPropertyExpression,
+        ObjectPropertyExpression,
+    ),
+    /// An `EquivalentObjectProperties` axiom.
+    EquivalentObjectProperties(Vec<Annotation>, Vec<ObjectPropertyExpression>),
+    /// A `DisjointObjectProperties` axiom.
+    DisjointObjectProperties(Vec<Annotation>, Vec<ObjectPropertyExpression>),
+    /// An `InverseObjectProperties` axiom.
+    InverseObjectProperties(Vec<Annotation>, ObjectProperty, ObjectProperty),
+    /// An `ObjectPropertyDomain` axiom.
+    ObjectPropertyDomain(Vec<Annotation>, ObjectPropertyExpression, ClassExpression),
+    /// An `ObjectPropertyRange` axiom.
+    ObjectPropertyRange(Vec<Annotation>, ObjectPropertyExpression, ClassExpression),
+    /// A `FunctionalObjectProperty` axiom.
+    FunctionalObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// An `InverseFunctionalObjectProperty` axiom.
+    InverseFunctionalObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// A `ReflexiveObjectProperty` axiom.
+    ReflexiveObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// An `IrreflexiveObjectProperty` axiom.
+    IrreflexiveObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// A `SymmetricObjectProperty` axiom.
+    SymmetricObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// An `AsymmetricObjectProperty` axiom.
+    AsymmetricObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// A `TransitiveObjectProperty` axiom.
+    TransitiveObjectProperty(Vec<Annotation>, ObjectPropertyExpression),
+    /// A `SubDataPropertyOf` axiom.
+    SubDataPropertyOf(Vec<Annotation>, DataProperty, DataProperty),
+    /// An `EquivalentDataProperties` axiom.
+    EquivalentDataProperties(Vec<Annotation>, Vec<DataProperty>),
+    /// A `DisjointDataProperties` axiom.
+    DisjointDataProperties(Vec<Annotation>, Vec<DataProperty>),
+    /// A `DataPropertyDomain` axiom.
+    DataPropertyDomain(Vec<Annotation>, DataProperty, ClassExpression),
+    /// A `DataPropertyRange` axiom.
+    DataPropertyRange(Vec<Annotation>, DataProperty, DataRange),
+    /// A `FunctionalDataProperty` axiom.
+    FunctionalDataProperty(Vec<Annotation>, DataProperty),
+    /// A `DatatypeDefinition` axiom.
+    DatatypeDefinition(Vec<Annotation>, Datatype, DataRange),
+    /// A `HasKey` axiom.
+    HasKey(
+        Vec<Annotation>,
+        ClassExpression,
+        Vec<ObjectPropertyExpression>,
+        Vec<DataProperty>,
+    ),
+    /// A `SameIndividual` axiom.
+    SameIndividual(Vec<Annotation>, Vec<Individual>),
+    /// A `DifferentIndividuals` axiom.
+    DifferentIndividuals(Vec<Annotation>, Vec<Individual>),
+    /// A `ClassAssertion` axiom.
+    ClassAssertion(Vec<Annotation>, ClassExpression, Individual),
+    /// An `ObjectPropertyAssertion` axiom.
+    ObjectPropertyAssertion(
+        Vec<Annotation>,
+        ObjectPropertyExpression,
+        Individual,
+        Individual,
+    ),
+    /// A `NegativeObjectPropertyAssertion` axiom.
+    NegativeObjectPropertyAssertion(
+        Vec<Annotation>,
+        ObjectPropertyExpression,
+        Individual,
+        Individual,
+    ),
+    /// A `DataPropertyAssertion` axiom.
+    DataPropertyAssertion(Vec<Annotation>, DataProperty, Individual, Literal),
+    /// A `NegativeDataPropertyAssertion` axiom.
+    NegativeDataPropertyAssertion(Vec<Annotation>, DataProperty, Individual, Literal),
+    /// An `AnnotationAssertion` axiom.
+    AnnotationAssertion(
+        Vec<Annotation>,
+        AnnotationProperty,
+        AnnotationSubject,
+        AnnotationValue,
+    ),
+    /// A `SubAnnotationPropertyOf` axiom.
+    SubAnnotationPropertyOf(Vec<Annotation>, AnnotationProperty, AnnotationProperty),
+    /// An `AnnotationPropertyDomain` axiom.
+    AnnotationPropertyDomain(Vec<Annotation>, AnnotationProperty, IRI),
+    /// An `AnnotationPropertyRange` axiom.
+    AnnotationPropertyRange(Vec<Annotation>, AnnotationProperty, IRI),
+}
+
+impl Axiom {
+    /// Get the annotations of the axiom, in source order.
+    pub fn annotations(&self) -> &[Annotation] {
+        use Axiom::*;
+        match self {
+            Declaration(a, ..)
+            | SubClassOf(a, ..)
+            | EquivalentClasses(a, ..)
+            | DisjointClasses(a, ..)
+            | DisjointUnion(a, ..)
+            | SubObjectPropertyOf(a, ..)
+            | EquivalentObjectProperties(a, ..)
+            | DisjointObjectProperties(a, ..)
+            | InverseObjectProperties(a, ..)
+            | ObjectPropertyDomain(a, ..)
+            | ObjectPropertyRange(a, ..)
+            | FunctionalObjectProperty(a, ..)
+            | InverseFunctionalObjectProperty(a, ..)
+            | ReflexiveObjectProperty(a, ..)
+            | IrreflexiveObjectProperty(a, ..)
+            | SymmetricObjectProperty(a, ..)
+            | AsymmetricObjectProperty(a, ..)
+            | TransitiveObjectProperty(a, ..)
+            | SubDataPropertyOf(a, ..)
+            | EquivalentDataProperties(a, ..)
+            | DisjointDataProperties(a, ..)
+            | DataPropertyDomain(a, ..)
+            | DataPropertyRange(a, ..)
+            | FunctionalDataProperty(a, ..)
+            | DatatypeDefinition(a, ..)
+            | HasKey(a, ..)
+            | SameIndividual(a, ..)
+            | DifferentIndividuals(a, ..)
+            | ClassAssertion(a, ..)
+            | ObjectPropertyAssertion(a, ..)
+            | NegativeObjectPropertyAssertion(a, ..)
+            | DataPropertyAssertion(a, ..)
+            | NegativeDataPropertyAssertion(a, ..)
+            | AnnotationAssertion(a, ..)
+            | SubAnnotationPropertyOf(a, ..)
+            | AnnotationPropertyDomain(a, ..)
+            | AnnotationPropertyRange(a, ..) => a,
+        }
+    }
+}
+
+impl Node for Axiom {
+    const RULE: Rule = Rule::Axiom;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        use Axiom::*;
+
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        let mut c = Children::new(inner);
+        let a = c.next()?;
+        Ok(match rule {
+            Rule::Declaration => Declaration(a, c.next()?),
+            Rule::SubClassOf => SubClassOf(a, c.next()?, c.next()?),
+            Rule::EquivalentClasses => EquivalentClasses(a, c.rest()?),
+            Rule::DisjointClasses => DisjointClasses(a, c.rest()?),
+            Rule::DisjointUnion => DisjointUnion(a, c.next()?, c.rest()?),
+            Rule::SubObjectPropertyOf => SubObjectPropertyOf(a, c.next()?, c.next_inner()?),
+            Rule::EquivalentObjectProperties => EquivalentObjectProperties(a, c.rest()?),
+            Rule::DisjointObjectProperties => DisjointObjectProperties(a, c.rest()?),
+            Rule::InverseObjectProperties => InverseObjectProperties(a, c.next()?, c.next()?),
+            Rule::ObjectPropertyDomain => ObjectPropertyDomain(a, c.next()?, c.next()?),
+            Rule::ObjectPropertyRange => ObjectPropertyRange(a, c.next()?, c.next()?),
+            Rule::FunctionalObjectProperty => FunctionalObjectProperty(a, c.next()?),
+            Rule::InverseFunctionalObjectProperty => InverseFunctionalObjectProperty(a, c.next()?),
+            Rule::ReflexiveObjectProperty => ReflexiveObjectProperty(a, c.next()?),
+            Rule::IrreflexiveObjectProperty => IrreflexiveObjectProperty(a, c.next()?),
+            Rule::SymmetricObjectProperty => SymmetricObjectProperty(a, c.next()?),
+            Rule::AsymmetricObjectProperty => AsymmetricObjectProperty(a, c.next()?),
+            Rule::TransitiveObjectProperty => TransitiveObjectProperty(a, c.next()?),
+            Rule::SubDataPropertyOf => SubDataPropertyOf(a, c.next()?, c.next()?),
+            Rule::EquivalentDataProperties => EquivalentDataProperties(a, c.rest()?),
+            Rule::DisjointDataProperties => DisjointDataProperties(a, c.rest()?),
+            Rule::DataPropertyDomain => DataPropertyDomain(a, c.next()?, c.next()?),
+            Rule::DataPropertyRange => DataPropertyRange(a, c.next()?, c.next()?),
+            Rule::FunctionalDataProperty => FunctionalDataProperty(a, c.next()?),
+            Rule::DatatypeDefinition => DatatypeDefinition(a, c.next()?, c.next()?),
+            Rule::HasKey => {
+                let ce = c.next()?;
+                let (mut opes, mut dps) = (Vec::new(), Vec::new());
+                for pair in c.0 {
+                    match pair.as_rule() {
+                        Rule::ObjectPropertyExpression => opes.push(Node::from_pair(pair)?),
+                        _ => dps.push(Node::from_pair(pair)?),
+                    }
+                }
+                HasKey(a, ce, opes, dps)
+            }
+            Rule::SameIndividual => SameIndividual(a, c.rest()?),
+            Rule::DifferentIndividuals => DifferentIndividuals(a, c.rest()?),
+            Rule::ClassAssertion => ClassAssertion(a, c.next()?, c.next()?),
+            Rule::ObjectPropertyAssertion => {
+                ObjectPropertyAssertion(a, c.next()?, c.next()?, c.next()?)
+            }
+            Rule::NegativeObjectPropertyAssertion => {
+                NegativeObjectPropertyAssertion(a, c.next()?, c.next()?, c.next()?)
+            }
+            Rule::DataPropertyAssertion => {
+                DataPropertyAssertion(a, c.next()?, c.next()?, c.next()?)
+            }
+            Rule::NegativeDataPropertyAssertion => {
+                NegativeDataPropertyAssertion(a, c.next()?, c.next()?, c.next()?)
+            }
+            Rule::AnnotationAssertion => AnnotationAssertion(a, c.next()?, c.next()?, c.next()?),
+            Rule::SubAnnotationPropertyOf => {
+                SubAnnotationPropertyOf(a, c.next_inner()?, c.next_inner()?)
+            }
+            Rule::AnnotationPropertyDomain => AnnotationPropertyDomain(a, c.next()?, c.next()?),
+            Rule::AnnotationPropertyRange => AnnotationPropertyRange(a, c.next()?, c.next()?),
+            rule => unreachable!("unexpected rule in Axiom::from_pair: {:?}", rule),
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Axiom {
+    type Target = owl::AnnotatedAxiom<A>;
+    /// Convert the axiom, dropping duplicate and nested annotations.
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        use owl::*;
+
+        let axiom: owl::Axiom<A> = match self {
+            Self::Declaration(_, e) => e.lower(ctx)?,
+            Self::SubClassOf(_, sub, sup) => SubClassOf {
+                sub: sub.lower(ctx)?,
+                sup: sup.lower(ctx)?,
+            }
+            .into(),
+            Self::EquivalentClasses(_, ces) => EquivalentClasses(lower_all(ces, ctx)?).into(),
+            Self::DisjointClasses(_, ces) => DisjointClasses(lower_all(ces, ctx)?).into(),
+            Self::DisjointUnion(_, c, ces) => {
+                DisjointUnion(c.lower(ctx)?, lower_all(ces, ctx)?).into()
+            }
+            Self::SubObjectPropertyOf(_, sub, sup) => SubObjectPropertyOf {
+                sub: sub.lower(ctx)?,
+                sup: sup.lower(ctx)?,
+            }
+            .into(),
+            Self::EquivalentObjectProperties(_, opes) => {
+                EquivalentObjectProperties(lower_all(opes, ctx)?).into()
+            }
+            Self::DisjointObjectProperties(_, opes) => {
+                DisjointObjectProperties(lower_all(opes, ctx)?).into()
+            }
+            Self::InverseObjectProperties(_, a, b) => {
+                InverseObjectProperties(a.lower(ctx)?, b.lower(ctx)?).into()
+            }
+            Self::ObjectPropertyDomain(_, ope, ce) => {
+                ObjectPropertyDomain::new(ope.lower(ctx)?, ce.lower(ctx)?).into()
+            }
+            Self::ObjectPropertyRange(_, ope, ce) => {
+                ObjectPropertyRange::new(ope.lower(ctx)?, ce.lower(ctx)?).into()
+            }
+            Self::FunctionalObjectProperty(_, ope) => {
+                FunctionalObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::InverseFunctionalObjectProperty(_, ope) => {
+                InverseFunctionalObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::ReflexiveObjectProperty(_, ope) => {
+                ReflexiveObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::IrreflexiveObjectProperty(_, ope) => {
+                IrreflexiveObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::SymmetricObjectProperty(_, ope) => {
+                SymmetricObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::AsymmetricObjectProperty(_, ope) => {
+                AsymmetricObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::TransitiveObjectProperty(_, ope) => {
+                TransitiveObjectProperty(ope.lower(ctx)?).into()
+            }
+            Self::SubDataPropertyOf(_, sub, sup) => SubDataPropertyOf {
+                sub: sub.lower(ctx)?,
+                sup: sup.lower(ctx)?,
+            }
+            .into(),
+            Self::EquivalentDataProperties(_, dps) => {
+                EquivalentDataProperties(lower_all(dps, ctx)?).into()
+            }
+            Self::DisjointDataProperties(_, dps) => {
+                DisjointDataProperties(lower_all(dps, ctx)?).into()
+            }
+            Self::DataPropertyDomain(_, dp, ce) => {
+                DataPropertyDomain::new(dp.lower(ctx)?, ce.lower(ctx)?).into()
+            }
+            Self::DataPropertyRange(_, dp, dr) => {
+                DataPropertyRange::new(dp.lower(ctx)?, dr.lower(ctx)?).into()
+            }
+            Self::FunctionalDataProperty(_, dp) => FunctionalDataProperty(dp.lower(ctx)?).into(),
+            Self::DatatypeDefinition(_, dt, dr) => {
+                DatatypeDefinition::new(dt.lower(ctx)?, dr.lower(ctx)?).into()
+            }
+            Self::HasKey(_, ce, opes, dps) => {
+                let mut vpe = Vec::with_capacity(opes.len() + dps.len());
+                for ope in opes {
+                    vpe.push(PropertyExpression::ObjectPropertyExpression(
+                        ope.lower(ctx)?,
+                    ));
+                }
+                for dp in dps {
+                    vpe.push(PropertyExpression::DataProperty(dp.lower(ctx)?));
+                }
+                HasKey::new(ce.lower(ctx)?, vpe).into()
+            }
+            Self::SameIndividual(_, is) => SameIndividual(lower_all(is, ctx)?).into(),
+            Self::DifferentIndividuals(_, is) => DifferentIndividuals(lower_all(is, ctx)?).into(),
+            Self::ClassAssertion(_, ce, i) => {
+                ClassAssertion::new(ce.lower(ctx)?, i.lower(ctx)?).into()
+            }
+            Self::ObjectPropertyAssertion(_, ope, from, to) => ObjectPropertyAssertion {
+                ope: ope.lower(ctx)?,
+                from: from.lower(ctx)?,
+                to: to.lower(ctx)?,
+            }
+            .into(),
+            Self::NegativeObjectPropertyAssertion(_, ope, from, to) => {
+                NegativeObjectPropertyAssertion::new(
+                    ope.lower(ctx)?,
+                    from.lower(ctx)?,
+                    to.lower(ctx)?,
+                )
+                .into()
+            }
+            Self::DataPropertyAssertion(_, dp, from, to) => {
+                DataPropertyAssertion::new(dp.lower(ctx)?, from.lower(ctx)?, to.lower(ctx)?).into()
+            }
+            Self::NegativeDataPropertyAssertion(_, dp, from, to) => {
+                NegativeDataPropertyAssertion::new(dp.lower(ctx)?, from.lower(ctx)?, to.lower(ctx)?)
+                    .into()
+            }
+            Self::AnnotationAssertion(_, ap, subject, av) => AnnotationAssertion::new(
+                subject.lower(ctx)?,
+                owl::Annotation {
+                    ap: ap.lower(ctx)?,
+                    av: av.lower(ctx)?,
+                },
+            )
+            .into(),
+            Self::SubAnnotationPropertyOf(_, sub, sup) => SubAnnotationPropertyOf {
+                sub: sub.lower(ctx)?,
+                sup: sup.lower(ctx)?,
+            }
+            .into(),
+            Self::AnnotationPropertyDomain(_, ap, iri) => {
+                AnnotationPropertyDomain::new(ap.lower(ctx)?, iri.lower(ctx)?).into()
+            }
+            Self::AnnotationPropertyRange(_, ap, iri) => {
+                AnnotationPropertyRange::new(ap.lower(ctx)?, iri.lower(ctx)?).into()
+            }
+        };
+
+        let ann: BTreeSet<_> = lower_all(self.annotations(), ctx)?;
+        Ok(AnnotatedAxiom::new(axiom, ann))
+    }
+}
+
+// --- Rules and description graphs ------------------------------------------
+
+/// A variable of a rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Variable(pub IRI);
+
+impl Node for Variable {
+    const RULE: Rule = Rule::Variable;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        Children::new(pair).next().map(Variable)
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Variable {
+    type Target = swrl::Variable<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        self.0.lower(ctx).map(swrl::Variable)
+    }
+}
+
+/// An argument of a rule atom standing for an individual.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IArg {
+    /// A variable of the rule.
+    Variable(Variable),
+    /// A named or anonymous individual.
+    Individual(Individual),
+}
+
+impl Node for IArg {
+    const RULE: Rule = Rule::IArg;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::Variable => Node::from_pair(inner).map(IArg::Variable),
+            Rule::Individual => Node::from_pair(inner).map(IArg::Individual),
+            rule => unreachable!("unexpected rule in IArg::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for IArg {
+    type Target = swrl::IArg<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            IArg::Variable(v) => v.lower(ctx).map(swrl::IArg::Variable),
+            IArg::Individual(i) => i.lower(ctx).map(swrl::IArg::Individual),
+        }
+    }
+}
+
+/// An argument of a rule atom standing for a literal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DArg {
+    /// A variable of the rule.
+    Variable(Variable),
+    /// A literal.
+    Literal(Literal),
+}
+
+impl Node for DArg {
+    const RULE: Rule = Rule::DArg;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::Variable => Node::from_pair(inner).map(DArg::Variable),
+            Rule::Literal => Node::from_pair(inner).map(DArg::Literal),
+            rule => unreachable!("unexpected rule in DArg::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for DArg {
+    type Target = swrl::DArg<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            DArg::Variable(v) => v.lower(ctx).map(swrl::DArg::Variable),
+            DArg::Literal(l) => l.lower(ctx).map(swrl::DArg::Literal),
+        }
+    }
+}
+
+/// An atom of the body or head of a rule, with its predicate first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Atom {
+    /// A `ClassAtom`.
+    ClassAtom(ClassExpression, IArg),
+    /// A `DataRangeAtom`.
+    DataRangeAtom(DataRange, DArg),
+    /// An `ObjectPropertyAtom`.
+    ObjectPropertyAtom(ObjectPropertyExpression, IArg, IArg),
+    /// A `DataPropertyAtom`.
+    DataPropertyAtom(DataProperty, IArg, DArg),
+    /// A `BuiltInAtom`, with the IRI of the built-in.
+    BuiltInAtom(IRI, Vec<DArg>),
+    /// A `SameIndividualAtom`.
+    SameIndividualAtom(IArg, IArg),
+    /// A `DifferentIndividualsAtom`.
+    DifferentIndividualsAtom(IArg, IArg),
+}
+
+impl Node for Atom {
+    const RULE: Rule = Rule::Atom;
+    /// Create an atom from an `Atom` or a `DGAtom` pair.
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        use Atom::*;
+
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        let mut c = Children::new(inner);
+        Ok(match rule {
+            Rule::ClassAtom => ClassAtom(c.next()?, c.next()?),
+            Rule::DataRangeAtom => DataRangeAtom(c.next()?, c.next()?),
+            Rule::ObjectPropertyAtom => ObjectPropertyAtom(c.next()?, c.next()?, c.next()?),
+            Rule::DataPropertyAtom => DataPropertyAtom(c.next()?, c.next()?, c.next()?),
+            Rule::BuiltInAtom => BuiltInAtom(c.next()?, c.rest()?),
+            Rule::SameIndividualAtom => SameIndividualAtom(c.next()?, c.next()?),
+            Rule::DifferentIndividualsAtom => DifferentIndividualsAtom(c.next()?, c.next()?),
+            rule => unreachable!("unexpected rule in Atom::from_pair: {:?}", rule),
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Atom {
+    type Target = swrl::Atom<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(match self {
+            Atom::ClassAtom(ce, arg) => swrl::Atom::ClassAtom {
+                pred: ce.lower(ctx)?,
+                arg: arg.lower(ctx)?,
+            },
+            Atom::DataRangeAtom(dr, arg) => swrl::Atom::DataRangeAtom {
+                pred: dr.lower(ctx)?,
+                arg: arg.lower(ctx)?,
+            },
+            Atom::ObjectPropertyAtom(ope, a, b) => swrl::Atom::ObjectPropertyAtom {
+                pred: ope.lower(ctx)?,
+                args: (a.lower(ctx)?, b.lower(ctx)?),
+            },
+            Atom::DataPropertyAtom(dp, a, b) => swrl::Atom::DataPropertyAtom {
+                pred: dp.lower(ctx)?,
+                args: (a.lower(ctx)?, b.lower(ctx)?),
+            },
+            Atom::BuiltInAtom(iri, args) => swrl::Atom::BuiltInAtom {
+                pred: iri.lower(ctx)?,
+                args: lower_all(args, ctx)?,
+            },
+            Atom::SameIndividualAtom(a, b) => {
+                swrl::Atom::SameIndividualAtom(a.lower(ctx)?, b.lower(ctx)?)
+            }
+            Atom::DifferentIndividualsAtom(a, b) => {
+                swrl::Atom::DifferentIndividualsAtom(a.lower(ctx)?, b.lower(ctx)?)
+            }
+        })
+    }
+}
+
+/// Read the atoms of a `Body` or `Head` pair.
+fn atoms(pair: Pair<Rule>) -> Result<Vec<Atom>> {
+    Children::new(pair).rest()
+}
+
+/// A DL-safe SWRL rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DLSafeRule {
+    /// The annotations of the rule, in source order.
+    pub annotations: Vec<Annotation>,
+    /// The atoms of the body of the rule.
+    pub body: Vec<Atom>,
+    /// The atoms of the head of the rule.
+    pub head: Vec<Atom>,
+}
+
+impl Node for DLSafeRule {
+    const RULE: Rule = Rule::DLSafeRule;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(DLSafeRule {
+            annotations: children.next()?,
+            body: atoms(children.pair())?,
+            head: atoms(children.pair())?,
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for DLSafeRule {
+    type Target = swrl::DLSafeRule<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(swrl::DLSafeRule {
+            ann: lower_all(&self.annotations, ctx)?,
+            body: lower_all(&self.body, ctx)?,
+            head: lower_all(&self.head, ctx)?,
+        })
+    }
+}
+
+/// A rule of description graphs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DescriptionGraphRule {
+    /// The annotations of the rule, in source order.
+    pub annotations: Vec<Annotation>,
+    /// The atoms of the body of the rule.
+    pub body: Vec<Atom>,
+    /// The atoms of the head of the rule.
+    pub head: Vec<Atom>,
+}
+
+impl Node for DescriptionGraphRule {
+    const RULE: Rule = Rule::DGRule;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(DescriptionGraphRule {
+            annotations: children.next()?,
+            body: atoms(children.pair())?,
+            head: atoms(children.pair())?,
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for DescriptionGraphRule {
+    type Target = dg::DescriptionGraphRule<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(dg::DescriptionGraphRule {
+            ann: lower_all(&self.annotations, ctx)?,
+            body: lower_all(&self.body, ctx)?,
+            head: lower_all(&self.head, ctx)?,
+        })
+    }
+}
+
+/// An assertion that a node of a description graph is an instance of a class.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeAssertion {
+    /// The class of the node.
+    pub class: Class,
+    /// The IRI of the node.
+    pub node: IRI,
+}
+
+impl Node for NodeAssertion {
+    const RULE: Rule = Rule::NodeAssertion;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(NodeAssertion {
+            class: children.next()?,
+            node: children.next_inner()?,
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for NodeAssertion {
+    type Target = dg::NodeAssertion<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(dg::NodeAssertion {
+            class: self.class.lower(ctx)?,
+            node: dg::DGNode(self.node.lower(ctx)?),
+        })
+    }
+}
+
+/// An assertion that two nodes of a description graph are connected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeAssertion {
+    /// The object property of the edge.
+    pub property: ObjectProperty,
+    /// The IRI of the source node.
+    pub from: IRI,
+    /// The IRI of the target node.
+    pub to: IRI,
+}
+
+impl Node for EdgeAssertion {
+    const RULE: Rule = Rule::EdgeAssertion;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(EdgeAssertion {
+            property: children.next()?,
+            from: children.next_inner()?,
+            to: children.next_inner()?,
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for EdgeAssertion {
+    type Target = dg::EdgeAssertion<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(dg::EdgeAssertion {
+            property: self.property.lower(ctx)?,
+            from: dg::DGNode(self.from.lower(ctx)?),
+            to: dg::DGNode(self.to.lower(ctx)?),
+        })
+    }
+}
+
+/// A description graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DescriptionGraph {
+    /// The annotations of the graph, in source order.
+    pub annotations: Vec<Annotation>,
+    /// The node assertions of the graph.
+    pub nodes: Vec<NodeAssertion>,
+    /// The edge assertions of the graph.
+    pub edges: Vec<EdgeAssertion>,
+    /// The main classes of the graph.
+    pub main_classes: Vec<Class>,
+}
+
+impl Node for DescriptionGraph {
+    const RULE: Rule = Rule::DGAxiom;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        Ok(DescriptionGraph {
+            annotations: children.next()?,
+            nodes: Children::new(children.pair()).rest()?,
+            edges: Children::new(children.pair()).rest()?,
+            main_classes: Children::new(children.pair()).rest()?,
+        })
+    }
+}
+
+impl<A: ForIRI> Lower<A> for DescriptionGraph {
+    type Target = dg::DescriptionGraph<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        Ok(dg::DescriptionGraph {
+            ann: lower_all(&self.annotations, ctx)?,
+            nodes: lower_all(&self.nodes, ctx)?,
+            edges: lower_all(&self.edges, ctx)?,
+            main_classes: lower_all(&self.main_classes, ctx)?,
+        })
+    }
+}
+
+// --- Ontologies --------------------------------------------------------------
+
+/// An axiom of an ontology, a rule or a description graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OntologyAxiom {
+    /// An OWL axiom.
+    Axiom(Axiom),
+    /// A DL-safe SWRL rule.
+    DLSafeRule(DLSafeRule),
+    /// A rule of description graphs.
+    DescriptionGraphRule(DescriptionGraphRule),
+    /// A description graph.
+    DescriptionGraph(DescriptionGraph),
+}
+
+impl Node for OntologyAxiom {
+    const RULE: Rule = Rule::OntologyAxiom;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::Axiom => Node::from_pair(inner).map(OntologyAxiom::Axiom),
+            Rule::Rule => {
+                let rule = inner.into_inner().next().unwrap();
+                match rule.as_rule() {
+                    Rule::DLSafeRule => Node::from_pair(rule).map(OntologyAxiom::DLSafeRule),
+                    Rule::DGRule => Node::from_pair(rule).map(OntologyAxiom::DescriptionGraphRule),
+                    rule => unreachable!("unexpected rule in OntologyAxiom::from_pair: {:?}", rule),
+                }
+            }
+            Rule::DGAxiom => Node::from_pair(inner).map(OntologyAxiom::DescriptionGraph),
+            rule => unreachable!("unexpected rule in OntologyAxiom::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> Lower<A> for OntologyAxiom {
+    type Target = Option<owl::AnnotatedAxiom<A>>;
+    /// Convert the axiom, or get `None` for rules and description graphs.
+    ///
+    /// Use [`Ontology::lower_into`] to pass rules and graphs to a sink.
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        match self {
+            OntologyAxiom::Axiom(axiom) => axiom.lower(ctx).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// An ontology, with its header and axioms in source order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Ontology {
+    /// The ontology IRI.
+    pub iri: Option<IRI>,
+    /// The version IRI of the ontology.
+    pub version_iri: Option<IRI>,
+    /// The IRIs of the imported ontologies.
+    pub imports: Vec<IRI>,
+    /// The ontology annotations, in source order.
+    pub annotations: Vec<Annotation>,
+    /// The axioms, rules and description graphs, in source order.
+    pub axioms: Vec<OntologyAxiom>,
+}
+
+impl Node for Ontology {
+    const RULE: Rule = Rule::Ontology;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut ontology = Ontology::default();
+        for pair in pair.into_inner() {
+            match pair.as_rule() {
+                Rule::OntologyIRI => ontology.iri = Children::new(pair).next().map(Some)?,
+                Rule::VersionIRI => ontology.version_iri = Children::new(pair).next().map(Some)?,
+                Rule::DirectlyImportsDocuments => {
+                    for import in pair.into_inner() {
+                        ontology.imports.push(Children::new(import).next()?);
+                    }
+                }
+                Rule::OntologyAnnotations => ontology.annotations = Children::new(pair).rest()?,
+                Rule::OntologyAxioms => ontology.axioms = Children::new(pair).rest()?,
+                rule => unreachable!("unexpected rule in Ontology::from_pair: {:?}", rule),
+            }
+        }
+        Ok(ontology)
+    }
+}
+
+impl Ontology {
+    /// Convert the ontology into the given sink.
+    pub fn lower_into<A, S>(&self, ctx: &Context<'_, A>, sink: &mut S) -> Result<()>
+    where
+        A: ForIRI,
+        S: OntologySink<A>,
+    {
+        sink.ontology_id(owl::OntologyID {
+            iri: self.iri.as_ref().map(|iri| iri.lower(ctx)).transpose()?,
+            viri: self
+                .version_iri
+                .as_ref()
+                .map(|iri| iri.lower(ctx))
+                .transpose()?,
+        });
+        for import in &self.imports {
+            sink.import(owl::Import(import.lower(ctx)?));
+        }
+        for annotation in &self.annotations {
+            sink.ontology_annotation(owl::OntologyAnnotation(annotation.lower(ctx)?));
+        }
+        let mut seen = SeenAxioms::new(ctx);
+        for axiom in &self.axioms {
+            match axiom {
+                OntologyAxiom::Axiom(axiom) => {
+                    let axiom = axiom.lower(ctx)?;
+                    seen.check(ctx, &axiom, || None);
+                    sink.axiom(axiom);
+                }
+                OntologyAxiom::DLSafeRule(rule) => {
+                    let kept = sink.rule(rule.lower(ctx)?);
+                    check_kept(ctx, kept, WarningKind::DroppedRule, || None)
+                }
+                OntologyAxiom::DescriptionGraphRule(rule) => {
+                    let kept = sink.graph_rule(rule.lower(ctx)?);
+                    check_kept(ctx, kept, WarningKind::DroppedDescriptionGraph, || None)
+                }
+                OntologyAxiom::DescriptionGraph(graph) => {
+                    let kept = sink.description_graph(graph.lower(ctx)?);
+                    check_kept(ctx, kept, WarningKind::DroppedDescriptionGraph, || None)
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<A: ForIRI> Lower<A> for Ontology {
+    type Target = SetOntology<A>;
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        let mut ontology = SetOntology::new();
+        self.lower_into(ctx, &mut ontology)?;
+        Ok(ontology)
+    }
+}
+
+/// A prefix declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PrefixDeclaration {
+    /// The prefix name with its colon, such as `obo:`, or `:` for the default prefix.
+    pub name: String,
+    /// The IRI of the prefix, without its angle brackets.
+    pub iri: String,
+}
+
+impl Node for PrefixDeclaration {
+    const RULE: Rule = Rule::PrefixDeclaration;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut children = Children::new(pair);
+        let name = children.pair().as_str().to_string();
+        let iri = children.pair().into_inner().next().unwrap().as_str();
+        Ok(PrefixDeclaration {
+            name,
+            iri: iri.to_string(),
+        })
+    }
+}
+
+/// An entire OWL functional document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct OntologyDocument {
+    /// The prefix declarations, in source order.
+    pub prefixes: Vec<PrefixDeclaration>,
+    /// The ontology of the document.
+    pub ontology: Ontology,
+}
+
+impl Node for OntologyDocument {
+    const RULE: Rule = Rule::OntologyDocument;
+    fn from_pair(pair: Pair<Rule>) -> Result<Self> {
+        let mut document = OntologyDocument::default();
+        for pair in pair.into_inner() {
+            match pair.as_rule() {
+                Rule::PrefixDeclaration => document.prefixes.push(Node::from_pair(pair)?),
+                Rule::Ontology => document.ontology = Node::from_pair(pair)?,
+                _ => (),
+            }
+        }
+        Ok(document)
+    }
+}
+
+impl OntologyDocument {
+    /// Get the prefix mapping declared in the document.
+    pub fn prefix_mapping(&self) -> Result<PrefixMapping> {
+        let mut prefixes = PrefixMapping::default();
+        for declaration in &self.prefixes {
+            match declaration.name.trim_end_matches(':') {
+                "" => prefixes.set_default(&declaration.iri),
+                name => prefixes.add_prefix(name, &declaration.iri).map_err(|_| {
+                    Error::Unsupported(format!("reserved prefix `{}`", declaration.name))
+                })?,
+            }
+        }
+        Ok(prefixes)
+    }
+
+    /// Convert the document into the given sink, and get its prefix mapping.
+    ///
+    /// The prefixes of `ctx` are ignored, and the prefixes declared in the
+    /// document are used instead.
+    pub fn lower_into<A, S>(&self, ctx: &Context<'_, A>, sink: &mut S) -> Result<PrefixMapping>
+    where
+        A: ForIRI,
+        S: OntologySink<A>,
+    {
+        let prefixes = self.prefix_mapping()?;
+        for declaration in &self.prefixes {
+            let name = declaration.name.trim_end_matches(':');
+            sink.prefix(Some(name).filter(|n| !n.is_empty()), &declaration.iri);
+        }
+        self.ontology
+            .lower_into(&ctx.with_prefixes(&prefixes), sink)?;
+        Ok(prefixes)
+    }
+}
+
+impl<A: ForIRI> Lower<A> for OntologyDocument {
+    type Target = (SetOntology<A>, PrefixMapping);
+    fn lower(&self, ctx: &Context<'_, A>) -> Result<Self::Target> {
+        let mut ontology = SetOntology::new();
+        let prefixes = self.lower_into(ctx, &mut ontology)?;
+        Ok((ontology, prefixes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::FromFunctional;
+
+    #[test]
+    fn annotations() {
+        let axiom = Axiom::from_ofn(
+            r#"SubClassOf(
+                Annotation(Annotation(:source "a") :comment "x")
+                Annotation(:comment "x")
+                Annotation(:b "y")
+                :A :B
+            )"#,
+        )
+        .unwrap();
+        let annotations = axiom.annotations();
+        assert_eq!(annotations.len(), 3);
+        assert_eq!(annotations[0].annotations.len(), 1);
+        assert_eq!(
+            annotations[2].property,
+            AnnotationProperty(IRI::Abbreviated(":b".into()))
+        );
+
+        let mut prefixes = PrefixMapping::default();
+        prefixes.set_default("http://example.com/");
+        let build = owl::Build::<String>::new();
+        let lowered = axiom.lower(&Context::new(&build, &prefixes)).unwrap();
+        assert_eq!(lowered.ann.len(), 2);
+    }
+
+    #[test]
+    fn cardinality() {
+        let ce = ClassExpression::from_ofn("DataMinCardinality(1 <http://a.com/p>)").unwrap();
+        assert_eq!(
+            ce,
+            ClassExpression::DataMinCardinality(
+                1,
+                DataProperty(IRI::Full("http://a.com/p".into())),
+                None
+            )
+        );
+
+        let build = owl::Build::<String>::new();
+        let lowered = ce.lower(&Context::from(&build)).unwrap();
+        assert_eq!(
+            lowered,
+            owl::ClassExpression::DataMinCardinality {
+                n: 1,
+                dp: build.data_property("http://a.com/p"),
+                dr: build
+                    .datatype("http://www.w3.org/2000/01/rdf-schema#Literal")
+                    .into(),
+            }
+        );
+    }
+
+    #[test]
+    fn literals() {
+        let literal = Literal::from_ofn(r#""a \"b\""@en"#).unwrap();
+        assert_eq!(
+            literal,
+            Literal::WithLanguage(r#"a "b""#.into(), "en".into())
+        );
+        let literal = Literal::from_ofn(r#""1"^^xsd:integer"#).unwrap();
+        assert_eq!(
+            literal,
+            Literal::Typed("1".into(), Datatype(IRI::Abbreviated("xsd:integer".into())))
+        );
+    }
+
+    #[test]
+    fn has_key() {
+        let axiom = Axiom::from_ofn("HasKey(:A (:p ObjectInverseOf(:q)) (:d))").unwrap();
+        match axiom {
+            Axiom::HasKey(_, _, opes, dps) => {
+                assert_eq!(opes.len(), 2);
+                assert_eq!(dps.len(), 1);
+            }
+            other => panic!("unexpected axiom: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn undefined_prefix() {
+        let build = owl::Build::<String>::new();
+        let iri = IRI::from_ofn("ex:A").unwrap();
+        assert!(matches!(
+            iri.lower(&Context::from(&build)),
+            Err(Error::Expansion(_))
+        ));
+    }
+
+    const RULES: &str = r#"Prefix(:=<http://example.com/>)
+Ontology(
+Declaration(Class(:A))
+DLSafeRule(Annotation(:comment "x") Body(ClassAtom(:A Variable(:x))) Head(DataPropertyAtom(:d Variable(:x) "1")))
+DescriptionGraphRule(Body(ObjectPropertyAtom(:r Variable(:x) :i)) Head())
+DescriptionGraph(Nodes(NodeAssertion(:A :n)) Edges(EdgeAssertion(:r :n :n)) MainClasses(:A))
+)"#;
+
+    #[derive(Default)]
+    struct Rules {
+        rules: Vec<swrl::DLSafeRule<String>>,
+        graphs: Vec<dg::DescriptionGraph<String>>,
+        graph_rules: Vec<dg::DescriptionGraphRule<String>>,
+    }
+
+    impl OntologySink<String> for Rules {
+        fn axiom(&mut self, _axiom: owl::AnnotatedAxiom<String>) {}
+
+        fn rule(&mut self, rule: swrl::DLSafeRule<String>) -> bool {
+            self.rules.push(rule);
+            true
+        }
+
+        fn description_graph(&mut self, graph: dg::DescriptionGraph<String>) -> bool {
+            self.graphs.push(graph);
+            true
+        }
+
+        fn graph_rule(&mut self, rule: dg::DescriptionGraphRule<String>) -> bool {
+            self.graph_rules.push(rule);
+            true
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let document = OntologyDocument::from_ofn(RULES).unwrap();
+        match &document.ontology.axioms[1] {
+            OntologyAxiom::DLSafeRule(rule) => {
+                assert_eq!(rule.annotations.len(), 1);
+                assert!(matches!(rule.head[0], Atom::DataPropertyAtom(..)));
+            }
+            other => panic!("unexpected axiom: {:?}", other),
+        }
+
+        let mut sink = Rules::default();
+        let build = owl::Build::new();
+        let ctx = Context::from(&build);
+        document.lower_into(&ctx, &mut sink).unwrap();
+        let prefixes = document.prefix_mapping().unwrap();
+        let ctx = ctx.with_prefixes(&prefixes);
+        let rule = swrl::DLSafeRule::from_ofn_ctx(RULES.lines().nth(3).unwrap(), &ctx);
+        assert_eq!(sink.rules, vec![rule.unwrap()]);
+        assert_eq!(sink.graph_rules[0].body.len(), 1);
+        assert_eq!(
+            sink.graphs[0].main_classes,
+            vec![build.class("http://example.com/A")]
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let warnings = std::cell::RefCell::new(Vec::new());
+        let callback = |w: crate::Warning| warnings.borrow_mut().push(w.kind);
+        let build = owl::Build::<String>::new();
+        let ctx = Context::from(&build).with_warnings(&callback);
+
+        OntologyDocument::from_ofn(RULES)
+            .unwrap()
+            .lower(&ctx)
+            .unwrap();
+        assert_eq!(
+            warnings.take(),
+            vec![
+                WarningKind::DroppedRule,
+                WarningKind::DroppedDescriptionGraph,
+                WarningKind::DroppedDescriptionGraph
+            ]
+        );
+
+        let annotation = Annotation::from_ofn(
+            r#"Annotation(Annotation(<http://a.com/p> "a") <http://a.com/p> "b")"#,
+        );
+        annotation.unwrap().lower(&ctx).unwrap();
+        assert_eq!(warnings.take(), vec![WarningKind::DroppedAnnotation]);
+    }
+
+    #[test]
+    fn same_as_from_str() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("data")
+            .join("bfo.ofn");
+        let text = std::fs::read_to_string(path).unwrap();
+        let (expected, expected_prefixes) =
+            crate::from_str::<String, SetOntology<String>, _>(&text).unwrap();
+
+        let document = OntologyDocument::from_ofn(&text).unwrap();
+        let build = owl::Build::new();
+        let (ontology, prefixes) = document.lower(&Context::from(&build)).unwrap();
+        assert_eq!(ontology, expected);
+        assert_eq!(
+            prefixes.mappings().collect::<Vec<_>>(),
+            expected_prefixes.mappings().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Preservation of the comments of a document.
+
+use std::collections::HashMap;
+
+use horned_owl::model::*;
+use pest::iterators::Pair;
+
+use crate::error::Result;
+use crate::parser::Rule;
+use crate::scan::comments;
+use crate::sink::Element;
+use crate::sink::Observer;
+use crate::Context;
+
+/// The comments of an OWL functional document.
+///
+/// Comments are attached to the import, ontology annotation or axiom that
+/// follows them, or to the ontology header if they come before the
+/// `Ontology` element. Comments before a SWRL rule or a description graph
+/// are attached to the next axiom, or to the trailer if there is none.
+/// Comments found inside an element are not preserved. The text of a
+/// comment is stored without its leading `#`.
+///
+/// Comments are collected in [`SideTables::comments`](crate::SideTables::comments),
+/// and written back by a context built with [`Context::with_comments`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comments<A: ForIRI> {
+    /// The comments before the `Ontology` element.
+    pub header: Vec<String>,
+    /// The comments before each import, ontology annotation and axiom.
+    pub axioms: HashMap<AnnotatedAxiom<A>, Vec<String>>,
+    /// The comments after the last axiom of the ontology.
+    pub trailer: Vec<String>,
+}
+
+impl<A: ForIRI> Default for Comments<A> {
+    fn default() -> Self {
+        Self {
+            header: Vec::new(),
+            axioms: HashMap::new(),
+            trailer: Vec::new(),
+        }
+    }
+}
+
+/// An observer collecting the comments of a document.
+pub(crate) struct CommentCollector<'t, A: ForIRI> {
+    text: &'t str,
+    /// The end of the last element.
+    cursor: usize,
+    /// The comments waiting for the next axiom.
+    pending: Vec<String>,
+    comments: Comments<A>,
+}
+
+impl<'t, A: ForIRI> CommentCollector<'t, A> {
+    pub(crate) fn new(text: &'t str) -> Self {
+        Self {
+            text,
+            cursor: 0,
+            pending: Vec::new(),
+            comments: Comments::default(),
+        }
+    }
+
+    /// Get the comments, attaching the remaining ones to the trailer.
+    pub(crate) fn finish(mut self) -> Comments<A> {
+        self.pending.extend(owned(&self.text[self.cursor..]));
+        self.comments.trailer = self.pending;
+        self.comments
+    }
+}
+
+/// Get the comments of a text as owned strings.
+fn owned(text: &str) -> impl Iterator<Item = String> + '_ {
+    comments(text).into_iter().map(String::from)
+}
+
+impl<'t, A: ForIRI> Observer<A> for CommentCollector<'t, A> {
+    fn ontology(&mut self, pair: &Pair<Rule>) {
+        let start = pair.as_span().start();
+        let open = start + self.text[start..].find('(').map_or(0, |i| i + 1);
+        self.comments.header = owned(&self.text[..open]).collect();
+        self.cursor = open;
+    }
+
+    fn element(
+        &mut self,
+        pair: &Pair<Rule>,
+        element: &Element<A>,
+        _ctx: &Context<'_, A>,
+    ) -> Result<()> {
+        let span = pair.as_span();
+        self.pending
+            .extend(owned(&self.text[self.cursor..span.start()]));
+        self.cursor = span.end();
+        if let Element::Axiom(axiom) = element {
+            if !self.pending.is_empty() {
+                self.comments
+                    .axioms
+                    .entry(axiom.clone())
+                    .or_default()
+                    .append(&mut self.pending);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::rc::Rc;
+
+    use curie::PrefixMapping;
+    use horned_owl::ontology::axiom_mapped::AxiomMappedOntology;
+
+    use super::*;
+    use crate::side_tables::collect;
+    use crate::side_tables::roundtrip;
+    use crate::AsFunctional;
+
+    type Ontology = AxiomMappedOntology<String, Rc<AnnotatedAxiom<String>>>;
+
+    const DOC: &str = r##"# generated by hand
+Prefix(ex:=<http://example.com/>)
+Prefix(owl:=<http://www.w3.org/2002/07/owl#>)
+Ontology(ex:o # the ontology IRI
+  Annotation(ex:comment "# not a comment")
+
+  # Class: ex:A (a class)
+  Declaration(Class(ex:A))
+  # curator note
+  # on two lines
+  SubClassOf(ex:A # inner comments are lost
+    ex:B)
+  Declaration(Class(ex:B))
+  # the end
+)
+# after the ontology
+"##;
+
+    #[test]
+    fn attach() {
+        let comments = collect(DOC).2.comments.unwrap();
+        let build = Build::new();
+        assert_eq!(comments.header, vec![" generated by hand"]);
+        assert_eq!(comments.trailer, vec![" the end", " after the ontology"]);
+        assert_eq!(comments.axioms.len(), 3);
+
+        let annotation = AnnotatedAxiom::from(OntologyAnnotation(Annotation {
+            ap: build.annotation_property("http://example.com/comment"),
+            av: Literal::Simple {
+                literal: String::from("# not a comment"),
+            }
+            .into(),
+        }));
+        assert_eq!(comments.axioms[&annotation], vec![" the ontology IRI"]);
+        let declaration = AnnotatedAxiom::from(DeclareClass(build.class("http://example.com/A")));
+        assert_eq!(
+            comments.axioms[&declaration],
+            vec![" Class: ex:A (a class)"]
+        );
+        let subclass = AnnotatedAxiom::from(SubClassOf {
+            sub: build.class("http://example.com/A").into(),
+            sup: build.class("http://example.com/B").into(),
+        });
+        assert_eq!(
+            comments.axioms[&subclass],
+            vec![" curator note", " on two lines"]
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let doc = "Ontology(\n# a rule\nDLSafeRule(Body() Head())\n# a class\nDeclaration(Class(<http://a.com/A>))\n# a graph rule\nDescriptionGraphRule(Body() Head())\n)";
+        let comments = collect(doc).2.comments.unwrap();
+        let declaration = AnnotatedAxiom::from(DeclareClass(Build::new().class("http://a.com/A")));
+        assert_eq!(comments.axioms[&declaration], vec![" a rule", " a class"]);
+        assert_eq!(comments.trailer, vec![" a graph rule"]);
+    }
+
+    #[test]
+    fn header() {
+        let doc = "# before\nOntology(<http://a.com/o>\n# import\nImport(<http://a.com/p>)\n# annotation\nAnnotation(<http://a.com/p> \"x\")\n# axiom\nDeclaration(Class(<http://a.com/A>))\n)";
+        let (ontology, prefixes, tables) = collect(doc);
+        let comments = tables.comments.unwrap();
+        assert_eq!(comments.header, vec![" before"]);
+        assert_eq!(comments.axioms.len(), 3);
+
+        let ctx = Context::from(&prefixes).with_comments(&comments);
+        assert_eq!(roundtrip(doc), doc);
+        let ontology = Ontology::from(ontology);
+        assert_eq!(ontology.as_ofn_ctx(&ctx).to_string(), doc);
+    }
+
+    #[test]
+    fn reparse() {
+        let (ontology, prefixes, tables) = collect(DOC);
+        let written = format!(
+            "{}{}",
+            <PrefixMapping as AsFunctional<String>>::as_ofn(&prefixes),
+            roundtrip(DOC)
+        );
+
+        let (reparsed, _, retables) = collect(&written);
+        assert_eq!(reparsed, ontology);
+        assert_eq!(retables.comments, tables.comments);
+    }
+}
@@ -0,0 +1,366 @@
+//! A lossless concrete syntax tree of OWL functional documents.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::ops::Range;
+
+use pest::iterators::Pair;
+
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::limits::Limits;
+use crate::parser::Rule;
+
+/// The kind of a token of the concrete syntax tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A run of whitespace.
+    Whitespace,
+    /// A comment, from the `#` sign to the end of the line.
+    Comment,
+    /// A keyword, such as `SubClassOf` or `Ontology`.
+    Keyword,
+    /// An opening parenthesis.
+    LParen,
+    /// A closing parenthesis.
+    RParen,
+    /// The equal sign of a prefix declaration.
+    Equals,
+    /// A single caret of the `^^` before the datatype of a literal.
+    Caret,
+    /// A full IRI, with its angle brackets.
+    FullIRI,
+    /// An abbreviated IRI, such as `obo:BFO_0000001`.
+    AbbreviatedIRI,
+    /// The prefix name of a prefix declaration, with its colon.
+    PrefixName,
+    /// A quoted string, with its quotes.
+    QuotedString,
+    /// A language tag, with its `@` sign.
+    LanguageTag,
+    /// A blank node label, such as `_:b0`.
+    BlankNode,
+    /// A non-negative integer.
+    Integer,
+}
+
+/// A token of the concrete syntax tree, with its original text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SyntaxToken {
+    kind: TokenKind,
+    text: String,
+    span: Range<usize>,
+}
+
+impl SyntaxToken {
+    /// Get the kind of the token.
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// Get the text of the token, as written in the input.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the byte range of the token in the input.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Check whether the token is whitespace or a comment.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
+    }
+}
+
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.text)
+    }
+}
+
+/// A child of a node of the concrete syntax tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SyntaxElement {
+    /// A node for a grammar rule.
+    Node(SyntaxNode),
+    /// A token, including whitespace and comments.
+    Token(SyntaxToken),
+}
+
+impl Display for SyntaxElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SyntaxElement::Node(node) => node.fmt(f),
+            SyntaxElement::Token(token) => token.fmt(f),
+        }
+    }
+}
+
+/// A node of the concrete syntax tree, for a rule of the OWL2 grammar.
+///
+/// The tree keeps every token of the input, including whitespace, comments
+/// and parentheses, so that displaying a node gives back the exact text it
+/// was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SyntaxNode {
+    rule: Rule,
+    span: Range<usize>,
+    children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    /// Get the grammar rule of the node, such as [`Rule::SubClassOf`].
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Get the name of the grammar rule of the node, such as `SubClassOf`.
+    pub fn name(&self) -> String {
+        format!("{:?}", self.rule)
+    }
+
+    /// Get the byte range of the node in the input.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Get the children of the node, in input order.
+    pub fn children(&self) -> &[SyntaxElement] {
+        &self.children
+    }
+
+    /// Iterate over the child nodes of the node.
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Get all the tokens of the node, in input order.
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// Build the node for a pair, covering the given range of the input.
+    fn build(pair: Pair<Rule>, text: &str, range: Range<usize>) -> Self {
+        let rule = pair.as_rule();
+        let mut children = Vec::new();
+        let mut cursor = range.start;
+        for inner in pair.into_inner() {
+            let span = inner.as_span();
+            if inner.as_rule() == Rule::EOI {
+                continue;
+            }
+            tokenize(text, cursor..span.start(), &mut children);
+            let token = |kind| SyntaxToken {
+                kind,
+                text: span.as_str().to_string(),
+                span: span.start()..span.end(),
+            };
+            children.push(match inner.as_rule() {
+                Rule::FullIRI => SyntaxElement::Token(token(TokenKind::FullIRI)),
+                Rule::AbbreviatedIRI => SyntaxElement::Token(token(TokenKind::AbbreviatedIRI)),
+                Rule::PrefixName => SyntaxElement::Token(token(TokenKind::PrefixName)),
+                Rule::QuotedString => SyntaxElement::Token(token(TokenKind::QuotedString)),
+                Rule::LanguageTag => SyntaxElement::Token(token(TokenKind::LanguageTag)),
+                Rule::SPARQL_BlankNodeLabel => SyntaxElement::Token(token(TokenKind::BlankNode)),
+                Rule::NonNegativeInteger => SyntaxElement::Token(token(TokenKind::Integer)),
+                _ => SyntaxElement::Node(Self::build(inner, text, span.start()..span.end())),
+            });
+            cursor = span.end();
+        }
+        tokenize(text, cursor..range.end, &mut children);
+        Self {
+            rule,
+            span: range,
+            children,
+        }
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.children.iter().try_for_each(|child| child.fmt(f))
+    }
+}
+
+/// Split the text between two nodes into tokens.
+///
+/// Only whitespace, comments, keywords and punctuation can be found between
+/// the nodes, since the grammar rules matching them are silent.
+fn tokenize(text: &str, range: Range<usize>, tokens: &mut Vec<SyntaxElement>) {
+    let bytes = text.as_bytes();
+    let mut i = range.start;
+    while i < range.end {
+        let start = i;
+        let kind = match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                while i < range.end && matches!(bytes[i], b' ' | b'\t' | b'\n' | b'\r') {
+                    i += 1;
+                }
+                TokenKind::Whitespace
+            }
+            b'#' => {
+                while i < range.end && !matches!(bytes[i], b'\n' | b'\r') {
+                    i += 1;
+                }
+                TokenKind::Comment
+            }
+            b'(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            b')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            b'=' => {
+                i += 1;
+                TokenKind::Equals
+            }
+            b'^' => {
+                i += 1;
+                TokenKind::Caret
+            }
+            _ => {
+                while i < range.end && !b" \t\n\r#()=^".contains(&bytes[i]) {
+                    i += 1;
+                }
+                TokenKind::Keyword
+            }
+        };
+        tokens.push(SyntaxElement::Token(SyntaxToken {
+            kind,
+            text: text[start..i].to_string(),
+            span: start..i,
+        }));
+    }
+}
+
+/// Parse an entire OWL document into a lossless concrete syntax tree.
+///
+/// The tree is a syntactic view of the document, while the `horned-owl`
+/// model obtained with [`from_str`](crate::from_str) is its semantic view.
+///
+/// # Example
+/// ```rust
+/// let doc = "Prefix(ex:=<http://example.com/>)\nOntology( # comment\n  Declaration(Class(ex:A))\n)\n";
+/// let cst = horned_functional::parse_cst(doc).unwrap();
+/// assert_eq!(cst.to_string(), doc);
+/// ```
+pub fn parse_cst(src: &str) -> Result<SyntaxNode> {
+    let pair = parse_exact(Rule::OntologyDocument, src, &Limits::default())?;
+    Ok(SyntaxNode::build(pair, src, 0..src.len()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DOC: &str = r#"
+# a document
+Prefix(ex:=<http://example.com/>)
+Ontology(ex:o
+  Declaration( Class( ex:A ) ) # declare A
+  SubClassOf(ex:A ObjectMinCardinality(1 ex:p))
+  AnnotationAssertion(ex:label _:b0 "a \"label\""@en)
+  ClassAssertion(ex:A _:b0)
+  DataPropertyAssertion(ex:d ex:i "1" ^^ <http://www.w3.org/2001/XMLSchema#integer>)
+)
+"#;
+
+    #[test]
+    fn lossless() {
+        let cst = parse_cst(DOC).unwrap();
+        assert_eq!(cst.to_string(), DOC);
+        assert_eq!(cst.span(), 0..DOC.len());
+
+        let tokens = cst.tokens();
+        let text = tokens.iter().map(|t| t.text()).collect::<String>();
+        assert_eq!(text, DOC);
+        for token in tokens {
+            assert_eq!(&DOC[token.span()], token.text());
+        }
+    }
+
+    #[test]
+    fn lossless_bfo() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("data")
+            .join("bfo.ofn");
+        let text = std::fs::read_to_string(path).unwrap();
+        assert_eq!(parse_cst(&text).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn tokens() {
+        let cst = parse_cst(DOC).unwrap();
+        let kinds = cst
+            .tokens()
+            .into_iter()
+            .filter(|t| !t.is_trivia())
+            .take(12)
+            .map(|t| (t.kind(), t.text()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (TokenKind::Keyword, "Prefix"),
+                (TokenKind::LParen, "("),
+                (TokenKind::PrefixName, "ex:"),
+                (TokenKind::Equals, "="),
+                (TokenKind::FullIRI, "<http://example.com/>"),
+                (TokenKind::RParen, ")"),
+                (TokenKind::Keyword, "Ontology"),
+                (TokenKind::LParen, "("),
+                (TokenKind::AbbreviatedIRI, "ex:o"),
+                (TokenKind::Keyword, "Declaration"),
+                (TokenKind::LParen, "("),
+                (TokenKind::Keyword, "Class"),
+            ]
+        );
+
+        let comments = cst
+            .tokens()
+            .into_iter()
+            .filter(|t| t.kind() == TokenKind::Comment)
+            .map(|t| t.text())
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec!["# a document", "# declare A"]);
+    }
+
+    #[test]
+    fn nodes() {
+        let cst = parse_cst(DOC).unwrap();
+        assert_eq!(cst.rule(), Rule::OntologyDocument);
+        assert_eq!(cst.name(), "OntologyDocument");
+        let rules = cst.nodes().map(|n| n.rule()).collect::<Vec<_>>();
+        assert_eq!(rules, vec![Rule::PrefixDeclaration, Rule::Ontology]);
+
+        let ontology = cst.nodes().nth(1).unwrap();
+        let axioms = ontology
+            .nodes()
+            .find(|n| n.rule() == Rule::OntologyAxioms)
+            .unwrap();
+        assert_eq!(axioms.nodes().count(), 5);
+        let first = axioms.nodes().next().unwrap();
+        assert_eq!(first.to_string(), "Declaration( Class( ex:A ) )");
+    }
+}
@@ -0,0 +1,1825 @@
+//! A recursive-descent parser built on top of the hand-written lexer.
+//!
+//! This backend reads the input in a single pass, without building a token
+//! queue or backtracking, and produces the same values as the `pest`
+//! grammar. Syntax errors are reported as `pest` errors, so that both
+//! backends can be used interchangeably.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use curie::PrefixMapping;
+use horned_owl::model::*;
+use horned_owl::vocab::OWL2Datatype;
+use horned_owl::vocab::WithIRI;
+use horned_owl::vocab::OWL;
+
+use crate::dg::DGNode;
+use crate::dg::DescriptionGraph;
+use crate::dg::DescriptionGraphRule;
+use crate::dg::EdgeAssertion;
+use crate::dg::NodeAssertion;
+use crate::diagnostic::Location;
+use crate::error::Error;
+use crate::error::Result;
+use crate::from_ofn::FromFunctional;
+use crate::from_pair::anonymous_value;
+use crate::from_pair::expand_curie;
+use crate::from_pair::facet;
+use crate::from_pair::unquote;
+use crate::from_pair::FromPair;
+use crate::lexer::Lexer;
+use crate::lexer::Token;
+use crate::parser::Rule;
+use crate::progress::Progress;
+use crate::scan::next_element;
+use crate::scan::skip_trivia;
+use crate::scan::Scan;
+use crate::sink::OntologySink;
+use crate::swrl::Atom;
+use crate::swrl::DArg;
+use crate::swrl::DLSafeRule;
+use crate::swrl::IArg;
+use crate::swrl::Variable;
+use crate::warning::check_kept;
+use crate::warning::check_prefix;
+use crate::warning::SeenAxioms;
+use crate::Context;
+use crate::Warning;
+use crate::WarningKind;
+
+// ---------------------------------------------------------------------------
+
+/// A parser reading OWL elements from a stream of tokens.
+#[derive(Debug)]
+pub(crate) struct Parser<'i> {
+    lexer: Lexer<'i>,
+    /// The next token, with its start and end offsets, if already read.
+    peeked: Option<(usize, usize, Token<'i>)>,
+    /// The end offset of the last consumed token.
+    end: usize,
+}
+
+impl<'i> Parser<'i> {
+    /// Create a new parser over the given input.
+    pub(crate) fn new(input: &'i str) -> Self {
+        Self {
+            lexer: Lexer::new(input),
+            peeked: None,
+            end: 0,
+        }
+    }
+
+    /// Peek at the next token without consuming it.
+    fn peek(&mut self) -> Result<Option<Token<'i>>> {
+        if self.peeked.is_none() {
+            if let Some((start, token)) = self.lexer.next_token()? {
+                self.peeked = Some((start, self.lexer.offset(), token));
+            }
+        }
+        Ok(self.peeked.map(|(_, _, token)| token))
+    }
+
+    /// Get the location of the next token, or of the end of the input.
+    fn location(&self) -> (usize, usize) {
+        let len = self.lexer.input().len();
+        self.peeked.map(|(s, e, _)| (s, e)).unwrap_or((len, len))
+    }
+
+    /// Consume the next token.
+    fn bump(&mut self) -> Result<Option<Token<'i>>> {
+        self.peek()?;
+        Ok(self.peeked.take().map(|(_, end, token)| {
+            self.end = end;
+            token
+        }))
+    }
+
+    /// Create an error for when `rule` was expected at the next token.
+    fn expected(&self, rule: Rule) -> Error {
+        expected_at(self.lexer.input(), self.location().0, rule)
+    }
+
+    /// Get the span of the next token.
+    fn span(&self) -> pest::Span<'i> {
+        let (start, end) = self.location();
+        pest::Span::new(self.lexer.input(), start, end).unwrap()
+    }
+
+    /// Get the location from `start` to the end of the last consumed token.
+    fn location_from(&self, start: usize) -> Option<Location> {
+        let span = pest::Span::new(self.lexer.input(), start, self.end)?;
+        Some(Location::from_span(&span))
+    }
+
+    /// Create a custom error located at the next token.
+    fn custom(&self, message: &str) -> Error {
+        Error::custom(message, self.span())
+    }
+
+    /// Consume the given token, or fail with `message`.
+    fn expect(&mut self, token: Token<'_>, message: &str) -> Result<()> {
+        if self.peek()? == Some(token) {
+            self.bump().map(|_| ())
+        } else {
+            Err(self.custom(message))
+        }
+    }
+
+    /// Consume an opening parenthesis.
+    fn open(&mut self) -> Result<()> {
+        self.expect(Token::LParen, "expected `(`")
+    }
+
+    /// Consume a closing parenthesis.
+    fn close(&mut self) -> Result<()> {
+        self.expect(Token::RParen, "expected `)`")
+    }
+
+    /// Check whether the next token is a closing parenthesis.
+    fn at_close(&mut self) -> Result<bool> {
+        Ok(self.peek()? == Some(Token::RParen))
+    }
+
+    /// Check whether the next token is an IRI.
+    fn at_iri(&mut self) -> Result<bool> {
+        Ok(match self.peek()? {
+            Some(Token::FullIRI(_)) => true,
+            Some(Token::Word(w)) => w.contains(':'),
+            _ => false,
+        })
+    }
+
+    /// Check whether the next token is a blank node label.
+    fn at_blank_node(&mut self) -> Result<bool> {
+        Ok(matches!(self.peek()?, Some(Token::Word(w)) if w.starts_with("_:")))
+    }
+
+    /// Get the keyword starting the next element, if any.
+    fn keyword(&mut self) -> Result<Option<&'i str>> {
+        Ok(match self.peek()? {
+            Some(Token::Word(w)) if !w.contains(':') => Some(w),
+            _ => None,
+        })
+    }
+
+    /// Consume the given keyword and the following opening parenthesis.
+    fn start(&mut self, keyword: &str, rule: Rule) -> Result<()> {
+        if self.keyword()? == Some(keyword) {
+            self.bump()?;
+            self.open()
+        } else {
+            Err(self.expected(rule))
+        }
+    }
+
+    /// Read elements with `f` until a closing parenthesis.
+    ///
+    /// At least `min` elements must be read, otherwise an error expecting
+    /// `rule` is returned.
+    fn list<T, F>(&mut self, min: usize, rule: Rule, mut f: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Self) -> Result<T>,
+    {
+        let mut elements = Vec::new();
+        while !self.at_close()? || elements.len() < min {
+            if self.at_close()? {
+                return Err(self.expected(rule));
+            }
+            elements.push(f(self)?);
+        }
+        Ok(elements)
+    }
+}
+
+/// Create an error for when `rule` was expected at the given position.
+fn expected_at(input: &str, pos: usize, rule: Rule) -> Error {
+    Error::from(pest::error::Error::new_from_pos(
+        pest::error::ErrorVariant::ParsingError {
+            positives: vec![rule],
+            negatives: vec![],
+        },
+        pest::Position::new(input, pos).unwrap(),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+
+/// A trait for OWL elements that can be read by the recursive-descent parser.
+pub(crate) trait FromTokens<A: ForIRI>: Sized {
+    /// Read an instance from the next tokens of the parser.
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self>;
+}
+
+/// Parse a string into `T`, and check the whole string was consumed.
+pub(crate) fn parse_exact<A, T>(s: &str, ctx: &Context<'_, A>) -> Result<T>
+where
+    A: ForIRI,
+    T: FromTokens<A> + FromPair<A>,
+{
+    // `pest` does not allow leading whitespace outside of documents
+    if skip_trivia(s).unwrap_or(s.len()) > 0 {
+        return Err(expected_at(s, 0, T::RULE));
+    }
+    ctx.limits.check_input(s)?;
+    let mut p = Parser::new(s);
+    let value = T::from_tokens(&mut p, ctx)?;
+    if p.end == s.len() {
+        Ok(value)
+    } else {
+        Err(Error::custom(
+            "remaining input",
+            pest::Span::new(s, p.end, s.len()).unwrap(),
+        ))
+    }
+}
+
+/// Parse an entire OWL document, using the recursive-descent parser.
+///
+/// The prefix declarations are read first, then the ontology is parsed by
+/// its own `FromFunctional` implementation with the declared prefixes.
+pub(crate) fn parse_document<A, O>(s: &str, ctx: &Context<'_, A>) -> Result<(O, PrefixMapping)>
+where
+    A: ForIRI,
+    O: FromFunctional<A>,
+{
+    ctx.limits.check_input(s)?;
+    let mut p = Parser::new(s);
+    let mut prefixes = PrefixMapping::default();
+    while p.keyword()? == Some("Prefix") {
+        declare_prefix(&mut p, ctx, &mut prefixes)?;
+    }
+    if p.keyword()? != Some("Ontology") {
+        return Err(p.expected(Rule::Ontology));
+    }
+
+    let start = p.location().0;
+    let end = match next_element(&s[start..], true) {
+        Scan::Element(range) => start + range.end,
+        _ => s.len(),
+    };
+    let mut context = ctx.with_prefixes(&prefixes);
+    // report progress relative to the whole document
+    let shifted;
+    if let Some(callback) = ctx.progress {
+        shifted = move |p: Progress| {
+            callback(Progress {
+                bytes: start + p.bytes,
+                ..p
+            })
+        };
+        context.progress = Some(&shifted);
+    }
+    // and report warnings relative to the whole document as well
+    let relocated;
+    if let Some(callback) = ctx.warnings {
+        relocated = move |w: Warning| callback(w.shift_in(s, start));
+        context.warnings = Some(&relocated);
+    }
+    let ontology = O::from_ofn_ctx(&s[start..end], &context).map_err(|e| e.shift_in(s, start))?;
+
+    match skip_trivia(&s[end..]) {
+        None => Ok((ontology, prefixes)),
+        Some(i) => Err(expected_at(s, end + i, Rule::EOI)),
+    }
+}
+
+/// Read a `Prefix` declaration and add it to the prefix mapping.
+fn declare_prefix<A: ForIRI>(
+    p: &mut Parser<'_>,
+    ctx: &Context<'_, A>,
+    prefixes: &mut PrefixMapping,
+) -> Result<()> {
+    p.start("Prefix", Rule::PrefixDeclaration)?;
+    let prefix = match p.peek()? {
+        Some(Token::Word(w)) if w.ends_with(':') => match &w[..w.len() - 1] {
+            "" => None,
+            prefix if is_pn_prefix(prefix) => Some(prefix),
+            _ => return Err(p.expected(Rule::PrefixName)),
+        },
+        _ => return Err(p.expected(Rule::PrefixName)),
+    };
+    let span = p.span();
+    p.bump()?;
+    p.expect(Token::Eq, "expected `=`")?;
+    let iri = match p.peek()? {
+        Some(Token::FullIRI(iri)) if is_iri(iri) => iri,
+        _ => return Err(p.expected(Rule::FullIRI)),
+    };
+    p.bump()?;
+    p.close()?;
+
+    check_prefix(ctx, prefixes, prefix, || Some(Location::from_span(&span)));
+    match prefix {
+        Some(prefix) => prefixes
+            .add_prefix(prefix, iri)
+            .map_err(|_| Error::custom("reserved prefix", span)),
+        None => {
+            prefixes.set_default(iri);
+            Ok(())
+        }
+    }
+}
+
+/// Read an `Ontology` element into a sink.
+pub(crate) fn read_ontology<A, S>(
+    p: &mut Parser<'_>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+) -> Result<()>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+{
+    p.start("Ontology", Rule::Ontology)?;
+
+    // Parse ontology IRI and Version IRI if any
+    let mut ontology_id = OntologyID::default();
+    if p.at_iri()? {
+        ontology_id.iri = Some(IRI::from_tokens(p, ctx)?);
+        if p.at_iri()? {
+            ontology_id.viri = Some(IRI::from_tokens(p, ctx)?);
+        }
+    }
+    sink.ontology_id(ontology_id);
+
+    // Process imports
+    while p.keyword()? == Some("Import") {
+        sink.import(Import::from_tokens(p, ctx)?);
+    }
+
+    // Process ontology annotations
+    while p.keyword()? == Some("Annotation") {
+        sink.ontology_annotation(OntologyAnnotation::from_tokens(p, ctx)?);
+    }
+
+    // Process axioms, SWRL rules and description graphs
+    let mut count = 0;
+    let mut seen = SeenAxioms::new(ctx);
+    while !p.at_close()? {
+        let start = p.location().0;
+        ctx.progress(start, count)?;
+        count += 1;
+        ctx.limits.check_axioms(count, p.span())?;
+        match p.keyword()? {
+            Some("DLSafeRule") => {
+                let kept = sink.rule(DLSafeRule::from_tokens(p, ctx)?);
+                check_kept(ctx, kept, WarningKind::DroppedRule, || {
+                    p.location_from(start)
+                });
+            }
+            Some("DescriptionGraphRule") => {
+                let kept = sink.graph_rule(DescriptionGraphRule::from_tokens(p, ctx)?);
+                let kind = WarningKind::DroppedDescriptionGraph;
+                check_kept(ctx, kept, kind, || p.location_from(start));
+            }
+            Some("DescriptionGraph") => {
+                let kept = sink.description_graph(DescriptionGraph::from_tokens(p, ctx)?);
+                let kind = WarningKind::DroppedDescriptionGraph;
+                check_kept(ctx, kept, kind, || p.location_from(start));
+            }
+            _ => {
+                let axiom = AnnotatedAxiom::from_tokens(p, ctx)?;
+                seen.check(ctx, &axiom, || p.location_from(start));
+                sink.axiom(axiom);
+            }
+        }
+    }
+
+    p.close()?;
+    ctx.progress(p.end, count)
+}
+
+// ---------------------------------------------------------------------------
+
+#[inline]
+fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z'
+        | 'a'..='z'
+        | '\u{00C0}'..='\u{00D6}'
+        | '\u{00D8}'..='\u{00F6}'
+        | '\u{00F8}'..='\u{02FF}'
+        | '\u{0370}'..='\u{037D}'
+        | '\u{037F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+#[inline]
+fn is_pn_chars_u(c: char) -> bool {
+    is_pn_chars_base(c) || c == '_'
+}
+
+#[inline]
+fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_u(c)
+        || c.is_ascii_digit()
+        || matches!(c, '-' | '\u{00B7}' | '\u{0300}'..='\u{036F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// Check whether `s` is made of `PnChars`, with single dots in between.
+fn is_pn_tail(s: &str) -> bool {
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '.' => chars.next().unwrap_or('.'),
+            c => c,
+        };
+        if !is_pn_chars(c) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Check whether `s` is a valid SPARQL `PN_PREFIX`.
+fn is_pn_prefix(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if is_pn_chars_base(c)) && is_pn_tail(chars.as_str())
+}
+
+/// Check whether `s` is a valid SPARQL `PN_LOCAL`.
+fn is_pn_local(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if is_pn_chars_u(c) || c.is_ascii_digit())
+        && is_pn_tail(chars.as_str())
+}
+
+/// Split a prefixed name into its prefix and local part, if it is valid.
+pub(crate) fn split_pname(s: &str) -> Option<(Option<&str>, &str)> {
+    let (prefix, local) = s.split_at(s.find(':')?);
+    let local = &local[1..];
+    if !is_pn_local(local) {
+        None
+    } else if prefix.is_empty() {
+        Some((None, local))
+    } else if is_pn_prefix(prefix) {
+        Some((Some(prefix), local))
+    } else {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+#[inline]
+fn is_ucs_char(c: char) -> bool {
+    matches!(c,
+        '\u{A0}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFEF}'
+        | '\u{10000}'..='\u{1FFFD}'
+        | '\u{20000}'..='\u{2FFFD}'
+        | '\u{30000}'..='\u{3FFFD}'
+        | '\u{40000}'..='\u{4FFFD}'
+        | '\u{50000}'..='\u{5FFFD}'
+        | '\u{60000}'..='\u{6FFFD}'
+        | '\u{70000}'..='\u{7FFFD}'
+        | '\u{80000}'..='\u{8FFFD}'
+        | '\u{90000}'..='\u{9FFFD}'
+        | '\u{A0000}'..='\u{AFFFD}'
+        | '\u{B0000}'..='\u{BFFFD}'
+        | '\u{C0000}'..='\u{CFFFD}'
+        | '\u{D0000}'..='\u{DFFFD}'
+        | '\u{E1000}'..='\u{EFFFD}'
+    )
+}
+
+#[inline]
+fn is_private(c: char) -> bool {
+    matches!(c,
+        '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{FFFFD}' | '\u{100000}'..='\u{10FFFD}'
+    )
+}
+
+/// Check whether `c` is an `ipchar`, without percent-encoding.
+#[inline]
+fn is_ip_char(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || is_ucs_char(c)
+        || matches!(
+            c,
+            '-' | '.'
+                | '_'
+                | '~'
+                | '!'
+                | '$'
+                | '&'
+                | '\''
+                | '('
+                | ')'
+                | '*'
+                | '+'
+                | ';'
+                | '='
+                | ','
+                | ':'
+                | '@'
+        )
+}
+
+/// Check whether `s` is a valid absolute IRI, as defined in RFC 3987.
+///
+/// This accepts all the IRIs accepted by the `pest` grammar, but is more
+/// lenient about the structure of the authority.
+fn is_iri(s: &str) -> bool {
+    let (scheme, rest) = match s.find(':') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => return false,
+    };
+
+    let mut chars = scheme.chars();
+    if !matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        || !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        return false;
+    }
+
+    // the hierarchical part cannot be empty
+    if !matches!(rest.chars().next(), Some(c) if c == '/' || c == '%' || is_ip_char(c)) {
+        return false;
+    }
+
+    let mut fragment = false;
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {
+                let mut hex = chars.by_ref().take(2).filter(char::is_ascii_hexdigit);
+                if hex.next().is_none() || hex.next().is_none() {
+                    return false;
+                }
+            }
+            '#' if !fragment => fragment = true,
+            '/' | '?' => (),
+            '[' | ']' if !fragment => (),
+            c if is_ip_char(c) || is_private(c) => (),
+            _ => return false,
+        }
+    }
+    true
+}
+
+// ---------------------------------------------------------------------------
+
+/// Match the longest run of `min` to `max` characters from `s` with `f`.
+fn run(s: &[u8], i: usize, min: usize, max: usize, f: fn(&u8) -> bool) -> Option<usize> {
+    let n = s[i.min(s.len())..]
+        .iter()
+        .take(max)
+        .take_while(|b| f(b))
+        .count();
+    if n >= min {
+        Some(i + n)
+    } else {
+        None
+    }
+}
+
+/// Match `"-" ~ f` at position `i`.
+fn dash(s: &[u8], i: usize, f: impl Fn(usize) -> Option<usize>) -> Option<usize> {
+    if s.get(i) == Some(&b'-') {
+        f(i + 1)
+    } else {
+        None
+    }
+}
+
+/// Match `("-" ~ f)*` at position `i`.
+fn dashes(s: &[u8], mut i: usize, max: usize, f: impl Fn(usize) -> Option<usize>) -> usize {
+    for _ in 0..max {
+        match dash(s, i, &f) {
+            Some(j) => i = j,
+            None => break,
+        }
+    }
+    i
+}
+
+fn bcp47_private_use(s: &[u8], i: usize) -> Option<usize> {
+    if s.get(i) != Some(&b'x') {
+        return None;
+    }
+    let alnum = |i| run(s, i, 1, 8, u8::is_ascii_alphanumeric);
+    dash(s, i + 1, alnum).map(|j| dashes(s, j, usize::MAX, alnum))
+}
+
+fn bcp47_lang_tag(s: &[u8]) -> Option<usize> {
+    let alpha = u8::is_ascii_alphabetic;
+    let alnum = u8::is_ascii_alphanumeric;
+    let digit = u8::is_ascii_digit;
+
+    // language, with an optional extended language
+    let mut i = run(s, 0, 2, 3, alpha)?;
+    i = dash(s, i, |i| {
+        run(s, i, 3, 3, alpha).map(|j| dashes(s, j, 2, |j| run(s, j, 3, 3, alpha)))
+    })
+    .unwrap_or(i);
+    // script and region
+    i = dash(s, i, |i| run(s, i, 4, 4, alpha)).unwrap_or(i);
+    i = dash(s, i, |i| {
+        run(s, i, 2, 2, alpha).or_else(|| run(s, i, 3, 3, digit))
+    })
+    .unwrap_or(i);
+    // variants
+    i = dashes(s, i, usize::MAX, |i| {
+        run(s, i, 5, 8, alnum)
+            .or_else(|| run(s, i, 1, 1, digit).and_then(|j| run(s, j, 3, 3, alnum)))
+    });
+    // extensions
+    i = dashes(s, i, usize::MAX, |i| {
+        let singleton = |b: &u8| b.is_ascii_alphanumeric() && !matches!(b, b'x' | b'X');
+        run(s, i, 1, 1, singleton).and_then(|j| {
+            dash(s, j, |j| run(s, j, 2, 8, alnum))
+                .map(|k| dashes(s, k, usize::MAX, |k| run(s, k, 2, 8, alnum)))
+        })
+    });
+    // private use
+    Some(dash(s, i, |i| bcp47_private_use(s, i)).unwrap_or(i))
+}
+
+/// Check whether `s` is a valid BCP 47 language tag.
+///
+/// The tag is matched exactly like the `pest` grammar does, alternatives
+/// being tried in order, so the same tags are accepted by both backends.
+fn is_language_tag(s: &str) -> bool {
+    const GRANDFATHERED: &[&str] = &[
+        "en-GB-oed",
+        "i-ami",
+        "i-bnn",
+        "i-default",
+        "i-enochian",
+        "i-hak",
+        "i-klingon",
+        "i-lux",
+        "i-mingo",
+        "i-navajo",
+        "i-pwn",
+        "i-tao",
+        "i-tay",
+        "i-tsu",
+        "sgn-BE-FR",
+        "sgn-BE-NL",
+        "sgn-CH-DE",
+        "art-lojban",
+        "cel-gaulish",
+        "no-bok",
+        "no-nyn",
+        "zh-guoyu",
+        "zh-hakka",
+        "zh-min",
+        "zh-min-nan",
+        "zh-xiang",
+    ];
+    let bytes = s.as_bytes();
+    let end = bcp47_lang_tag(bytes)
+        .or_else(|| bcp47_private_use(bytes, 0))
+        .or_else(|| {
+            GRANDFATHERED
+                .iter()
+                .find(|tag| s.starts_with(*tag))
+                .map(|tag| tag.len())
+        });
+    end == Some(s.len())
+}
+
+// ---------------------------------------------------------------------------
+
+macro_rules! impl_wrapper {
+    ($ty:ident) => {
+        impl<A: ForIRI> FromTokens<A> for $ty<A> {
+            fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+                IRI::from_tokens(p, ctx).map($ty)
+            }
+        }
+    };
+}
+
+impl_wrapper!(Class);
+impl_wrapper!(Datatype);
+impl_wrapper!(ObjectProperty);
+impl_wrapper!(DataProperty);
+impl_wrapper!(AnnotationProperty);
+impl_wrapper!(NamedIndividual);
+
+macro_rules! impl_declaration {
+    ($ty:ident, $keyword:literal, $rule:path) => {
+        impl<A: ForIRI> FromTokens<A> for $ty<A> {
+            fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+                p.start($keyword, $rule)?;
+                let entity = FromTokens::from_tokens(p, ctx)?;
+                p.close()?;
+                Ok($ty(entity))
+            }
+        }
+    };
+}
+
+impl_declaration!(Import, "Import", Rule::Import);
+impl_declaration!(DeclareClass, "Class", Rule::ClassDeclaration);
+impl_declaration!(DeclareDatatype, "Datatype", Rule::DatatypeDeclaration);
+impl_declaration!(
+    DeclareObjectProperty,
+    "ObjectProperty",
+    Rule::ObjectPropertyDeclaration
+);
+impl_declaration!(
+    DeclareDataProperty,
+    "DataProperty",
+    Rule::DataPropertyDeclaration
+);
+impl_declaration!(
+    DeclareAnnotationProperty,
+    "AnnotationProperty",
+    Rule::AnnotationPropertyDeclaration
+);
+impl_declaration!(
+    DeclareNamedIndividual,
+    "NamedIndividual",
+    Rule::NamedIndividualDeclaration
+);
+
+// ---------------------------------------------------------------------------
+
+/// Check whether a keyword starts an axiom.
+fn is_axiom(keyword: &str) -> bool {
+    matches!(
+        keyword,
+        "Declaration"
+            | "SubClassOf"
+            | "EquivalentClasses"
+            | "DisjointClasses"
+            | "DisjointUnion"
+            | "SubObjectPropertyOf"
+            | "EquivalentObjectProperties"
+            | "DisjointObjectProperties"
+            | "ObjectPropertyDomain"
+            | "ObjectPropertyRange"
+            | "InverseObjectProperties"
+            | "FunctionalObjectProperty"
+            | "InverseFunctionalObjectProperty"
+            | "ReflexiveObjectProperty"
+            | "IrreflexiveObjectProperty"
+            | "SymmetricObjectProperty"
+            | "AsymmetricObjectProperty"
+            | "TransitiveObjectProperty"
+            | "SubDataPropertyOf"
+            | "EquivalentDataProperties"
+            | "DisjointDataProperties"
+            | "DataPropertyDomain"
+            | "DataPropertyRange"
+            | "FunctionalDataProperty"
+            | "DatatypeDefinition"
+            | "HasKey"
+            | "SameIndividual"
+            | "DifferentIndividuals"
+            | "ClassAssertion"
+            | "ObjectPropertyAssertion"
+            | "NegativeObjectPropertyAssertion"
+            | "DataPropertyAssertion"
+            | "NegativeDataPropertyAssertion"
+            | "AnnotationAssertion"
+            | "SubAnnotationPropertyOf"
+            | "AnnotationPropertyDomain"
+            | "AnnotationPropertyRange"
+    )
+}
+
+impl<A: ForIRI> FromTokens<A> for AnnotatedAxiom<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        let keyword = match p.keyword()? {
+            Some(keyword) if is_axiom(keyword) => keyword,
+            _ => return Err(p.expected(Rule::Axiom)),
+        };
+        p.bump()?;
+        p.open()?;
+        let ann = BTreeSet::from_tokens(p, ctx)?;
+
+        let axiom: Axiom<A> = match keyword {
+            // Declaration
+            "Declaration" => match p.keyword()? {
+                Some("Class") => DeclareClass::from_tokens(p, ctx)?.into(),
+                Some("Datatype") => DeclareDatatype::from_tokens(p, ctx)?.into(),
+                Some("ObjectProperty") => DeclareObjectProperty::from_tokens(p, ctx)?.into(),
+                Some("DataProperty") => DeclareDataProperty::from_tokens(p, ctx)?.into(),
+                Some("AnnotationProperty") => {
+                    DeclareAnnotationProperty::from_tokens(p, ctx)?.into()
+                }
+                Some("NamedIndividual") => DeclareNamedIndividual::from_tokens(p, ctx)?.into(),
+                _ => return Err(p.expected(Rule::Entity)),
+            },
+
+            // ClassAxiom
+            "SubClassOf" => {
+                let subcls = ClassExpression::from_tokens(p, ctx)?;
+                let supercls = ClassExpression::from_tokens(p, ctx)?;
+                SubClassOf::new(supercls, subcls).into()
+            }
+            "EquivalentClasses" => {
+                let ce = p.list(2, Rule::ClassExpression, |p| {
+                    ClassExpression::from_tokens(p, ctx)
+                })?;
+                EquivalentClasses(ce).into()
+            }
+            "DisjointClasses" => {
+                let ce = p.list(2, Rule::ClassExpression, |p| {
+                    ClassExpression::from_tokens(p, ctx)
+                })?;
+                DisjointClasses(ce).into()
+            }
+            "DisjointUnion" => {
+                let cls = Class::from_tokens(p, ctx)?;
+                let ce = p.list(2, Rule::ClassExpression, |p| {
+                    ClassExpression::from_tokens(p, ctx)
+                })?;
+                DisjointUnion(cls, ce).into()
+            }
+
+            // ObjectPropertyAxiom
+            "SubObjectPropertyOf" => {
+                let sub = SubObjectPropertyExpression::from_tokens(p, ctx)?;
+                let sup = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                SubObjectPropertyOf { sup, sub }.into()
+            }
+            "EquivalentObjectProperties" => {
+                let ops = p.list(2, Rule::ObjectPropertyExpression, |p| {
+                    ObjectPropertyExpression::from_tokens(p, ctx)
+                })?;
+                EquivalentObjectProperties(ops).into()
+            }
+            "DisjointObjectProperties" => {
+                let ops = p.list(2, Rule::ObjectPropertyExpression, |p| {
+                    ObjectPropertyExpression::from_tokens(p, ctx)
+                })?;
+                DisjointObjectProperties(ops).into()
+            }
+            "ObjectPropertyDomain" => {
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let ce = ClassExpression::from_tokens(p, ctx)?;
+                ObjectPropertyDomain::new(ope, ce).into()
+            }
+            "ObjectPropertyRange" => {
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let ce = ClassExpression::from_tokens(p, ctx)?;
+                ObjectPropertyRange::new(ope, ce).into()
+            }
+            "InverseObjectProperties" => {
+                let r1 = ObjectProperty::from_tokens(p, ctx)?;
+                let r2 = ObjectProperty::from_tokens(p, ctx)?;
+                InverseObjectProperties(r1, r2).into()
+            }
+            "FunctionalObjectProperty" => {
+                FunctionalObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+            "InverseFunctionalObjectProperty" => {
+                InverseFunctionalObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+            "ReflexiveObjectProperty" => {
+                ReflexiveObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+            "IrreflexiveObjectProperty" => {
+                IrreflexiveObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+            "SymmetricObjectProperty" => {
+                SymmetricObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+            "AsymmetricObjectProperty" => {
+                AsymmetricObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+            "TransitiveObjectProperty" => {
+                TransitiveObjectProperty(FromTokens::from_tokens(p, ctx)?).into()
+            }
+
+            // DataPropertyAxiom
+            "SubDataPropertyOf" => {
+                let sub = FromTokens::from_tokens(p, ctx)?;
+                let sup = FromTokens::from_tokens(p, ctx)?;
+                SubDataPropertyOf { sub, sup }.into()
+            }
+            "EquivalentDataProperties" => {
+                let dps = p.list(2, Rule::DataProperty, |p| DataProperty::from_tokens(p, ctx))?;
+                EquivalentDataProperties(dps).into()
+            }
+            "DisjointDataProperties" => {
+                let dps = p.list(2, Rule::DataProperty, |p| DataProperty::from_tokens(p, ctx))?;
+                DisjointDataProperties(dps).into()
+            }
+            "DataPropertyDomain" => {
+                let dp = DataProperty::from_tokens(p, ctx)?;
+                let ce = ClassExpression::from_tokens(p, ctx)?;
+                DataPropertyDomain::new(dp, ce).into()
+            }
+            "DataPropertyRange" => {
+                let dp = DataProperty::from_tokens(p, ctx)?;
+                let dr = DataRange::from_tokens(p, ctx)?;
+                DataPropertyRange::new(dp, dr).into()
+            }
+            "FunctionalDataProperty" => {
+                FunctionalDataProperty(DataProperty::from_tokens(p, ctx)?).into()
+            }
+            "DatatypeDefinition" => {
+                let k = Datatype::from_tokens(p, ctx)?;
+                let r = DataRange::from_tokens(p, ctx)?;
+                DatatypeDefinition::new(k, r).into()
+            }
+
+            // HasKey
+            "HasKey" => {
+                let ce = ClassExpression::from_tokens(p, ctx)?;
+                p.open()?;
+                let mut vpe = p.list(0, Rule::ObjectPropertyExpression, |p| {
+                    ObjectPropertyExpression::from_tokens(p, ctx)
+                        .map(PropertyExpression::ObjectPropertyExpression)
+                })?;
+                p.close()?;
+                p.open()?;
+                vpe.extend(p.list(0, Rule::DataProperty, |p| {
+                    DataProperty::from_tokens(p, ctx).map(PropertyExpression::DataProperty)
+                })?);
+                p.close()?;
+                HasKey::new(ce, vpe).into()
+            }
+
+            // Assertion
+            "SameIndividual" => {
+                let individuals =
+                    p.list(2, Rule::Individual, |p| Individual::from_tokens(p, ctx))?;
+                SameIndividual(individuals).into()
+            }
+            "DifferentIndividuals" => {
+                let individuals =
+                    p.list(2, Rule::Individual, |p| Individual::from_tokens(p, ctx))?;
+                DifferentIndividuals(individuals).into()
+            }
+            "ClassAssertion" => {
+                let ce = ClassExpression::from_tokens(p, ctx)?;
+                let i = Individual::from_tokens(p, ctx)?;
+                ClassAssertion::new(ce, i).into()
+            }
+            "ObjectPropertyAssertion" => {
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let from = Individual::from_tokens(p, ctx)?;
+                let to = Individual::from_tokens(p, ctx)?;
+                ObjectPropertyAssertion { ope, from, to }.into()
+            }
+            "NegativeObjectPropertyAssertion" => {
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let from = Individual::from_tokens(p, ctx)?;
+                let to = Individual::from_tokens(p, ctx)?;
+                NegativeObjectPropertyAssertion::new(ope, from, to).into()
+            }
+            "DataPropertyAssertion" => {
+                let dp = DataProperty::from_tokens(p, ctx)?;
+                let from = Individual::from_tokens(p, ctx)?;
+                let to = Literal::from_tokens(p, ctx)?;
+                DataPropertyAssertion::new(dp, from, to).into()
+            }
+            "NegativeDataPropertyAssertion" => {
+                let dp = DataProperty::from_tokens(p, ctx)?;
+                let from = Individual::from_tokens(p, ctx)?;
+                let to = Literal::from_tokens(p, ctx)?;
+                NegativeDataPropertyAssertion::new(dp, from, to).into()
+            }
+
+            // AnnotationAxiom
+            "AnnotationAssertion" => {
+                let ap = AnnotationProperty::from_tokens(p, ctx)?;
+                let subject = AnnotationSubject::from_tokens(p, ctx)?;
+                let av = AnnotationValue::from_tokens(p, ctx)?;
+                AnnotationAssertion::new(subject, Annotation { ap, av }).into()
+            }
+            "SubAnnotationPropertyOf" => {
+                let sub = FromTokens::from_tokens(p, ctx)?;
+                let sup = FromTokens::from_tokens(p, ctx)?;
+                SubAnnotationPropertyOf { sub, sup }.into()
+            }
+            "AnnotationPropertyDomain" => {
+                let ap = AnnotationProperty::from_tokens(p, ctx)?;
+                let iri = IRI::from_tokens(p, ctx)?;
+                AnnotationPropertyDomain::new(ap, iri).into()
+            }
+            "AnnotationPropertyRange" => {
+                let ap = AnnotationProperty::from_tokens(p, ctx)?;
+                let iri = IRI::from_tokens(p, ctx)?;
+                AnnotationPropertyRange::new(ap, iri).into()
+            }
+
+            keyword => unreachable!("unexpected keyword in AnnotatedAxiom: {:?}", keyword),
+        };
+
+        p.close()?;
+        Ok(AnnotatedAxiom { axiom, ann })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for Axiom<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        AnnotatedAxiom::from_tokens(p, ctx).map(|aa| aa.axiom)
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for Annotation<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.start("Annotation", Rule::Annotation)?;
+        // nested annotations are only kept in side tables, which this
+        // backend does not collect
+        let _annotations: BTreeSet<Annotation<A>> = FromTokens::from_tokens(p, ctx)?;
+        let ap = FromTokens::from_tokens(p, ctx)?;
+        let av = FromTokens::from_tokens(p, ctx)?;
+        p.close()?;
+        Ok(Annotation { ap, av })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for BTreeSet<Annotation<A>> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut annotations = BTreeSet::new();
+        while p.keyword()? == Some("Annotation") {
+            annotations.insert(Annotation::from_tokens(p, ctx)?);
+        }
+        Ok(annotations)
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for OntologyAnnotation<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        Annotation::from_tokens(p, ctx).map(OntologyAnnotation)
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for AnnotationSubject<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.at_blank_node()? {
+            AnonymousIndividual::from_tokens(p, ctx).map(AnnotationSubject::AnonymousIndividual)
+        } else if p.at_iri()? {
+            IRI::from_tokens(p, ctx).map(AnnotationSubject::IRI)
+        } else {
+            Err(p.expected(Rule::AnnotationSubject))
+        }
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for AnnotationValue<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.at_blank_node()? && ctx.strict {
+            Err(p.custom("anonymous annotation targets are not supported"))
+        } else if p.at_blank_node()? {
+            AnonymousIndividual::from_tokens(p, ctx).map(|anon| anonymous_value(&anon, ctx))
+        } else if p.at_iri()? {
+            IRI::from_tokens(p, ctx).map(AnnotationValue::IRI)
+        } else if let Some(Token::Quoted(_)) = p.peek()? {
+            Literal::from_tokens(p, ctx).map(AnnotationValue::Literal)
+        } else {
+            Err(p.expected(Rule::AnnotationValue))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for AnonymousIndividual<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        match p.peek()? {
+            Some(Token::Word(w)) if w.starts_with("_:") && is_pn_local(&w[2..]) => {
+                p.bump()?;
+                Ok(AnonymousIndividual(ctx.iri(&w[2..]).underlying()))
+            }
+            _ => Err(p.expected(Rule::AnonymousIndividual)),
+        }
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for Individual<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.at_blank_node()? {
+            AnonymousIndividual::from_tokens(p, ctx).map(Individual::Anonymous)
+        } else if p.at_iri()? {
+            NamedIndividual::from_tokens(p, ctx).map(Individual::Named)
+        } else {
+            Err(p.expected(Rule::Individual))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for IRI<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        match p.peek()? {
+            Some(Token::FullIRI(iri)) if is_iri(iri) => {
+                ctx.limits.check_iri(iri, p.span())?;
+                p.bump()?;
+                Ok(ctx.iri(iri))
+            }
+            Some(Token::Word(w)) => match split_pname(w) {
+                Some((prefix, local)) => {
+                    let span = p.span();
+                    p.bump()?;
+                    expand_curie(prefix, local, span, ctx)
+                }
+                None => Err(p.expected(Rule::IRI)),
+            },
+            _ => Err(p.expected(Rule::IRI)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for Literal<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        let literal = match p.peek()? {
+            Some(Token::Quoted(quoted)) => {
+                ctx.limits.check_literal(p.span())?;
+                unquote(quoted)
+            }
+            _ => return Err(p.expected(Rule::Literal)),
+        };
+        p.bump()?;
+        match p.peek()? {
+            Some(Token::Caret) => {
+                p.bump()?;
+                p.expect(Token::Caret, "expected `^`")?;
+                let dty = Datatype::from_tokens(p, ctx)?;
+                Ok(Literal::Datatype {
+                    literal,
+                    datatype_iri: dty.0,
+                })
+            }
+            Some(Token::LanguageTag(tag)) if is_language_tag(tag) => {
+                p.bump()?;
+                let lang = tag.to_string();
+                Ok(Literal::Language { literal, lang })
+            }
+            Some(Token::LanguageTag(_)) => Err(p.expected(Rule::LanguageTag)),
+            _ => Ok(Literal::Simple { literal }),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for u32 {
+    fn from_tokens(p: &mut Parser<'_>, _ctx: &Context<'_, A>) -> Result<Self> {
+        match p.peek()? {
+            Some(Token::Word(w)) if !w.is_empty() && w.bytes().all(|b| b.is_ascii_digit()) => {
+                let n = u32::from_str(w).map_err(|_| p.custom("integer is too large"))?;
+                p.bump()?;
+                Ok(n)
+            }
+            _ => Err(p.expected(Rule::NonNegativeInteger)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for ObjectPropertyExpression<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.at_iri()? {
+            ObjectProperty::from_tokens(p, ctx).map(ObjectPropertyExpression::ObjectProperty)
+        } else if p.keyword()? == Some("ObjectInverseOf") {
+            p.start("ObjectInverseOf", Rule::InverseObjectProperty)?;
+            let op = ObjectProperty::from_tokens(p, ctx)?;
+            p.close()?;
+            Ok(ObjectPropertyExpression::InverseObjectProperty(op))
+        } else {
+            Err(p.expected(Rule::ObjectPropertyExpression))
+        }
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for SubObjectPropertyExpression<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.keyword()? == Some("ObjectPropertyChain") {
+            p.start("ObjectPropertyChain", Rule::PropertyExpressionChain)?;
+            let objs = p.list(2, Rule::ObjectPropertyExpression, |p| {
+                ObjectPropertyExpression::from_tokens(p, ctx)
+            })?;
+            p.close()?;
+            Ok(SubObjectPropertyExpression::ObjectPropertyChain(objs))
+        } else {
+            ObjectPropertyExpression::from_tokens(p, ctx)
+                .map(SubObjectPropertyExpression::ObjectPropertyExpression)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for Facet {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.peek()?;
+        let span = p.span();
+        facet(&IRI::from_tokens(p, ctx)?, || {
+            Some(Location::from_span(&span))
+        })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for FacetRestriction<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        let f = Facet::from_tokens(p, ctx)?;
+        let l = Literal::from_tokens(p, ctx)?;
+        Ok(FacetRestriction { f, l })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for DataRange<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.at_iri()? {
+            return Datatype::from_tokens(p, ctx).map(DataRange::Datatype);
+        }
+
+        let keyword = p.keyword()?;
+        let rule = match keyword {
+            Some("DataIntersectionOf") => Rule::DataIntersectionOf,
+            Some("DataUnionOf") => Rule::DataUnionOf,
+            Some("DataComplementOf") => Rule::DataComplementOf,
+            Some("DataOneOf") => Rule::DataOneOf,
+            Some("DatatypeRestriction") => Rule::DatatypeRestriction,
+            _ => return Err(p.expected(Rule::DataRange)),
+        };
+        p.bump()?;
+        p.open()?;
+
+        let dr = match rule {
+            Rule::DataIntersectionOf => {
+                DataRange::DataIntersectionOf(
+                    p.list(2, Rule::DataRange, |p| Self::from_tokens(p, ctx))?,
+                )
+            }
+            Rule::DataUnionOf => {
+                DataRange::DataUnionOf(p.list(2, Rule::DataRange, |p| Self::from_tokens(p, ctx))?)
+            }
+            Rule::DataComplementOf => {
+                DataRange::DataComplementOf(Box::new(Self::from_tokens(p, ctx)?))
+            }
+            Rule::DataOneOf => {
+                DataRange::DataOneOf(p.list(1, Rule::Literal, |p| Literal::from_tokens(p, ctx))?)
+            }
+            _ => {
+                let dt = Datatype::from_tokens(p, ctx)?;
+                let restrictions = p.list(1, Rule::FacetRestriction, |p| {
+                    FacetRestriction::from_tokens(p, ctx)
+                })?;
+                DataRange::DatatypeRestriction(dt, restrictions)
+            }
+        };
+
+        p.close()?;
+        Ok(dr)
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+/// Read the data properties and the data range of a data quantifier.
+///
+/// Because the data range can be a datatype IRI, every IRI but the last one
+/// is a data property. Only one data property is supported by `horned-owl`.
+fn data_quantifier<A: ForIRI>(
+    p: &mut Parser<'_>,
+    ctx: &Context<'_, A>,
+    keyword: &str,
+) -> Result<(DataProperty<A>, DataRange<A>)> {
+    let mut dps = Vec::new();
+    let mut dr = None;
+    while p.at_iri()? {
+        let (start, _) = p.location();
+        let iri = IRI::from_tokens(p, ctx)?;
+        if p.at_close()? && !dps.is_empty() {
+            dr = Some(DataRange::Datatype(Datatype(iri)));
+            break;
+        }
+        dps.push((start, p.end, iri));
+    }
+
+    let dr = match dr {
+        Some(dr) => dr,
+        None => DataRange::from_tokens(p, ctx)?,
+    };
+    let mut dps = dps.into_iter();
+    let dp = match dps.next() {
+        Some((_, _, iri)) => DataProperty(iri),
+        None => return Err(p.expected(Rule::DataProperty)),
+    };
+    match dps.next() {
+        None => Ok((dp, dr)),
+        Some((start, end, _)) => Err(Error::custom(
+            format!("cannot use data property chaining in `{}`", keyword),
+            pest::Span::new(p.lexer.input(), start, end).unwrap(),
+        )),
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for ClassExpression<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.at_iri()? {
+            return Class::from_tokens(p, ctx).map(ClassExpression::Class);
+        }
+
+        let keyword = match p.keyword()? {
+            Some(keyword) => keyword,
+            None => return Err(p.expected(Rule::ClassExpression)),
+        };
+        let ce = match keyword {
+            "ObjectIntersectionOf" | "ObjectUnionOf" => {
+                p.bump()?;
+                p.open()?;
+                let ces = p.list(2, Rule::ClassExpression, |p| Self::from_tokens(p, ctx))?;
+                if keyword == "ObjectUnionOf" {
+                    ClassExpression::ObjectUnionOf(ces)
+                } else {
+                    ClassExpression::ObjectIntersectionOf(ces)
+                }
+            }
+            "ObjectComplementOf" => {
+                p.bump()?;
+                p.open()?;
+                ClassExpression::ObjectComplementOf(Box::new(Self::from_tokens(p, ctx)?))
+            }
+            "ObjectOneOf" => {
+                p.bump()?;
+                p.open()?;
+                let individuals =
+                    p.list(1, Rule::Individual, |p| Individual::from_tokens(p, ctx))?;
+                ClassExpression::ObjectOneOf(individuals)
+            }
+            "ObjectSomeValuesFrom" | "ObjectAllValuesFrom" => {
+                p.bump()?;
+                p.open()?;
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let bce = Box::new(Self::from_tokens(p, ctx)?);
+                if keyword == "ObjectSomeValuesFrom" {
+                    ClassExpression::ObjectSomeValuesFrom { ope, bce }
+                } else {
+                    ClassExpression::ObjectAllValuesFrom { ope, bce }
+                }
+            }
+            "ObjectHasValue" => {
+                p.bump()?;
+                p.open()?;
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let i = Individual::from_tokens(p, ctx)?;
+                ClassExpression::ObjectHasValue { ope, i }
+            }
+            "ObjectHasSelf" => {
+                p.bump()?;
+                p.open()?;
+                ClassExpression::ObjectHasSelf(ObjectPropertyExpression::from_tokens(p, ctx)?)
+            }
+            "ObjectMinCardinality" | "ObjectMaxCardinality" | "ObjectExactCardinality" => {
+                p.bump()?;
+                p.open()?;
+                let n = u32::from_tokens(p, ctx)?;
+                let ope = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let bce = if p.at_close()? {
+                    // Missing class expression is equivalent to `owl:Thing` as class expression.
+                    // see https://www.w3.org/TR/owl2-syntax/#Object_Property_Cardinality_Restrictions
+                    Box::new(ClassExpression::Class(Class(ctx.iri(OWL::Thing.iri_str()))))
+                } else {
+                    Box::new(Self::from_tokens(p, ctx)?)
+                };
+                match keyword {
+                    "ObjectMinCardinality" => ClassExpression::ObjectMinCardinality { n, ope, bce },
+                    "ObjectMaxCardinality" => ClassExpression::ObjectMaxCardinality { n, ope, bce },
+                    _ => ClassExpression::ObjectExactCardinality { n, ope, bce },
+                }
+            }
+            "DataSomeValuesFrom" | "DataAllValuesFrom" => {
+                p.bump()?;
+                p.open()?;
+                let (dp, dr) = data_quantifier(p, ctx, keyword)?;
+                if keyword == "DataSomeValuesFrom" {
+                    ClassExpression::DataSomeValuesFrom { dp, dr }
+                } else {
+                    ClassExpression::DataAllValuesFrom { dp, dr }
+                }
+            }
+            "DataHasValue" => {
+                p.bump()?;
+                p.open()?;
+                let dp = DataProperty::from_tokens(p, ctx)?;
+                let l = Literal::from_tokens(p, ctx)?;
+                ClassExpression::DataHasValue { dp, l }
+            }
+            "DataMinCardinality" | "DataMaxCardinality" | "DataExactCardinality" => {
+                p.bump()?;
+                p.open()?;
+                let n = u32::from_tokens(p, ctx)?;
+                let dp = DataProperty::from_tokens(p, ctx)?;
+                let dr = if p.at_close()? {
+                    // No data range is equivalent to `rdfs:Literal` as a data range.
+                    // see https://www.w3.org/TR/owl2-syntax/#Data_Property_Cardinality_Restrictions
+                    Datatype(ctx.iri(OWL2Datatype::RDFSLiteral.iri_str())).into()
+                } else {
+                    DataRange::from_tokens(p, ctx)?
+                };
+                match keyword {
+                    "DataMinCardinality" => ClassExpression::DataMinCardinality { n, dp, dr },
+                    "DataMaxCardinality" => ClassExpression::DataMaxCardinality { n, dp, dr },
+                    _ => ClassExpression::DataExactCardinality { n, dp, dr },
+                }
+            }
+            _ => return Err(p.expected(Rule::ClassExpression)),
+        };
+
+        p.close()?;
+        Ok(ce)
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for Variable<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.start("Variable", Rule::Variable)?;
+        let iri = IRI::from_tokens(p, ctx)?;
+        p.close()?;
+        Ok(Variable(iri))
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for IArg<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.keyword()? == Some("Variable") {
+            Variable::from_tokens(p, ctx).map(IArg::Variable)
+        } else if p.at_iri()? || p.at_blank_node()? {
+            Individual::from_tokens(p, ctx).map(IArg::Individual)
+        } else {
+            Err(p.expected(Rule::IArg))
+        }
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for DArg<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        if p.keyword()? == Some("Variable") {
+            Variable::from_tokens(p, ctx).map(DArg::Variable)
+        } else if let Some(Token::Quoted(_)) = p.peek()? {
+            Literal::from_tokens(p, ctx).map(DArg::Literal)
+        } else {
+            Err(p.expected(Rule::DArg))
+        }
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for Atom<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        let keyword = match p.keyword()? {
+            Some(keyword) => keyword,
+            None => return Err(p.expected(Rule::Atom)),
+        };
+        let atom = match keyword {
+            "ClassAtom" => {
+                p.bump()?;
+                p.open()?;
+                let pred = ClassExpression::from_tokens(p, ctx)?;
+                let arg = IArg::from_tokens(p, ctx)?;
+                Atom::ClassAtom { pred, arg }
+            }
+            "DataRangeAtom" => {
+                p.bump()?;
+                p.open()?;
+                let pred = DataRange::from_tokens(p, ctx)?;
+                let arg = DArg::from_tokens(p, ctx)?;
+                Atom::DataRangeAtom { pred, arg }
+            }
+            "ObjectPropertyAtom" => {
+                p.bump()?;
+                p.open()?;
+                let pred = ObjectPropertyExpression::from_tokens(p, ctx)?;
+                let args = (IArg::from_tokens(p, ctx)?, IArg::from_tokens(p, ctx)?);
+                Atom::ObjectPropertyAtom { pred, args }
+            }
+            "DataPropertyAtom" => {
+                p.bump()?;
+                p.open()?;
+                let pred = DataProperty::from_tokens(p, ctx)?;
+                let args = (IArg::from_tokens(p, ctx)?, DArg::from_tokens(p, ctx)?);
+                Atom::DataPropertyAtom { pred, args }
+            }
+            "BuiltInAtom" => {
+                p.bump()?;
+                p.open()?;
+                let pred = IRI::from_tokens(p, ctx)?;
+                let args = p.list(1, Rule::DArg, |p| DArg::from_tokens(p, ctx))?;
+                Atom::BuiltInAtom { pred, args }
+            }
+            "SameIndividualAtom" | "DifferentIndividualsAtom" => {
+                p.bump()?;
+                p.open()?;
+                let a = IArg::from_tokens(p, ctx)?;
+                let b = IArg::from_tokens(p, ctx)?;
+                if keyword == "SameIndividualAtom" {
+                    Atom::SameIndividualAtom(a, b)
+                } else {
+                    Atom::DifferentIndividualsAtom(a, b)
+                }
+            }
+            _ => return Err(p.expected(Rule::Atom)),
+        };
+
+        p.close()?;
+        Ok(atom)
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for DLSafeRule<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.start("DLSafeRule", Rule::DLSafeRule)?;
+        let ann = FromTokens::from_tokens(p, ctx)?;
+        p.start("Body", Rule::DLBody)?;
+        let body = p.list(0, Rule::Atom, |p| Atom::from_tokens(p, ctx))?;
+        p.close()?;
+        p.start("Head", Rule::DLHead)?;
+        let head = p.list(0, Rule::Atom, |p| Atom::from_tokens(p, ctx))?;
+        p.close()?;
+        p.close()?;
+        Ok(DLSafeRule { ann, body, head })
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromTokens<A> for DGNode<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        IRI::from_tokens(p, ctx).map(DGNode)
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for NodeAssertion<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.start("NodeAssertion", Rule::NodeAssertion)?;
+        let class = Class::from_tokens(p, ctx)?;
+        let node = DGNode::from_tokens(p, ctx)?;
+        p.close()?;
+        Ok(NodeAssertion { class, node })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for EdgeAssertion<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.start("EdgeAssertion", Rule::EdgeAssertion)?;
+        let property = ObjectProperty::from_tokens(p, ctx)?;
+        let from = DGNode::from_tokens(p, ctx)?;
+        let to = DGNode::from_tokens(p, ctx)?;
+        p.close()?;
+        Ok(EdgeAssertion { property, from, to })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for DescriptionGraph<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        p.start("DescriptionGraph", Rule::DGAxiom)?;
+        let ann = FromTokens::from_tokens(p, ctx)?;
+        p.start("Nodes", Rule::DGNodes)?;
+        let nodes = p.list(1, Rule::NodeAssertion, |p| {
+            NodeAssertion::from_tokens(p, ctx)
+        })?;
+        p.close()?;
+        p.start("Edges", Rule::DGEdges)?;
+        let edges = p.list(1, Rule::EdgeAssertion, |p| {
+            EdgeAssertion::from_tokens(p, ctx)
+        })?;
+        p.close()?;
+        p.start("MainClasses", Rule::MainClasses)?;
+        let main_classes = p.list(1, Rule::Class, |p| Class::from_tokens(p, ctx))?;
+        p.close()?;
+        p.close()?;
+        Ok(DescriptionGraph {
+            ann,
+            nodes,
+            edges,
+            main_classes,
+        })
+    }
+}
+
+impl<A: ForIRI> FromTokens<A> for DescriptionGraphRule<A> {
+    fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut atom = |p: &mut Parser<'_>| match p.keyword()? {
+            Some("ClassAtom") | Some("ObjectPropertyAtom") => Atom::from_tokens(p, ctx),
+            _ => Err(p.expected(Rule::DGAtom)),
+        };
+        p.start("DescriptionGraphRule", Rule::DGRule)?;
+        let ann = FromTokens::from_tokens(p, ctx)?;
+        p.start("Body", Rule::DGBody)?;
+        let body = p.list(0, Rule::DGAtom, &mut atom)?;
+        p.close()?;
+        p.start("Head", Rule::DGHead)?;
+        let head = p.list(0, Rule::DGAtom, &mut atom)?;
+        p.close()?;
+        p.close()?;
+        Ok(DescriptionGraphRule { ann, body, head })
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+
+    fn prefixes() -> PrefixMapping {
+        let mut prefixes = PrefixMapping::default();
+        prefixes
+            .add_prefix("owl", "http://www.w3.org/2002/07/owl#")
+            .unwrap();
+        prefixes
+            .add_prefix("xsd", "http://www.w3.org/2001/XMLSchema#")
+            .unwrap();
+        prefixes.add_prefix("ex", "http://example.com/").unwrap();
+        prefixes
+    }
+
+    /// Check both backends give the same result on the given input.
+    macro_rules! assert_same {
+        ($ty:ty, $doc:expr) => {
+            let build = Build::new();
+            let prefixes = prefixes();
+            let ctx = Context::new(&build, &prefixes);
+            let expected = <$ty as FromFunctional<String>>::from_ofn_ctx($doc, &ctx).unwrap();
+            let actual = parse_exact::<String, $ty>($doc, &ctx).unwrap();
+            assert_eq!(actual, expected);
+        };
+    }
+
+    #[test]
+    fn pname() {
+        assert_eq!(split_pname("ex:A"), Some((Some("ex"), "A")));
+        assert_eq!(split_pname(":A.b"), Some((None, "A.b")));
+        assert_eq!(split_pname("ex:A."), None);
+        assert_eq!(split_pname("ex:"), None);
+        assert_eq!(split_pname("1x:A"), None);
+        assert_eq!(split_pname("ex:1"), Some((Some("ex"), "1")));
+    }
+
+    #[test]
+    fn iri() {
+        assert!(is_iri("http://example.com/a(b)#c"));
+        assert!(is_iri("urn:isbn:0451450523"));
+        assert!(is_iri("http://example.com/%C3%A9"));
+        assert!(!is_iri("http://example.com/%C3%A"));
+        assert!(!is_iri("http://example.com/a b"));
+        assert!(!is_iri("http://example.com/#a#b"));
+        assert!(!is_iri("example.com"));
+        assert!(!is_iri("http:"));
+    }
+
+    #[test]
+    fn language_tag() {
+        use crate::parser::OwlFunctionalParser;
+
+        let tags = [
+            "en",
+            "en-GB",
+            "es-419",
+            "de-CH-1901",
+            "zh-Hant-TW",
+            "x-private",
+            "i-klingon",
+            "en-GB-oed",
+            "english",
+            "en-",
+            "e",
+            "en-a-bbb-x-ccc",
+        ];
+        for tag in tags {
+            let doc = format!("@{}", tag);
+            let expected = OwlFunctionalParser::parse(Rule::LanguageTag, &doc)
+                .map(|pairs| pairs.as_str().len() == doc.len())
+                .unwrap_or(false);
+            assert_eq!(is_language_tag(tag), expected, "{}", tag);
+        }
+    }
+
+    #[test]
+    fn class_expression() {
+        assert_same!(ClassExpression<String>, "ex:A");
+        assert_same!(
+            ClassExpression<String>,
+            "ObjectIntersectionOf(ex:A ObjectSomeValuesFrom(ObjectInverseOf(ex:p) ex:B))"
+        );
+        assert_same!(ClassExpression<String>, "ObjectMinCardinality(1 ex:p)");
+        assert_same!(
+            ClassExpression<String>,
+            "DataMaxCardinality(2 ex:d xsd:string)"
+        );
+        assert_same!(
+            ClassExpression<String>,
+            "DataSomeValuesFrom(ex:d xsd:string)"
+        );
+        assert_same!(
+            ClassExpression<String>,
+            "DataAllValuesFrom(ex:d DataOneOf(\"a\" \"b\"@en \"1\"^^xsd:integer))"
+        );
+    }
+
+    #[test]
+    fn axiom() {
+        assert_same!(
+            AnnotatedAxiom<String>,
+            "SubClassOf(Annotation(ex:a \"x\") ex:A ObjectUnionOf(ex:B ex:C))"
+        );
+        assert_same!(
+            AnnotatedAxiom<String>,
+            "HasKey(owl:Thing (ex:p ObjectInverseOf(ex:q)) (ex:d))"
+        );
+        assert_same!(
+            AnnotatedAxiom<String>,
+            "SubObjectPropertyOf(ObjectPropertyChain(ex:p ex:q) ex:r)"
+        );
+        assert_same!(
+            AnnotatedAxiom<String>,
+            "DatatypeDefinition(ex:t DatatypeRestriction(xsd:integer xsd:minInclusive \"0\"^^xsd:integer))"
+        );
+        assert_same!(
+            AnnotatedAxiom<String>,
+            "SameIndividual( ex:a # comment\n _:b )"
+        );
+    }
+
+    #[test]
+    fn ontology() {
+        let doc = "Prefix(:=<http://example.com/>)\nOntology(:o\n  Import(:p)\n  Annotation(:a \"x\")\n  DLSafeRule(Body() Head())\n  Declaration(Class(:A))\n)\n# end\n";
+        let expected = <(SetOntology<String>, PrefixMapping)>::from_ofn(doc).unwrap();
+        let ctx = Context::default().with_backend(crate::Backend::Descent);
+        let actual = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx).unwrap();
+        assert_eq!(actual.0, expected.0);
+        assert_eq!(actual.1, expected.1);
+    }
+
+    #[test]
+    fn errors() {
+        let ctx = Context::default();
+        assert!(parse_exact::<String, ClassExpression<String>>(" <http://a.com/>", &ctx).is_err());
+        assert!(parse_exact::<String, IRI<String>>("<http://a.com/> ", &ctx).is_err());
+        assert!(parse_exact::<String, IRI<String>>("ex:A", &ctx).is_err());
+        assert!(parse_exact::<String, ClassExpression<String>>(
+            "ObjectMinCardinality(4294967296 <http://a.com/>)",
+            &ctx
+        )
+        .is_err());
+        assert!(parse_exact::<String, ClassExpression<String>>(
+            "DataSomeValuesFrom(<http://a.com/d> <http://a.com/e> <http://a.com/t>)",
+            &ctx
+        )
+        .is_err());
+
+        let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n  SubClassOf(<http://example.com/A>)\n)";
+        let expected = match <(SetOntology<String>, PrefixMapping)>::from_ofn(doc) {
+            Err(Error::Pest(e)) => e,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let ctx = Context::default().with_backend(crate::Backend::Descent);
+        match <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx) {
+            Err(Error::Pest(e)) => assert_eq!(e.line_col, expected.line_col),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expansion_errors() {
+        let doc = "Prefix(obo:=<http://purl.obolibrary.org/obo/>)\nOntology(\n  Declaration(Class(ob:A))\n)";
+        let descent = Context::default().with_backend(crate::Backend::Descent);
+        let expected = match <(SetOntology<String>, PrefixMapping)>::from_ofn(doc) {
+            Err(Error::Expansion(e)) => e,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(expected.curie, "ob:A");
+        assert_eq!(expected.suggestion.as_deref(), Some("obo"));
+        let location = expected.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column), (3, 21));
+        assert_eq!(&doc[location.span.clone()], "ob:A");
+        match <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &descent) {
+            Err(Error::Expansion(e)) => assert_eq!(e, expected),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let facet = "DatatypeRestriction(<http://a.com/t> <http://a.com/f> \"1\")";
+        let expected = match DataRange::<String>::from_ofn(facet) {
+            Err(Error::InvalidFacet(e)) => e,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(expected.iri, "http://a.com/f");
+        assert_eq!(expected.location.as_ref().unwrap().span, 37..53);
+        match DataRange::<String>::from_ofn_ctx(facet, &descent) {
+            Err(Error::InvalidFacet(e)) => assert_eq!(e, expected),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Description graphs and their rules, an OWL extension describing
+//! structured objects with a graph of classes.
+//!
+//! They are collected in [`SideTables::description_graphs`] when parsing,
+//! since `horned-owl` ontologies cannot store them, and written back when
+//! serializing an ontology with [`Context::with_description_graphs`].
+//!
+//! [`SideTables::description_graphs`]: crate::SideTables::description_graphs
+//! [`Context::with_description_graphs`]: crate::Context::with_description_graphs
+
+use std::collections::BTreeSet;
+
+use horned_owl::model::*;
+
+use crate::swrl::Atom;
+
+/// A node of a description graph.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DGNode<A>(pub IRI<A>);
+
+/// An assertion that a node of a description graph is an instance of a class.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeAssertion<A> {
+    /// The class of the node.
+    pub class: Class<A>,
+    /// The node.
+    pub node: DGNode<A>,
+}
+
+/// An assertion that two nodes of a description graph are connected.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct EdgeAssertion<A> {
+    /// The object property labelling the edge.
+    pub property: ObjectProperty<A>,
+    /// The source node of the edge.
+    pub from: DGNode<A>,
+    /// The target node of the edge.
+    pub to: DGNode<A>,
+}
+
+/// A description graph.
+///
+/// # Example
+/// ```rust
+/// use horned_functional::dg::DescriptionGraph;
+/// use horned_functional::AsFunctional;
+/// use horned_functional::FromFunctional;
+///
+/// let text = "DescriptionGraph(Nodes(NodeAssertion(<http://a.com/A> <http://a.com/n>)) Edges(EdgeAssertion(<http://a.com/r> <http://a.com/n> <http://a.com/n>)) MainClasses(<http://a.com/A>))";
+/// let graph = DescriptionGraph::<String>::from_ofn(text).unwrap();
+/// assert_eq!(graph.nodes.len(), 1);
+/// assert_eq!(graph.as_ofn().to_string(), text);
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescriptionGraph<A> {
+    /// The annotations of the graph.
+    pub ann: BTreeSet<Annotation<A>>,
+    /// The classes of the nodes of the graph.
+    pub nodes: Vec<NodeAssertion<A>>,
+    /// The edges between the nodes of the graph.
+    pub edges: Vec<EdgeAssertion<A>>,
+    /// The classes whose instances are described by the graph.
+    pub main_classes: Vec<Class<A>>,
+}
+
+/// A rule of description graphs.
+///
+/// Only class and object property atoms can appear in the body and head
+/// of a graph rule.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DescriptionGraphRule<A> {
+    /// The annotations of the rule.
+    pub ann: BTreeSet<Annotation<A>>,
+    /// The atoms which must hold for the rule to apply.
+    pub body: Vec<Atom<A>>,
+    /// The atoms inferred when the rule applies.
+    pub head: Vec<Atom<A>>,
+}
+
+/// The description graphs and graph rules of an OWL functional document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescriptionGraphs<A> {
+    /// The description graphs, in document order.
+    pub graphs: Vec<DescriptionGraph<A>>,
+    /// The description graph rules, in document order.
+    pub rules: Vec<DescriptionGraphRule<A>>,
+}
+
+impl<A> Default for DescriptionGraphs<A> {
+    fn default() -> Self {
+        Self {
+            graphs: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use curie::PrefixMapping;
+
+    use super::*;
+    use crate::side_tables::collect;
+    use crate::side_tables::roundtrip;
+    use crate::AsFunctional;
+    use crate::Backend;
+    use crate::Context;
+    use crate::FromFunctional;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Prefix(var:=<urn:swrl#>)
+Ontology(:o
+Declaration(Class(:Hand))
+DescriptionGraph(Annotation(:comment "a hand") Nodes(NodeAssertion(:Hand :h) NodeAssertion(:Finger :f1) NodeAssertion(:Finger :f2)) Edges(EdgeAssertion(:hasPart :h :f1) EdgeAssertion(:hasPart :h :f2)) MainClasses(:Hand))
+DescriptionGraphRule(Body(ClassAtom(:Hand Variable(var:x)) ObjectPropertyAtom(:hasPart Variable(var:x) Variable(var:y))) Head(ClassAtom(:Finger Variable(var:y))))
+)"#;
+
+    fn context(prefixes: &PrefixMapping) -> [Context<'_, String>; 2] {
+        [
+            Context::from(prefixes),
+            Context::from(prefixes).with_backend(Backend::Descent),
+        ]
+    }
+
+    #[test]
+    fn backends() {
+        let mut prefixes = PrefixMapping::default();
+        prefixes.set_default("http://example.com/");
+        prefixes.add_prefix("var", "urn:swrl#").unwrap();
+        let [pest, descent] = context(&prefixes);
+
+        let line = DOC.lines().nth(4).unwrap();
+        let graph = DescriptionGraph::<String>::from_ofn_ctx(line, &pest).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(
+            DescriptionGraph::<String>::from_ofn_ctx(line, &descent).unwrap(),
+            graph
+        );
+        assert_eq!(graph.as_ofn_ctx(&pest).to_string(), line);
+
+        let line = DOC.lines().nth(5).unwrap();
+        let rule = DescriptionGraphRule::<String>::from_ofn_ctx(line, &pest).unwrap();
+        assert_eq!(
+            DescriptionGraphRule::<String>::from_ofn_ctx(line, &descent).unwrap(),
+            rule
+        );
+        assert_eq!(rule.as_ofn_ctx(&pest).to_string(), line);
+    }
+
+    #[test]
+    fn graph_atoms() {
+        let rule = "DescriptionGraphRule(Body(SameIndividualAtom(<urn:a> <urn:b>)) Head())";
+        for ctx in context(&PrefixMapping::default()) {
+            assert!(DescriptionGraphRule::<String>::from_ofn_ctx(rule, &ctx).is_err());
+        }
+    }
+
+    #[test]
+    fn document() {
+        let (ontology, _, tables) = collect(DOC);
+        let graphs = tables.description_graphs.unwrap();
+        assert_eq!(ontology.iter().count(), 1);
+        assert_eq!(graphs.rules.len(), 1);
+        let graph = &graphs.graphs[0];
+        assert_eq!(graph.ann.len(), 1);
+        assert_eq!(
+            graph.main_classes,
+            vec![Build::new().class("http://example.com/Hand")]
+        );
+        let hand = &graph.edges[0].from;
+        assert!(graph.edges.iter().all(|edge| &edge.from == hand));
+        assert_eq!(roundtrip(DOC), &DOC[DOC.find("Ontology").unwrap()..]);
+    }
+}
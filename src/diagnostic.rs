@@ -0,0 +1,363 @@
+//! Human-readable rendering of parser errors.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::ops::Range;
+
+use crate::error::Error;
+use crate::parser::Rule;
+
+/// The location of an error or of an element in the parsed input.
+///
+/// The byte span can be used to label the error with an external report
+/// renderer, such as `codespan-reporting` or `ariadne`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The 1-based line of the start of the error.
+    pub line: usize,
+    /// The 1-based column of the start of the error, in characters.
+    pub column: usize,
+    /// The 1-based line of the end of the error.
+    pub end_line: usize,
+    /// The 1-based column of the end of the error, in characters.
+    pub end_column: usize,
+    /// The byte range of the error in the input.
+    ///
+    /// The range is empty when the error is located at a single position.
+    pub span: Range<usize>,
+}
+
+impl Location {
+    /// Get the location of a span of the parsed input.
+    pub(crate) fn from_span(span: &pest::Span) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        let (end_line, end_column) = span.end_pos().line_col();
+        Self {
+            line,
+            column,
+            end_line,
+            end_column,
+            span: span.start()..span.end(),
+        }
+        .clamped()
+    }
+
+    /// Move the end of the location to its start if it comes before it.
+    fn clamped(mut self) -> Self {
+        if (self.end_line, self.end_column) < (self.line, self.column) {
+            self.end_line = self.line;
+            self.end_column = self.column;
+        }
+        self
+    }
+
+    /// Move a location in a fragment starting at `offset`, whose 1-based
+    /// location in the whole document is `line` and `column`.
+    pub(crate) fn shift(self, offset: usize, line: usize, column: usize) -> Self {
+        let shift = |l: usize, c: usize| {
+            if l == 1 {
+                (line, column + c - 1)
+            } else {
+                (line + l - 1, c)
+            }
+        };
+        let (line, column) = shift(self.line, self.column);
+        let (end_line, end_column) = shift(self.end_line, self.end_column);
+        Self {
+            line,
+            column,
+            end_line,
+            end_column,
+            span: self.span.start + offset..self.span.end + offset,
+        }
+    }
+
+    /// Relocate a location in `text[offset..]` to be relative to `text`.
+    pub(crate) fn shift_in(self, text: &str, offset: usize) -> Self {
+        let before = &text[..offset];
+        let line = before.matches('\n').count();
+        let column = match before.rfind('\n') {
+            Some(i) => before[i + 1..].chars().count(),
+            None => before.chars().count(),
+        };
+        let shift = |l: usize, c: usize| {
+            if l == 1 {
+                (l + line, c + column)
+            } else {
+                (l + line, c)
+            }
+        };
+        let (line, column) = shift(self.line, self.column);
+        let (end_line, end_column) = shift(self.end_line, self.end_column);
+        Self {
+            line,
+            column,
+            end_line,
+            end_column,
+            span: self.span.start + offset..self.span.end + offset,
+        }
+        .clamped()
+    }
+}
+
+/// Get the edit distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Find the candidate closest to `name`, if any is close enough.
+pub(crate) fn suggest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(d, _)| *d <= threshold)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Get a user-facing name for a grammar rule.
+pub(crate) fn rule_name(rule: &Rule) -> String {
+    let name = match rule {
+        Rule::EOI => "end of input",
+        Rule::IRI | Rule::RFC3987_Iri | Rule::RFC3987_IriReference => "IRI",
+        Rule::FullIRI => "full IRI",
+        Rule::AbbreviatedIRI | Rule::SPARQL_PnameLn => "abbreviated IRI",
+        Rule::PrefixName | Rule::SPARQL_PnameNs | Rule::SPARQL_PnPrefix => "prefix name",
+        Rule::SPARQL_PnLocal => "local name",
+        Rule::SPARQL_PnChars | Rule::SPARQL_PnCharsU | Rule::SPARQL_PnCharsBase => "name character",
+        Rule::NodeID | Rule::SPARQL_BlankNodeLabel => "blank node",
+        Rule::LanguageTag | Rule::BCP47_LanguageTag => "language tag",
+        Rule::NonNegativeInteger => "non-negative integer",
+        Rule::QuotedString => "quoted string",
+        Rule::EQ => "`=`",
+        Rule::CARET => "`^^`",
+        Rule::LIT_SUBANNOTATION_PROPERTY_OF => "`SubAnnotationPropertyOf`",
+        Rule::LIT_DL_SAFE_RULE => "`DLSafeRule`",
+        _ => return describe(&format!("{:?}", rule)),
+    };
+    name.to_string()
+}
+
+/// Derive a user-facing name from the name of a rule.
+///
+/// Keyword rules such as `LIT_SUB_CLASS_OF` are shown as the keyword they
+/// match, and other rules such as `ObjectPropertyExpression` are split into
+/// lowercase words, keeping acronyms such as `IRI` as they are.
+fn describe(name: &str) -> String {
+    if let Some(keyword) = name.strip_prefix("LIT_") {
+        let mut out = String::from("`");
+        for word in keyword.split('_') {
+            out.push_str(&word[..1]);
+            out.push_str(&word[1..].to_lowercase());
+        }
+        out.push('`');
+        return out;
+    }
+
+    let name = match name.strip_prefix("RFC3987_") {
+        Some(rest) => rest.replacen("Iri", "IRI", 1),
+        None => name
+            .trim_start_matches("SPARQL_")
+            .trim_start_matches("BCP47_")
+            .to_string(),
+    };
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let boundary = chars[i].is_uppercase()
+            && (chars[i - 1].is_lowercase()
+                || chars.get(i + 1).map(|c| c.is_lowercase()).unwrap_or(false));
+        if boundary {
+            words.push(chars[start..i].iter().collect::<String>());
+            start = i;
+        }
+    }
+    words.push(chars[start..].iter().collect::<String>());
+
+    words
+        .into_iter()
+        .map(|w| {
+            if w.len() > 1 && w.chars().all(|c| c.is_uppercase()) {
+                w
+            } else {
+                w.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Rename the rules of a `pest` error with their user-facing names.
+pub(crate) fn renamed(error: &pest::error::Error<Rule>) -> pest::error::Error<Rule> {
+    error.clone().renamed_rules(rule_name)
+}
+
+/// A displayable error with a snippet of the input it was found in.
+///
+/// Use [`Error::diagnostic`] to create a `Diagnostic` for an error.
+#[derive(Debug, Clone)]
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    source: &'a str,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub(crate) fn new(error: &'a Error, source: &'a str) -> Self {
+        Self { error, source }
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "error: {}", self.error.message())?;
+        let location = match self.error.location() {
+            Some(location) if location.span.start <= self.source.len() => location,
+            _ => return Ok(()),
+        };
+
+        // find the line containing the start of the error
+        let start = self.source[..location.span.start]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let end = self.source[start..]
+            .find(['\n', '\r'])
+            .map(|i| start + i)
+            .unwrap_or(self.source.len());
+        let line = &self.source[start..end];
+
+        let number = location.line.to_string();
+        let pad = " ".repeat(number.len());
+        let offset = self.source[start..location.span.start].chars().count();
+        let width = self.source[location.span.start..location.span.end.min(end)]
+            .chars()
+            .count()
+            .max(1);
+
+        writeln!(f, "{}--> {}:{}", pad, location.line, location.column)?;
+        writeln!(f, "{} |", pad)?;
+        writeln!(f, "{} | {}", number, line)?;
+        writeln!(f, "{} | {}{}", pad, " ".repeat(offset), "^".repeat(width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use horned_owl::model::Axiom;
+    use horned_owl::model::ClassExpression;
+
+    use super::*;
+    use crate::FromFunctional;
+
+    #[test]
+    fn rule_names() {
+        assert_eq!(rule_name(&Rule::ClassExpression), "class expression");
+        assert_eq!(
+            rule_name(&Rule::ObjectPropertyExpression),
+            "object property expression"
+        );
+        assert_eq!(rule_name(&Rule::SPARQL_PnameLn), "abbreviated IRI");
+        assert_eq!(rule_name(&Rule::OntologyIRI), "ontology IRI");
+        assert_eq!(rule_name(&Rule::LIT_SUB_CLASS_OF), "`SubClassOf`");
+        assert_eq!(rule_name(&Rule::RFC3987_IriPath), "IRI path");
+    }
+
+    #[test]
+    fn suggestions() {
+        let prefixes = ["obo", "oboInOwl", "rdfs", "owl"];
+        assert_eq!(suggest("ob", prefixes), Some("obo"));
+        assert_eq!(suggest("rdf", prefixes), Some("rdfs"));
+        assert_eq!(suggest("oboinowl", prefixes), Some("oboInOwl"));
+        assert_eq!(suggest("xsd", prefixes), None);
+    }
+
+    #[test]
+    fn location() {
+        let doc = "Ontology(\n  Declaration(Klass(<http://example.com/B>))\n)";
+        let err = <(
+            horned_owl::ontology::set::SetOntology<String>,
+            curie::PrefixMapping,
+        )>::from_ofn(doc)
+        .unwrap_err();
+        let location = err.location().unwrap();
+        assert_eq!((location.line, location.column), (2, 15));
+        assert_eq!(location.span, 24..24);
+    }
+
+    #[test]
+    fn message() {
+        let err =
+            ClassExpression::<String>::from_ofn("ObjectSomeValuesFrom(<http://a>)").unwrap_err();
+        let message = err.message();
+        assert!(!message.contains("ClassExpression"), "{}", message);
+        assert!(message.contains("class expression"), "{}", message);
+    }
+
+    #[test]
+    fn snippet() {
+        let doc = "Declaration(Klass(<http://example.com/B>))";
+        let err = Axiom::<String>::from_ofn(doc).unwrap_err();
+        let rendered = err.diagnostic(doc).to_string();
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], " --> 1:13");
+        assert_eq!(lines[3], &format!("1 | {}", doc));
+        assert_eq!(lines[4], "  |             ^");
+    }
+
+    #[test]
+    fn snippet_span() {
+        let doc = "AnnotationAssertion(\n  <http://a> <http://b> _:x)";
+        let ctx = crate::Context::default().with_strict(true);
+        let err = Axiom::<String>::from_ofn_ctx(doc, &ctx).unwrap_err();
+        let rendered = err.diagnostic(doc).to_string();
+        assert!(
+            rendered
+                .ends_with("2 |   <http://a> <http://b> _:x)\n  |                         ^^^\n"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn crlf() {
+        let doc = "AnnotationAssertion(\r\n  <http://a> <http://b> _:x\r\n)";
+        let ctx = crate::Context::default().with_strict(true);
+        let err = Axiom::<String>::from_ofn_ctx(doc, &ctx).unwrap_err();
+        let location = err.location().unwrap();
+        assert_eq!((location.line, location.column), (2, 25));
+        assert_eq!((location.end_line, location.end_column), (2, 28));
+        assert_eq!(&doc[location.span], "_:x");
+
+        let reversed = Location {
+            line: 2,
+            column: 3,
+            end_line: 1,
+            end_column: 4,
+            span: 5..5,
+        };
+        let clamped = reversed.clamped();
+        assert_eq!((clamped.end_line, clamped.end_column), (2, 3));
+    }
+}
@@ -0,0 +1,605 @@
+//! Format-preserving edition of the axioms of a document.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use curie::PrefixMapping;
+use horned_owl::model::*;
+
+use crate::as_ofn::AsFunctional;
+use crate::diagnostic::Location;
+use crate::error::Error;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::from_pair::declare_prefix;
+use crate::from_pair::FromPair;
+use crate::index::mentioned_iris;
+use crate::parser::Rule;
+use crate::sink::read_ontology_axiom;
+use crate::Context;
+
+/// An axiom of the edited document.
+struct Existing<A: ForIRI> {
+    span: Range<usize>,
+    axiom: Option<AnnotatedAxiom<A>>,
+    iris: Vec<String>,
+    removed: bool,
+}
+
+impl<A: ForIRI> Existing<A> {
+    /// Get the section of the ontology the axiom belongs to.
+    fn section(&self) -> Section {
+        self.axiom
+            .as_ref()
+            .map_or(Section::Axioms, |a| Section::of(a.axiom.kind()))
+    }
+}
+
+/// The sections of an ontology, in the order they must appear in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Imports,
+    Annotations,
+    Axioms,
+}
+
+impl Section {
+    fn of(kind: AxiomKind) -> Self {
+        match kind {
+            AxiomKind::Import => Section::Imports,
+            AxiomKind::OntologyAnnotation => Section::Annotations,
+            _ => Section::Axioms,
+        }
+    }
+}
+
+/// Get the range to delete to remove the element at `span`.
+///
+/// This is the whole line if it contains nothing else, or the element with
+/// the blanks separating it from the rest of the line otherwise.
+fn line_of(text: &str, span: &Range<usize>) -> Range<usize> {
+    let blanks = |s: &str| s.len() - s.trim_start_matches([' ', '\t']).len();
+    let start = text[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[span.end..]
+        .find('\n')
+        .map_or(text.len(), |i| span.end + i + 1);
+    let first = text[start..span.start].trim().is_empty();
+    if first && text[span.end..end].trim().is_empty() {
+        start..end
+    } else if first {
+        span.start..span.end + blanks(&text[span.end..])
+    } else {
+        text[..span.start].trim_end_matches([' ', '\t']).len()..span.end
+    }
+}
+
+/// Get the indentation of the line containing `offset`.
+fn indent_of(text: &str, offset: usize) -> &str {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Where to insert an added axiom, relative to an existing one.
+enum Anchor {
+    After(usize),
+    Before(usize),
+}
+
+/// Find where to insert an added axiom.
+///
+/// Imports and ontology annotations go after the last element of the same
+/// section, or before the first element of a later section. Other axioms
+/// go after the axiom sharing the rarest IRIs with them, or else after the
+/// last axiom of the same kind.
+fn anchor<A: ForIRI>(
+    existing: &[Existing<A>],
+    axiom: &AnnotatedAxiom<A>,
+    section: Section,
+    iris: &HashSet<String>,
+    counts: &HashMap<&str, usize>,
+) -> Option<Anchor> {
+    let remaining = || existing.iter().enumerate().filter(|(_, e)| !e.removed);
+    if section != Section::Axioms {
+        return match remaining().filter(|(_, e)| e.section() == section).last() {
+            Some((i, _)) => Some(Anchor::After(i)),
+            None => remaining()
+                .find(|(_, e)| e.section() > section)
+                .map(|(i, _)| Anchor::Before(i)),
+        };
+    }
+
+    let mut anchor = None;
+    let mut best = 0.0;
+    for (i, e) in remaining().filter(|(_, e)| e.section() == Section::Axioms) {
+        let score = e
+            .iris
+            .iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter(|iri| iris.contains(*iri))
+            .map(|iri| 1.0 / counts[iri.as_str()] as f64)
+            .sum::<f64>();
+        if score > 0.0 && score >= best {
+            best = score;
+            anchor = Some(i);
+        }
+    }
+    if anchor.is_none() {
+        let kind = axiom.axiom.kind();
+        anchor = remaining()
+            .filter(|(_, e)| e.axiom.as_ref().map(|a| a.axiom.kind()) == Some(kind))
+            .last()
+            .map(|(i, _)| i);
+    }
+    anchor.map(Anchor::After)
+}
+
+/// Add and remove axioms of an OWL functional document, keeping its formatting.
+///
+/// Removed axioms are deleted along with their line when they are alone
+/// on it, and everything else in the document is left untouched. Added
+/// axioms are written with the prefixes declared in the document, on a new
+/// line after the axiom sharing the most specific IRIs with them, or after
+/// the last axiom of the same kind, or at the end of the ontology. Imports
+/// and ontology annotations are added to the ontology header instead.
+/// Axioms that are already in the document are not added again, and
+/// removing an axiom that is not in the document is an error.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::*;
+/// let doc = r#"Prefix(ex:=<http://example.com/>)
+/// Ontology(
+///     Declaration(Class(ex:A))
+///     Declaration(Class(ex:B))
+///     SubClassOf(ex:A ex:B)
+/// )
+/// "#;
+///
+/// let build = Build::<String>::new();
+/// let label = AnnotationAssertion {
+///     subject: build.iri("http://example.com/A").into(),
+///     ann: Annotation {
+///         ap: build.annotation_property("http://example.com/label"),
+///         av: build.iri("http://example.com/Label").into(),
+///     },
+/// };
+/// let subclass = SubClassOf {
+///     sub: build.class("http://example.com/A").into(),
+///     sup: build.class("http://example.com/B").into(),
+/// };
+///
+/// let edited = horned_functional::edit_str(doc, &[label.into()], &[subclass.into()]).unwrap();
+/// assert_eq!(edited, r#"Prefix(ex:=<http://example.com/>)
+/// Ontology(
+///     Declaration(Class(ex:A))
+///     AnnotationAssertion(ex:label ex:A ex:Label)
+///     Declaration(Class(ex:B))
+/// )
+/// "#);
+/// ```
+pub fn edit_str<A: ForIRI>(
+    src: &str,
+    added: &[AnnotatedAxiom<A>],
+    removed: &[AnnotatedAxiom<A>],
+) -> Result<String> {
+    edit_str_ctx(src, added, removed, &Context::default())
+}
+
+/// Add and remove axioms in the text of an OWL document, with a context.
+///
+/// The IRI builder, limits and warnings callback of the context are used
+/// when parsing the document; its prefix mapping is ignored in favour of the
+/// prefixes declared in the document. See [`edit_str`] for details.
+///
+/// # Example
+/// ```rust
+/// # #[macro_use] extern crate matches;
+/// use horned_functional::Context;
+/// use horned_functional::Limits;
+///
+/// let limits = Limits {
+///     max_input_size: 16,
+///     ..Limits::default()
+/// };
+/// let ctx = Context::<String>::default().with_limits(limits);
+/// let doc = "Ontology(Declaration(Class(<http://example.com/A>)))";
+/// assert_matches!(
+///     horned_functional::edit_str_ctx(doc, &[], &[], &ctx),
+///     Err(horned_functional::Error::LimitExceeded(_))
+/// );
+/// ```
+pub fn edit_str_ctx<A: ForIRI>(
+    src: &str,
+    added: &[AnnotatedAxiom<A>],
+    removed: &[AnnotatedAxiom<A>],
+    ctx: &Context<'_, A>,
+) -> Result<String> {
+    let pair = parse_exact(Rule::OntologyDocument, src, &ctx.limits)?;
+
+    // read the prefixes and the elements of the ontology with their location
+    let mut prefixes = PrefixMapping::default();
+    let mut elements = Vec::new();
+    let mut close = src.len();
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::PrefixDeclaration => {
+                declare_prefix(inner, ctx, &mut prefixes)?;
+            }
+            Rule::Ontology => {
+                close = inner.as_span().end() - 1;
+                for pairs in inner.into_inner() {
+                    if matches!(
+                        pairs.as_rule(),
+                        Rule::DirectlyImportsDocuments
+                            | Rule::OntologyAnnotations
+                            | Rule::OntologyAxioms
+                    ) {
+                        elements.extend(pairs.into_inner());
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let build = Build::new();
+    let ctx = ctx.or_build(&build);
+    let ctx = ctx.with_prefixes(&prefixes);
+    let mut existing = Vec::with_capacity(elements.len());
+    let mut count = 0;
+    for pair in elements {
+        let span = pair.as_span();
+        let axiom = match pair.as_rule() {
+            Rule::Import => Some(Import::from_pair(pair, &ctx)?.into()),
+            Rule::Annotation => Some(OntologyAnnotation::from_pair(pair, &ctx)?.into()),
+            _ => {
+                count += 1;
+                ctx.limits.check_axioms(count, span)?;
+                read_ontology_axiom(pair, &ctx, || Some(Location::from_span(&span)))?
+            }
+        };
+        existing.push(Existing {
+            span: span.start()..span.end(),
+            removed: false,
+            iris: mentioned_iris(span.as_str(), &prefixes)?,
+            axiom,
+        });
+    }
+
+    // mark the removed axioms, which must all be in the document
+    for axiom in removed.iter().collect::<HashSet<_>>() {
+        let mut found = false;
+        for e in existing.iter_mut() {
+            if e.axiom.as_ref() == Some(axiom) {
+                e.removed = true;
+                found = true;
+            }
+        }
+        if !found {
+            return Err(Error::MissingAxiom(axiom.as_ofn_ctx(&ctx).to_string()));
+        }
+    }
+
+    // count how many remaining axioms mention each IRI
+    let mut counts = HashMap::<&str, usize>::new();
+    for e in existing
+        .iter()
+        .filter(|e| !e.removed && e.section() == Section::Axioms)
+    {
+        for iri in e.iris.iter().collect::<HashSet<_>>() {
+            *counts.entry(iri).or_default() += 1;
+        }
+    }
+
+    // the replacements to make, as `(range, text)`
+    let mut edits = Vec::<(Range<usize>, String)>::new();
+    for e in existing.iter().filter(|e| e.removed) {
+        edits.push((line_of(src, &e.span), String::new()));
+    }
+
+    let mut present = existing
+        .iter()
+        .filter(|e| !e.removed)
+        .filter_map(|e| e.axiom.as_ref())
+        .collect::<HashSet<_>>();
+    // add header elements in section order, since they may share an anchor
+    let mut added = added.iter().collect::<Vec<_>>();
+    added.sort_by_key(|a| Section::of(a.axiom.kind()));
+    for axiom in added {
+        if !present.insert(axiom) {
+            continue;
+        }
+        let written = axiom.as_ofn_ctx(&ctx).to_string();
+        let iris = mentioned_iris(&written, &prefixes)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let section = Section::of(axiom.axiom.kind());
+        let edit = match anchor(&existing, axiom, section, &iris, &counts) {
+            Some(Anchor::After(i)) => {
+                let span = &existing[i].span;
+                let indent = indent_of(src, span.start);
+                match src[span.end..].find('\n') {
+                    Some(j) => (span.end + j + 1, format!("{}{}\n", indent, written)),
+                    None => (src.len(), format!("\n{}{}", indent, written)),
+                }
+            }
+            Some(Anchor::Before(i)) => {
+                let span = &existing[i].span;
+                let indent = indent_of(src, span.start);
+                let mut start = src[..span.start].rfind('\n').map_or(0, |i| i + 1);
+                if src[start..span.start].trim().is_empty() {
+                    // keep the comments before the anchor together with it
+                    while start > 0 {
+                        let line = src[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+                        if !src[line..start].trim_start().starts_with('#') {
+                            break;
+                        }
+                        start = line;
+                    }
+                    (start, format!("{}{}\n", indent, written))
+                } else {
+                    (span.start, format!("{} ", written))
+                }
+            }
+            None => {
+                let indent = existing
+                    .iter()
+                    .rfind(|e| !e.removed)
+                    .map_or("", |e| indent_of(src, e.span.start));
+                let start = src[..close].rfind('\n').map_or(0, |i| i + 1);
+                if src[start..close].trim().is_empty() {
+                    (start, format!("{}{}\n", indent, written))
+                } else {
+                    (close, format!("\n{}{}\n", indent, written))
+                }
+            }
+        };
+        edits.push((edit.0..edit.0, edit.1));
+    }
+
+    // apply the edits in document order, insertions first
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut out = String::with_capacity(src.len());
+    let mut cursor = 0;
+    for (range, text) in edits {
+        out.push_str(&src[cursor..range.start.max(cursor)]);
+        out.push_str(&text);
+        cursor = cursor.max(range.end);
+    }
+    out.push_str(&src[cursor..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Prefix(rdfs:=<http://www.w3.org/2000/01/rdf-schema#>)
+Ontology(<http://example.com/o>
+  # classes
+  Declaration(Class(:A))
+  Declaration(Class(:B))
+
+  # labels
+  AnnotationAssertion(rdfs:label :A "A")
+  AnnotationAssertion(rdfs:label :B "B")
+
+  SubClassOf(:A :B) SubClassOf(:B :C)
+)
+"#;
+
+    fn label(build: &Build<String>, iri: &str, label: &str) -> AnnotatedAxiom<String> {
+        AnnotationAssertion {
+            subject: build.iri(iri).into(),
+            ann: Annotation {
+                ap: build.annotation_property("http://www.w3.org/2000/01/rdf-schema#label"),
+                av: Literal::Simple {
+                    literal: label.into(),
+                }
+                .into(),
+            },
+        }
+        .into()
+    }
+
+    #[test]
+    fn unchanged() {
+        let edited = edit_str::<String>(DOC, &[], &[]).unwrap();
+        assert_eq!(edited, DOC);
+    }
+
+    #[test]
+    fn remove() {
+        let build = Build::new();
+        let removed = [
+            label(&build, "http://example.com/A", "A"),
+            SubClassOf {
+                sub: build.class("http://example.com/B").into(),
+                sup: build.class("http://example.com/C").into(),
+            }
+            .into(),
+        ];
+        let edited = edit_str(DOC, &[], &removed).unwrap();
+        let expected = DOC
+            .replace("  AnnotationAssertion(rdfs:label :A \"A\")\n", "")
+            .replace(" SubClassOf(:B :C)", "");
+        assert_eq!(edited, expected);
+    }
+
+    #[test]
+    fn add_next_to_entity() {
+        let build = Build::new();
+        let added = [label(&build, "http://example.com/B", "b")];
+        let edited = edit_str(DOC, &added, &[]).unwrap();
+        let expected = DOC.replace(
+            "  AnnotationAssertion(rdfs:label :B \"B\")\n",
+            "  AnnotationAssertion(rdfs:label :B \"B\")\n  AnnotationAssertion(rdfs:label :B \"b\")\n",
+        );
+        assert_eq!(edited, expected);
+    }
+
+    #[test]
+    fn add_by_kind() {
+        let build = Build::<String>::new();
+        let added = [AnnotatedAxiom::from(DeclareClass(
+            build.class("http://example.com/D"),
+        ))];
+        let edited = edit_str(DOC, &added, &[]).unwrap();
+        let expected = DOC.replace(
+            "  Declaration(Class(:B))\n",
+            "  Declaration(Class(:B))\n  Declaration(Class(:D))\n",
+        );
+        assert_eq!(edited, expected);
+    }
+
+    #[test]
+    fn add_at_end() {
+        let build = Build::<String>::new();
+        let added = [AnnotatedAxiom::from(DisjointClasses(vec![
+            build.class("http://example.com/E").into(),
+            build.class("http://example.com/F").into(),
+        ]))];
+        let edited = edit_str(DOC, &added, &[]).unwrap();
+        let expected = DOC.replace(
+            "SubClassOf(:B :C)\n)",
+            "SubClassOf(:B :C)\n  DisjointClasses(:E :F)\n)",
+        );
+        assert_eq!(edited, expected);
+
+        let edited = edit_str("Ontology()", &added, &[]).unwrap();
+        assert_eq!(
+            edited,
+            "Ontology(\nDisjointClasses(<http://example.com/E> <http://example.com/F>)\n)"
+        );
+    }
+
+    #[test]
+    fn existing() {
+        let build = Build::<String>::new();
+        let added = [label(&build, "http://example.com/A", "A")];
+        assert_eq!(edit_str(DOC, &added, &[]).unwrap(), DOC);
+        // re-adding a removed axiom moves it next to its entity
+        let edited = edit_str(DOC, &added, &added).unwrap();
+        let reparsed =
+            crate::from_str::<String, horned_owl::ontology::set::SetOntology<String>, _>(&edited)
+                .unwrap()
+                .0;
+        assert!(reparsed.iter().any(|a| a == &added[0]));
+    }
+
+    /// Parse an edited document and get its axioms.
+    fn reparse(text: &str) -> horned_owl::ontology::set::SetOntology<String> {
+        crate::from_str::<String, _, _>(text).unwrap().0
+    }
+
+    #[test]
+    fn header() {
+        let build = Build::<String>::new();
+        let import = AnnotatedAxiom::from(Import(build.iri("http://example.com/p")));
+        let annotation = AnnotatedAxiom::from(OntologyAnnotation(Annotation {
+            ap: build.annotation_property("http://www.w3.org/2000/01/rdf-schema#comment"),
+            av: Literal::Simple {
+                literal: "x".into(),
+            }
+            .into(),
+        }));
+
+        let edited = edit_str(DOC, &[annotation.clone(), import.clone()], &[]).unwrap();
+        let expected = DOC.replace(
+            "  # classes\n",
+            "  Import(:p)\n  Annotation(rdfs:comment \"x\")\n  # classes\n",
+        );
+        assert_eq!(edited, expected);
+        let ontology = reparse(&edited);
+        assert!(ontology.iter().any(|a| a == &import));
+        assert!(ontology.iter().any(|a| a == &annotation));
+
+        let other = AnnotatedAxiom::from(Import(build.iri("http://example.com/q")));
+        let edited = edit_str(
+            &edited,
+            std::slice::from_ref(&other),
+            std::slice::from_ref(&annotation),
+        )
+        .unwrap();
+        assert_eq!(
+            edited,
+            DOC.replace("  # classes\n", "  Import(:p)\n  Import(:q)\n  # classes\n")
+        );
+        let ontology = reparse(&edited);
+        assert!(ontology.iter().any(|a| a == &other));
+        assert!(!ontology.iter().any(|a| a == &annotation));
+
+        let edited = edit_str("Ontology(<http://example.com/o>)", &[import], &[]).unwrap();
+        assert_eq!(
+            edited,
+            "Ontology(<http://example.com/o>\nImport(<http://example.com/p>)\n)"
+        );
+        reparse(&edited);
+    }
+
+    #[test]
+    fn missing() {
+        let build = Build::new();
+        let removed = [label(&build, "http://example.com/C", "C")];
+        let err = edit_str(DOC, &[], &removed).unwrap_err();
+        assert!(matches!(err, Error::MissingAxiom(_)));
+        assert_eq!(
+            err.to_string(),
+            "axiom not found in the document: AnnotationAssertion(rdfs:label :C \"C\")"
+        );
+    }
+
+    #[test]
+    fn reparsed() {
+        let build = Build::new();
+        let removed = [label(&build, "http://example.com/A", "A")];
+        let added = [
+            label(&build, "http://example.com/C", "C"),
+            AnnotatedAxiom::from(DeclareClass(build.class("http://example.com/C"))),
+        ];
+        let edited = edit_str(DOC, &added, &removed).unwrap();
+        let ontology = reparse(&edited);
+        let mut expected = reparse(DOC);
+        expected.remove(&removed[0]);
+        for axiom in added {
+            expected.insert(axiom);
+        }
+        assert_eq!(ontology, expected);
+    }
+
+    #[test]
+    fn context() {
+        use std::cell::RefCell;
+
+        use crate::Limits;
+        use crate::Warning;
+        use crate::WarningKind;
+
+        let doc =
+            "Ontology(\n  Declaration(Class(<http://a.com/A>))\n  DLSafeRule(Body() Head())\n)";
+        let warnings = RefCell::new(Vec::new());
+        let collect = |w: Warning| {
+            warnings
+                .borrow_mut()
+                .push((w.kind, w.location.unwrap().line))
+        };
+        let ctx = Context::default().with_warnings(&collect);
+        assert_eq!(edit_str_ctx::<String>(doc, &[], &[], &ctx).unwrap(), doc);
+        assert_eq!(warnings.into_inner(), [(WarningKind::DroppedRule, 3)]);
+
+        let limits = Limits {
+            max_axioms: 1,
+            ..Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        let err = edit_str_ctx::<String>(doc, &[], &[], &ctx).unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded(_)));
+        assert_eq!(err.location().unwrap().line, 3);
+    }
+}
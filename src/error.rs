@@ -1,5 +1,12 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
 use pest::Span;
 
+use super::diagnostic::Diagnostic;
+use super::diagnostic::Location;
+use super::limits::LimitKind;
 use super::parser::Rule;
 
 /// The result type for this crate.
@@ -23,7 +30,7 @@ pub enum Error {
     /// let res = SetOntology::<String>::from_ofn("Ontology(");
     /// assert_matches!(res, Err(horned_functional::Error::Pest(_)));
     /// ```
-    #[error(transparent)]
+    #[error("{}", crate::diagnostic::renamed(.0))]
     Pest(Box<pest::error::Error<Rule>>),
 
     /// An error that happened at the I/O level.
@@ -55,8 +62,8 @@ pub enum Error {
     /// let res = IRI::<String>::from_ofn("example:Entity");
     /// assert_matches!(res, Err(horned_functional::Error::Expansion(_)));
     /// ```
-    #[error("expansion error: {0:?}")]
-    Expansion(curie::ExpansionError),
+    #[error("{0}")]
+    Expansion(Box<ExpansionError>),
 
     /// An unknown IRI was used as a facet.
     ///
@@ -69,11 +76,214 @@ pub enum Error {
     /// let res = <Facet as FromFunctional<String>>::from_ofn("<http://example.com/thing>");
     /// assert_matches!(res, Err(horned_functional::Error::InvalidFacet(_)));
     /// ```
-    #[error("invalid facet: {0}")]
-    InvalidFacet(String),
+    #[error("invalid facet: {}", .0.iri)]
+    InvalidFacet(Box<FacetError>),
+
+    /// A resource limit of the parsing context was exceeded.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[macro_use] extern crate matches;
+    /// # use horned_owl::model::ClassExpression;
+    /// use horned_functional::FromFunctional;
+    ///
+    /// let deep = format!("{}<http://a.com/>{}", "ObjectComplementOf(".repeat(200), ")".repeat(200));
+    /// let res = ClassExpression::<String>::from_ofn(&deep);
+    /// assert_matches!(res, Err(horned_functional::Error::LimitExceeded(_)));
+    /// ```
+    #[error("{0}")]
+    LimitExceeded(Box<LimitError>),
+
+    /// Parsing was cancelled with a [`CancellationToken`].
+    ///
+    /// [`CancellationToken`]: crate::CancellationToken
+    #[error("parsing was cancelled")]
+    Cancelled,
+
+    /// An element of a syntax tree cannot be represented in `horned-owl`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[macro_use] extern crate matches;
+    /// # use horned_owl::model::Build;
+    /// use horned_functional::ast::Lower;
+    /// use horned_functional::ast::Node;
+    ///
+    /// let ce = horned_functional::ast::ClassExpression::from_ofn(
+    ///     "DataSomeValuesFrom(<http://a.com/p> <http://a.com/q> <http://a.com/d>)"
+    /// ).unwrap();
+    /// let build = Build::<String>::new();
+    /// let res = ce.lower(&horned_functional::Context::from(&build));
+    /// assert_matches!(res, Err(horned_functional::Error::Unsupported(_)));
+    /// ```
+    #[error("{0}")]
+    Unsupported(String),
+
+    /// An axiom to remove from a document was not found in it.
+    ///
+    /// The axiom is given in functional syntax.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[macro_use] extern crate matches;
+    /// # use horned_owl::model::*;
+    /// let build = Build::<String>::new();
+    /// let axiom = AnnotatedAxiom::from(DeclareClass(build.class("http://a.com/A")));
+    /// let res = horned_functional::edit_str("Ontology()", &[], &[axiom]);
+    /// assert_matches!(res, Err(horned_functional::Error::MissingAxiom(_)));
+    /// ```
+    #[error("axiom not found in the document: {0}")]
+    MissingAxiom(String),
+}
+
+/// The details of a CURIE that could not be expanded.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::Axiom;
+/// use horned_functional::Error;
+/// use horned_functional::FromFunctional;
+///
+/// let mut prefixes = curie::PrefixMapping::default();
+/// prefixes.add_prefix("obo", "http://purl.obolibrary.org/obo/").unwrap();
+/// let ctx = horned_functional::Context::from(&prefixes);
+///
+/// match Axiom::<String>::from_ofn_ctx("Declaration(Class(ob:MS_1000031))", &ctx) {
+///     Err(Error::Expansion(e)) => {
+///         assert_eq!(e.curie, "ob:MS_1000031");
+///         assert_eq!(e.prefix.as_deref(), Some("ob"));
+///         assert_eq!(e.suggestion.as_deref(), Some("obo"));
+///         assert_eq!(e.location.unwrap().span, 18..31);
+///     }
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpansionError {
+    /// The error returned by the prefix mapping.
+    pub error: curie::ExpansionError,
+    /// The CURIE that could not be expanded, as written in the input.
+    pub curie: String,
+    /// The prefix of the CURIE, or `None` for the default prefix.
+    pub prefix: Option<String>,
+    /// A declared prefix with a similar name, if any.
+    pub suggestion: Option<String>,
+    /// The location of the CURIE in the parsed input, if known.
+    pub location: Option<Location>,
+}
+
+impl Display for ExpansionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match &self.prefix {
+            Some(prefix) => write!(f, "undefined prefix `{}` in `{}`", prefix, self.curie)?,
+            None => write!(f, "undefined default prefix in `{}`", self.curie)?,
+        }
+        match &self.suggestion {
+            Some(suggestion) => write!(f, " (did you mean `{}`?)", suggestion),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The details of an exceeded resource limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitError {
+    /// The kind of limit that was exceeded.
+    pub kind: LimitKind,
+    /// The value of the limit.
+    pub max: usize,
+    /// The location where the limit was exceeded, if any.
+    pub location: Option<Location>,
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} exceeds the limit of {}", self.kind, self.max)
+    }
+}
+
+/// The details of an IRI that is not a known facet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetError {
+    /// The IRI used as a facet.
+    pub iri: String,
+    /// The location of the IRI in the parsed input, if known.
+    pub location: Option<Location>,
 }
 
 impl Error {
+    /// Get the location of the error in the parsed input, if known.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use horned_owl::model::Axiom;
+    /// use horned_functional::FromFunctional;
+    ///
+    /// let err = Axiom::<String>::from_ofn("Declaration(Klass(<http://example.com/A>))")
+    ///     .unwrap_err();
+    /// let location = err.location().unwrap();
+    /// assert_eq!((location.line, location.column), (1, 13));
+    /// assert_eq!(location.span, 12..12);
+    /// ```
+    pub fn location(&self) -> Option<Location> {
+        use pest::error::InputLocation;
+        use pest::error::LineColLocation;
+
+        match self {
+            Error::Pest(e) => {
+                let ((line, column), (end_line, end_column)) = match e.line_col {
+                    LineColLocation::Pos(p) => (p, p),
+                    LineColLocation::Span(s, e) => (s, e),
+                };
+                let span = match e.location {
+                    InputLocation::Pos(p) => p..p,
+                    InputLocation::Span((s, e)) => s..e,
+                };
+                Some(Location {
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                    span,
+                })
+            }
+            Error::Expansion(e) => e.location.clone(),
+            Error::InvalidFacet(e) => e.location.clone(),
+            Error::LimitExceeded(e) => e.location.clone(),
+            Error::IO(_) | Error::Cancelled | Error::Unsupported(_) | Error::MissingAxiom(_) => {
+                None
+            }
+        }
+    }
+
+    /// Get the message of the error, without its location.
+    ///
+    /// The grammar rules expected by the parser are given user-facing names
+    /// such as *class expression* or *abbreviated IRI*.
+    pub fn message(&self) -> String {
+        match self {
+            Error::Pest(e) => crate::diagnostic::renamed(e).variant.message().into_owned(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Get a displayable report of the error with a snippet of `source`.
+    ///
+    /// `source` must be the input that was given to the parser.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use horned_owl::model::Axiom;
+    /// use horned_functional::FromFunctional;
+    ///
+    /// let doc = "Declaration(Klass(<http://example.com/A>))";
+    /// let err = Axiom::<String>::from_ofn(doc).unwrap_err();
+    /// println!("{}", err.diagnostic(doc));
+    /// ```
+    pub fn diagnostic<'a>(&'a self, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic::new(self, source)
+    }
+
     /// Create a custom `pest` error located at the given span.
     pub fn custom<S: Into<String>>(message: S, span: Span) -> Self {
         Self::from(pest::error::Error::new_from_span(
@@ -83,6 +293,65 @@ impl Error {
             span,
         ))
     }
+
+    /// Move the location of an error by the given offset.
+    ///
+    /// This is used to report locations relative to a whole document when
+    /// only a fragment of it was given to the parser. `line` and `column`
+    /// are the 1-based location of the start of the fragment.
+    pub(crate) fn shift(self, offset: usize, line: usize, column: usize) -> Self {
+        use pest::error::InputLocation;
+        use pest::error::LineColLocation;
+
+        let lc = |(l, c): (usize, usize)| {
+            if l == 1 {
+                (line, column + c - 1)
+            } else {
+                (line + l - 1, c)
+            }
+        };
+        let shift_location = |location: &mut Option<Location>| {
+            *location = location.take().map(|l| l.shift(offset, line, column));
+        };
+
+        match self {
+            Error::Pest(mut e) => {
+                e.location = match e.location {
+                    InputLocation::Pos(p) => InputLocation::Pos(p + offset),
+                    InputLocation::Span((s, e)) => InputLocation::Span((s + offset, e + offset)),
+                };
+                e.line_col = match e.line_col {
+                    LineColLocation::Pos(p) => LineColLocation::Pos(lc(p)),
+                    LineColLocation::Span(s, e) => LineColLocation::Span(lc(s), lc(e)),
+                };
+                Error::Pest(e)
+            }
+            Error::Expansion(mut e) => {
+                shift_location(&mut e.location);
+                Error::Expansion(e)
+            }
+            Error::InvalidFacet(mut e) => {
+                shift_location(&mut e.location);
+                Error::InvalidFacet(e)
+            }
+            Error::LimitExceeded(mut e) => {
+                shift_location(&mut e.location);
+                Error::LimitExceeded(e)
+            }
+            other => other,
+        }
+    }
+
+    /// Move the location of an error in the fragment of `text` starting at `offset`.
+    pub(crate) fn shift_in(self, text: &str, offset: usize) -> Self {
+        let before = &text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = match before.rfind('\n') {
+            Some(i) => before[i + 1..].chars().count() + 1,
+            None => before.chars().count() + 1,
+        };
+        self.shift(offset, line, column)
+    }
 }
 
 impl From<pest::error::Error<Rule>> for Error {
@@ -90,9 +359,3 @@ impl From<pest::error::Error<Rule>> for Error {
         Error::Pest(Box::new(e))
     }
 }
-
-impl From<curie::ExpansionError> for Error {
-    fn from(e: curie::ExpansionError) -> Self {
-        Error::Expansion(e)
-    }
-}
@@ -3,12 +3,34 @@ use std::collections::BTreeSet;
 use curie::PrefixMapping;
 use horned_owl::model::*;
 use horned_owl::ontology::axiom_mapped::AxiomMappedOntology;
+use horned_owl::ontology::indexed::ForIndex;
+use horned_owl::ontology::indexed::FourIndexedOntology;
+use horned_owl::ontology::indexed::OneIndexedOntology;
+use horned_owl::ontology::indexed::OntologyIndex;
+use horned_owl::ontology::indexed::ThreeIndexedOntology;
+use horned_owl::ontology::indexed::TwoIndexedOntology;
+use horned_owl::ontology::iri_mapped::IRIMappedOntology;
 use horned_owl::ontology::set::SetOntology;
+use pest::iterators::Pair;
 
+use crate::descent;
+use crate::dg::DGNode;
+use crate::dg::DescriptionGraph;
+use crate::dg::DescriptionGraphRule;
+use crate::dg::EdgeAssertion;
+use crate::dg::NodeAssertion;
 use crate::error::Error;
 use crate::error::Result;
 use crate::from_pair::FromPair;
+use crate::limits::Limits;
 use crate::parser::OwlFunctionalParser;
+use crate::parser::Rule;
+use crate::swrl::Atom;
+use crate::swrl::DArg;
+use crate::swrl::DLSafeRule;
+use crate::swrl::IArg;
+use crate::swrl::Variable;
+use crate::Backend;
 use crate::Context;
 
 /// A trait for OWL elements that can be deserialized from OWL Functional syntax.
@@ -25,22 +47,32 @@ pub trait FromFunctional<A: ForIRI>: Sized + FromPair<A> {
     fn from_ofn_ctx(s: &str, context: &Context<'_, A>) -> Result<Self>;
 }
 
+/// Parse a string with the given rule, and check the whole string was consumed.
+pub(crate) fn parse_exact<'i>(rule: Rule, s: &'i str, limits: &Limits) -> Result<Pair<'i, Rule>> {
+    limits.check_input(s)?;
+    let mut pairs = OwlFunctionalParser::parse(rule, s)?;
+    if pairs.as_str().len() == s.len() {
+        Ok(pairs.next().unwrap())
+    } else {
+        Err(Error::from(pest::error::Error::new_from_span(
+            pest::error::ErrorVariant::CustomError {
+                message: "remaining input".to_string(),
+            },
+            pest::Span::new(s, pairs.as_str().len(), s.len()).unwrap(),
+        )))
+    }
+}
+
 impl<A, O> FromFunctional<A> for (O, PrefixMapping)
 where
     A: ForIRI,
     O: FromFunctional<A> + Ontology<A>,
 {
     fn from_ofn_ctx(s: &str, context: &Context<'_, A>) -> Result<Self> {
-        let mut pairs = OwlFunctionalParser::parse(Self::RULE, s)?;
-        if pairs.as_str().len() == s.len() {
-            Self::from_pair(pairs.next().unwrap(), context)
-        } else {
-            Err(Error::from(pest::error::Error::new_from_span(
-                pest::error::ErrorVariant::CustomError {
-                    message: "remaining input".to_string(),
-                },
-                pest::Span::new(s, pairs.as_str().len(), s.len()).unwrap(),
-            )))
+        match context.backend {
+            Backend::Pest => parse_exact(Self::RULE, s, &context.limits)
+                .and_then(|pair| Self::from_pair(pair, context)),
+            Backend::Descent => descent::parse_document(s, context),
         }
     }
 }
@@ -51,20 +83,10 @@ macro_rules! implement {
     ($A:ident, $($ty:ty),+) => {
         $(impl<$A: ForIRI> FromFunctional<$A> for $ty {
             fn from_ofn_ctx(s: &str, context: &Context<'_, $A>) -> Result<Self> {
-                let mut pairs = OwlFunctionalParser::parse(<Self as FromPair<$A>>::RULE, s)?;
-                if pairs.as_str().len() == s.len() {
-                     Self::from_pair(pairs.next().unwrap(), context)
-                } else {
-                    Err(
-                        Error::from(
-                            pest::error::Error::new_from_span(
-                                pest::error::ErrorVariant::CustomError {
-                                    message: "remaining input".to_string(),
-                                },
-                                pest::Span::new(s, pairs.as_str().len(), s.len()).unwrap()
-                            )
-                        )
-                    )
+                match context.backend {
+                    Backend::Pest => parse_exact(<Self as FromPair<$A>>::RULE, s, &context.limits)
+                        .and_then(|pair| Self::from_pair(pair, context)),
+                    Backend::Descent => descent::parse_exact(s, context),
                 }
             }
         })*
@@ -79,35 +101,83 @@ implement!(
     AnnotationSubject<A>,
     AnnotationValue<A>,
     AnonymousIndividual<A>,
+    Atom<A>,
     Axiom<A>,
-    // AxiomMappedOntology<A>,
     BTreeSet<Annotation<A>>,
     Class<A>,
     ClassExpression<A>,
     DataProperty<A>,
     DataRange<A>,
     Datatype<A>,
+    DArg<A>,
     DeclareClass<A>,
     DeclareDatatype<A>,
     DeclareObjectProperty<A>,
     DeclareDataProperty<A>,
     DeclareAnnotationProperty<A>,
     DeclareNamedIndividual<A>,
+    DescriptionGraph<A>,
+    DescriptionGraphRule<A>,
+    DGNode<A>,
+    DLSafeRule<A>,
+    EdgeAssertion<A>,
     Facet,
     FacetRestriction<A>,
+    IArg<A>,
     Import<A>,
     Individual<A>,
     IRI<A>,
     NamedIndividual<A>,
+    NodeAssertion<A>,
     Literal<A>,
     ObjectPropertyExpression<A>,
     ObjectProperty<A>,
     SetOntology<A>,
+    Variable<A>,
     OntologyAnnotation<A> // String,
                           // SubObjectPropertyExpression,
                           // u32
 );
 
+// Indexed ontologies need an additional type parameter for the index.
+macro_rules! implement_ontology {
+    ($([$($gen:tt)*] $ty:ty),+ $(,)?) => {
+        $(impl<A: ForIRI, $($gen)*> FromFunctional<A> for $ty {
+            fn from_ofn_ctx(s: &str, context: &Context<'_, A>) -> Result<Self> {
+                match context.backend {
+                    Backend::Pest => parse_exact(<Self as FromPair<A>>::RULE, s, &context.limits)
+                        .and_then(|pair| Self::from_pair(pair, context)),
+                    Backend::Descent => descent::parse_exact(s, context),
+                }
+            }
+        })*
+    }
+}
+
+implement_ontology!(
+    [AA: ForIndex<A>] AxiomMappedOntology<A, AA>,
+    [AA: ForIndex<A>] IRIMappedOntology<A, AA>,
+    [AA: ForIndex<A>, I: OntologyIndex<A, AA> + Default] OneIndexedOntology<A, AA, I>,
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA> + Default,
+        J: OntologyIndex<A, AA> + Default
+    ] TwoIndexedOntology<A, AA, I, J>,
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA> + Default,
+        J: OntologyIndex<A, AA> + Default,
+        K: OntologyIndex<A, AA> + Default
+    ] ThreeIndexedOntology<A, AA, I, J, K>,
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA> + Default,
+        J: OntologyIndex<A, AA> + Default,
+        K: OntologyIndex<A, AA> + Default,
+        L: OntologyIndex<A, AA> + Default
+    ] FourIndexedOntology<A, AA, I, J, K, L>,
+);
+
 #[cfg(test)]
 mod tests {
 
@@ -131,4 +201,53 @@ mod tests {
             Err(other) => panic!("unexpected error: {:?}", other),
         }
     }
+
+    #[test]
+    fn test_indexed_ontology() {
+        use horned_owl::model::RcStr;
+        use horned_owl::ontology::indexed::NullIndex;
+        use std::rc::Rc;
+
+        let doc = r#"
+            Prefix(:=<http://example.com/>)
+            Ontology(<http://example.com/ontology>
+                Declaration(Class(:A))
+                Declaration(Class(:B))
+                SubClassOf(:A :B)
+            )
+        "#;
+        let build = Build::new_rc();
+
+        let (ontology, _) =
+            <(AxiomMappedOntology<RcStr, Rc<AnnotatedAxiom<RcStr>>>, _)>::from_ofn(doc.trim())
+                .unwrap();
+        assert_eq!(
+            ontology.id().iri,
+            Some(build.iri("http://example.com/ontology"))
+        );
+        assert_eq!(
+            ontology.i().axiom_for_kind(AxiomKind::DeclareClass).count(),
+            2
+        );
+        assert_eq!(
+            ontology.i().axiom_for_kind(AxiomKind::SubClassOf).count(),
+            1
+        );
+
+        let (mut ontology, _) =
+            <(IRIMappedOntology<RcStr, Rc<AnnotatedAxiom<RcStr>>>, _)>::from_ofn(doc.trim())
+                .unwrap();
+        let a = build.iri("http://example.com/A");
+        assert_eq!(ontology.axiom_for_iri(&a).count(), 2);
+
+        let (ontology, _) = <(
+            OneIndexedOntology<RcStr, Rc<AnnotatedAxiom<RcStr>>, NullIndex>,
+            _,
+        )>::from_ofn(doc.trim())
+        .unwrap();
+        assert_eq!(
+            ontology.id().iri,
+            Some(build.iri("http://example.com/ontology"))
+        );
+    }
 }
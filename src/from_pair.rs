@@ -6,15 +6,41 @@ use curie::PrefixMapping;
 use enum_meta::Meta;
 use horned_owl::model::*;
 use horned_owl::ontology::axiom_mapped::AxiomMappedOntology;
+use horned_owl::ontology::indexed::ForIndex;
+use horned_owl::ontology::indexed::FourIndexedOntology;
+use horned_owl::ontology::indexed::OneIndexedOntology;
+use horned_owl::ontology::indexed::OntologyIndex;
+use horned_owl::ontology::indexed::ThreeIndexedOntology;
+use horned_owl::ontology::indexed::TwoIndexedOntology;
+use horned_owl::ontology::iri_mapped::IRIMappedOntology;
 use horned_owl::ontology::set::SetOntology;
 use horned_owl::vocab::OWL2Datatype;
 use horned_owl::vocab::WithIRI;
 use horned_owl::vocab::OWL;
 use pest::iterators::Pair;
-
+use pest::Span;
+
+use crate::descent::FromTokens;
+use crate::descent::Parser;
+use crate::dg::DGNode;
+use crate::dg::DescriptionGraph;
+use crate::dg::DescriptionGraphRule;
+use crate::dg::EdgeAssertion;
+use crate::dg::NodeAssertion;
+use crate::diagnostic::suggest;
+use crate::diagnostic::Location;
 use crate::error::Error;
+use crate::error::ExpansionError;
+use crate::error::FacetError;
 use crate::error::Result;
 use crate::parser::Rule;
+use crate::sink::read_ontology;
+use crate::swrl::Atom;
+use crate::swrl::DArg;
+use crate::swrl::DLSafeRule;
+use crate::swrl::IArg;
+use crate::swrl::Variable;
+use crate::warning::check_prefix;
 use crate::Context;
 
 // ---------------------------------------------------------------------------
@@ -30,7 +56,7 @@ pub trait FromPair<A: ForIRI>: Sized {
     /// Create a new instance from a `Pair`.
     #[inline]
     fn from_pair(pair: Pair<Rule>, context: &Context<'_, A>) -> Result<Self> {
-        if cfg!(debug_assertions) && &pair.as_rule() != &Self::RULE {
+        if cfg!(debug_assertions) && pair.as_rule() != Self::RULE {
             return Err(Error::from(pest::error::Error::new_from_span(
                 pest::error::ErrorVariant::ParsingError {
                     positives: vec![pair.as_rule()],
@@ -346,7 +372,7 @@ impl<A: ForIRI> FromPair<A> for AnnotatedAxiom<A> {
                 let mut inner = pair.into_inner();
                 let annotations = FromPair::from_pair(inner.next().unwrap(), ctx)?;
                 let ope = ObjectPropertyExpression::from_pair(inner.next().unwrap(), ctx)?;
-                let from = Individual::from_pair(inner.next().unwrap(), ctx)?.into();
+                let from = Individual::from_pair(inner.next().unwrap(), ctx)?;
                 let to = Individual::from_pair(inner.next().unwrap(), ctx)?;
                 Ok(Self::new(
                     NegativeObjectPropertyAssertion::new(ope, from, to),
@@ -463,6 +489,13 @@ impl<A: ForIRI> FromPair<A> for AnnotationSubject<A> {
 
 // ---------------------------------------------------------------------------
 
+/// Anonymous individuals are encoded as IRIs in the [`ANONYMOUS_VALUE_NAMESPACE`].
+///
+/// The encoding only keeps the node ID, which is scoped to its document:
+/// merging the ontologies of two documents that both use `_:genid1` as an
+/// annotation value unifies the two nodes. Use [`is_anonymous_value`] and
+/// [`anonymous_node_id`] to recognize these values, or [`Context::with_strict`]
+/// to reject them.
 impl<A: ForIRI> FromPair<A> for AnnotationValue<A> {
     const RULE: Rule = Rule::AnnotationValue;
     fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
@@ -470,17 +503,69 @@ impl<A: ForIRI> FromPair<A> for AnnotationValue<A> {
         match inner.as_rule() {
             Rule::IRI => IRI::from_pair(inner, ctx).map(AnnotationValue::IRI),
             Rule::Literal => Literal::from_pair(inner, ctx).map(AnnotationValue::Literal),
-            // FIXME: when horned-owl is updated, replace with
-            //        AnonymousIndividual::from_pair(inner, ctx).map(AnnotationValue::Anonymous)
-            Rule::AnonymousIndividual => Err(Error::custom(
+            Rule::AnonymousIndividual if ctx.strict => Err(Error::custom(
                 "anonymous annotation targets are not supported",
                 inner.as_span(),
             )),
+            Rule::AnonymousIndividual => {
+                AnonymousIndividual::from_pair(inner, ctx).map(|anon| anonymous_value(&anon, ctx))
+            }
             _ => unreachable!(),
         }
     }
 }
 
+/// The namespace of the IRIs storing anonymous individuals used as annotation values.
+///
+/// `horned-owl` has no variant for anonymous annotation values, so the value
+/// `_:b0` is stored as the IRI made of this namespace followed by `b0`, and
+/// written back as an anonymous individual.
+/// Node IDs are only unique within a document, so anonymous values from
+/// different documents with the same node ID are equal once parsed.
+pub const ANONYMOUS_VALUE_NAMESPACE: &str = "urn:uuid:63c86f81-1153-422b-b562-0b31496490fb#";
+
+/// Encode an anonymous individual as an annotation value.
+pub(crate) fn anonymous_value<A: ForIRI>(
+    anon: &AnonymousIndividual<A>,
+    ctx: &Context<'_, A>,
+) -> AnnotationValue<A> {
+    let id: &str = anon.0.borrow();
+    AnnotationValue::IRI(ctx.iri(format!("{}{}", ANONYMOUS_VALUE_NAMESPACE, id)))
+}
+
+/// Get the node ID of the anonymous individual stored in an annotation value, if any.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::*;
+/// use horned_functional::FromFunctional;
+///
+/// let annotation = Annotation::<String>::from_ofn("Annotation(<http://a.com/p> _:b0)").unwrap();
+/// assert_eq!(horned_functional::anonymous_node_id(&annotation.av), Some("b0"));
+/// ```
+pub fn anonymous_node_id<A: ForIRI>(value: &AnnotationValue<A>) -> Option<&str> {
+    match value {
+        AnnotationValue::IRI(iri) => iri.strip_prefix(ANONYMOUS_VALUE_NAMESPACE),
+        AnnotationValue::Literal(_) => None,
+    }
+}
+
+/// Check whether an annotation value stores an anonymous individual.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::*;
+/// use horned_functional::FromFunctional;
+///
+/// let anonymous = Annotation::<String>::from_ofn("Annotation(<http://a.com/p> _:b0)").unwrap();
+/// assert!(horned_functional::is_anonymous_value(&anonymous.av));
+/// let iri = Annotation::<String>::from_ofn("Annotation(<http://a.com/p> <http://a.com/b0>)").unwrap();
+/// assert!(!horned_functional::is_anonymous_value(&iri.av));
+/// ```
+pub fn is_anonymous_value<A: ForIRI>(value: &AnnotationValue<A>) -> bool {
+    anonymous_node_id(value).is_some()
+}
+
 // ---------------------------------------------------------------------------
 
 impl<A: ForIRI> FromPair<A> for AnonymousIndividual<A> {
@@ -693,14 +778,32 @@ impl<A: ForIRI> FromPair<A> for DataRange<A> {
 
 // ---------------------------------------------------------------------------
 
+/// Get the facet with the given IRI, found at the given location.
+///
+/// The location is only computed on error, since it is costly to obtain.
+pub(crate) fn facet<A: ForIRI>(
+    iri: &IRI<A>,
+    location: impl FnOnce() -> Option<Location>,
+) -> Result<Facet> {
+    Facet::all()
+        .into_iter()
+        .find(|facet| iri.to_string() == facet.iri_str())
+        .ok_or_else(|| {
+            Error::InvalidFacet(Box::new(FacetError {
+                iri: iri.to_string(),
+                location: location(),
+            }))
+        })
+}
+
 impl<A: ForIRI> FromPair<A> for Facet {
     const RULE: Rule = Rule::ConstrainingFacet;
     fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
-        let iri = IRI::from_pair(pair.into_inner().next().unwrap(), ctx)?;
-        Facet::all()
-            .into_iter()
-            .find(|facet| &iri.to_string() == facet.iri_str())
-            .ok_or_else(|| Error::InvalidFacet(iri.to_string()))
+        let inner = pair.into_inner().next().unwrap();
+        let span = inner.as_span();
+        facet(&IRI::from_pair(inner, ctx)?, || {
+            Some(Location::from_span(&span))
+        })
     }
 }
 
@@ -734,27 +837,63 @@ impl<A: ForIRI> FromPair<A> for Individual<A> {
 
 // ---------------------------------------------------------------------------
 
+/// Expand the CURIE with the given prefix and local part, written as `curie`.
+///
+/// The location is only computed on error, since it is costly to obtain.
+pub(crate) fn expand_curie_at<A: ForIRI>(
+    prefix: Option<&str>,
+    local: &str,
+    curie: &str,
+    location: impl FnOnce() -> Option<Location>,
+    ctx: &Context<'_, A>,
+) -> Result<String> {
+    let result = match ctx.prefixes {
+        Some(prefixes) => prefixes.expand_curie(&Curie::new(prefix, local)),
+        None => Err(curie::ExpansionError::Invalid),
+    };
+    result.map_err(|error| {
+        let suggestion = prefix.zip(ctx.prefixes).and_then(|(prefix, prefixes)| {
+            suggest(prefix, prefixes.mappings().map(|(p, _)| p.as_str()))
+        });
+        Error::Expansion(Box::new(ExpansionError {
+            error,
+            curie: curie.to_string(),
+            prefix: prefix.map(String::from),
+            suggestion: suggestion.map(String::from),
+            location: location(),
+        }))
+    })
+}
+
+/// Expand the CURIE with the given prefix and local part, found at the given span.
+pub(crate) fn expand_curie<A: ForIRI>(
+    prefix: Option<&str>,
+    local: &str,
+    span: Span,
+    ctx: &Context<'_, A>,
+) -> Result<IRI<A>> {
+    let location = || Some(Location::from_span(&span));
+    let iri = expand_curie_at(prefix, local, span.as_str(), location, ctx)?;
+    ctx.limits.check_iri(&iri, span)?;
+    Ok(ctx.iri(iri))
+}
+
 impl<A: ForIRI> FromPair<A> for IRI<A> {
     const RULE: Rule = Rule::IRI;
     fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
         let inner = pair.into_inner().next().unwrap();
         match inner.as_rule() {
             Rule::AbbreviatedIRI => {
+                let span = inner.as_span();
                 let mut pname = inner.into_inner().next().unwrap().into_inner();
                 let prefix = pname.next().unwrap().into_inner().next();
                 let local = pname.next().unwrap();
-                let curie = Curie::new(prefix.map(|p| p.as_str()), local.as_str());
-                if let Some(prefixes) = ctx.prefixes {
-                    prefixes
-                        .expand_curie(&curie)
-                        .map_err(Error::from)
-                        .map(|s| ctx.iri(s))
-                } else {
-                    Err(Error::from(curie::ExpansionError::Invalid))
-                }
+                expand_curie(prefix.map(|p| p.as_str()), local.as_str(), span, ctx)
             }
             Rule::FullIRI => {
+                let span = inner.as_span();
                 let iri = inner.into_inner().next().unwrap();
+                ctx.limits.check_iri(iri.as_str(), span)?;
                 Ok(ctx.iri(iri.as_str()))
             }
             rule => unreachable!("unexpected rule in IRI::from_pair: {:?}", rule),
@@ -829,62 +968,67 @@ impl<A: ForIRI> FromPair<A> for ObjectPropertyExpression<A> {
 // ---------------------------------------------------------------------------
 
 macro_rules! impl_ontology {
-    ($ty:ident) => {
-        impl<A: ForIRI> FromPair<A> for $ty<A> {
+    ([$($gen:tt)*] $ty:ty => $new:expr) => {
+        impl<A: ForIRI, $($gen)*> FromPair<A> for $ty {
             const RULE: Rule = Rule::Ontology;
             fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
-                debug_assert!(pair.as_rule() == Rule::Ontology);
-                let mut pairs = pair.into_inner();
-                let mut pair = pairs.next().unwrap();
-
-                let mut ontology = $ty::default();
-                let mut ontology_id = ontology.mut_id();
-
-                // Parse ontology IRI and Version IRI if any
-                if pair.as_rule() == Rule::OntologyIRI {
-                    let inner = pair.into_inner().next().unwrap();
-                    ontology_id.iri = Some(IRI::from_pair(inner, ctx)?);
-                    pair = pairs.next().unwrap();
-                    if pair.as_rule() == Rule::VersionIRI {
-                        let inner = pair.into_inner().next().unwrap();
-                        ontology_id.viri = Some(IRI::from_pair(inner, ctx)?);
-                        pair = pairs.next().unwrap();
-                    }
-                }
-
-                // Process imports
-                for p in pair.into_inner() {
-                    ontology.insert(Import::from_pair(p, ctx)?);
-                }
-
-                // Process ontology annotations
-                for pair in pairs.next().unwrap().into_inner() {
-                    ontology.insert(OntologyAnnotation::from_pair(pair, ctx)?);
-                }
-
-                // Process axioms, ignore SWRL rules
-                for pair in pairs.next().unwrap().into_inner() {
-                    let inner = pair.into_inner().next().unwrap();
-                    match inner.as_rule() {
-                        // FIXME: SWRL rules are not supported for now
-                        Rule::Rule | Rule::DGAxiom => (),
-                        Rule::Axiom => {
-                            ontology.insert(AnnotatedAxiom::from_pair(inner, ctx)?);
-                        }
-                        rule => {
-                            unreachable!("unexpected rule in Ontology::from_pair: {:?}", rule);
-                        }
-                    }
-                }
+                let mut ontology = $new;
+                read_ontology(pair, ctx, &mut ontology)?;
+                Ok(ontology)
+            }
+        }
 
+        impl<A: ForIRI, $($gen)*> FromTokens<A> for $ty {
+            fn from_tokens(p: &mut Parser<'_>, ctx: &Context<'_, A>) -> Result<Self> {
+                let mut ontology = $new;
+                crate::descent::read_ontology(p, ctx, &mut ontology)?;
                 Ok(ontology)
             }
         }
     };
 }
 
-impl_ontology!(SetOntology);
-// impl_ontology!(AxiomMappedOntology);
+impl_ontology!([] SetOntology<A> => SetOntology::new());
+impl_ontology!([AA: ForIndex<A>] AxiomMappedOntology<A, AA> => AxiomMappedOntology::new());
+impl_ontology!([AA: ForIndex<A>] IRIMappedOntology<A, AA> => IRIMappedOntology::default());
+impl_ontology!(
+    [AA: ForIndex<A>, I: OntologyIndex<A, AA> + Default]
+    OneIndexedOntology<A, AA, I> => OneIndexedOntology::new(I::default())
+);
+impl_ontology!(
+    [AA: ForIndex<A>, I: OntologyIndex<A, AA> + Default, J: OntologyIndex<A, AA> + Default]
+    TwoIndexedOntology<A, AA, I, J> => TwoIndexedOntology::new(I::default(), J::default(), Default::default())
+);
+impl_ontology!(
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA> + Default,
+        J: OntologyIndex<A, AA> + Default,
+        K: OntologyIndex<A, AA> + Default
+    ]
+    ThreeIndexedOntology<A, AA, I, J, K> => ThreeIndexedOntology::new(
+        I::default(),
+        J::default(),
+        K::default(),
+        Default::default()
+    )
+);
+impl_ontology!(
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA> + Default,
+        J: OntologyIndex<A, AA> + Default,
+        K: OntologyIndex<A, AA> + Default,
+        L: OntologyIndex<A, AA> + Default
+    ]
+    FourIndexedOntology<A, AA, I, J, K, L> => FourIndexedOntology::new(
+        I::default(),
+        J::default(),
+        K::default(),
+        L::default(),
+        Default::default()
+    )
+);
 
 // ---------------------------------------------------------------------------
 
@@ -897,6 +1041,42 @@ impl<A: ForIRI> FromPair<A> for OntologyAnnotation<A> {
 
 // ---------------------------------------------------------------------------
 
+/// Add the prefix declared in a `PrefixDeclaration` pair to a prefix mapping.
+///
+/// The declared prefix name (`None` for the default prefix) and IRI are
+/// returned as well.
+pub(crate) fn declare_prefix<'i, A: ForIRI>(
+    pair: Pair<'i, Rule>,
+    ctx: &Context<'_, A>,
+    prefixes: &mut PrefixMapping,
+) -> Result<(Option<&'i str>, &'i str)> {
+    let span = pair.as_span();
+    let mut decl = pair.into_inner();
+    let pname = decl.next().unwrap();
+    let iri = decl.next().unwrap().into_inner().next().unwrap().as_str();
+
+    let name_span = pname.as_span();
+    let prefix = pname
+        .into_inner()
+        .next()
+        .unwrap()
+        .into_inner()
+        .next()
+        .map(|p| p.as_str());
+    check_prefix(ctx, prefixes, prefix, || {
+        Some(Location::from_span(&name_span))
+    });
+    if let Some(prefix) = prefix {
+        if prefixes.add_prefix(prefix, iri).is_err() {
+            return Err(Error::custom("reserved prefix", span));
+        }
+    } else {
+        prefixes.set_default(iri);
+    }
+
+    Ok((prefix, iri))
+}
+
 impl<A, O> FromPair<A> for (O, PrefixMapping)
 where
     A: ForIRI,
@@ -910,38 +1090,31 @@ where
         let mut prefixes = PrefixMapping::default();
         let mut inner = pairs.next().unwrap();
         while inner.as_rule() == Rule::PrefixDeclaration {
-            let mut decl = inner.into_inner();
-            let mut pname = decl.next().unwrap().into_inner();
-            let iri = decl.next().unwrap().into_inner().next().unwrap();
-
-            if let Some(prefix) = pname.next().unwrap().into_inner().next() {
-                prefixes
-                    .add_prefix(prefix.as_str(), iri.as_str())
-                    .expect("grammar does not allow invalid prefixes");
-            } else {
-                prefixes.set_default(iri.as_str());
-            }
-
+            declare_prefix(inner, ctx, &mut prefixes)?;
             inner = pairs.next().unwrap();
         }
 
-        let context = Context::new(ctx.build, &prefixes);
-        O::from_pair(inner, &context).map(|ont| (ont, prefixes))
+        O::from_pair(inner, &ctx.with_prefixes(&prefixes)).map(|ont| (ont, prefixes))
     }
 }
 
 // ---------------------------------------------------------------------------
 
+/// Remove the quotes and the escape sequences of a quoted string.
+pub(crate) fn unquote(quoted: &str) -> String {
+    let s = &quoted[1..quoted.len() - 1];
+    if s.contains(r"\\") || s.contains(r#"\""#) {
+        s.replace(r"\\", r"\").replace(r#"\""#, r#"""#)
+    } else {
+        s.to_string()
+    }
+}
+
 impl<A: ForIRI> FromPair<A> for String {
     const RULE: Rule = Rule::QuotedString;
-    fn from_pair_unchecked(pair: Pair<Rule>, _ctx: &Context<'_, A>) -> Result<Self> {
-        let l = pair.as_str().len();
-        let s = &pair.as_str()[1..l - 1];
-        if s.contains(r"\\") || s.contains(r#"\""#) {
-            Ok(s.replace(r"\\", r"\").replace(r#"\""#, r#"""#))
-        } else {
-            Ok(s.to_string())
-        }
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        ctx.limits.check_literal(pair.as_span())?;
+        Ok(unquote(pair.as_str()))
     }
 }
 
@@ -974,7 +1147,179 @@ impl<A: ForIRI> FromPair<A> for SubObjectPropertyExpression<A> {
 impl<A: ForIRI> FromPair<A> for u32 {
     const RULE: Rule = Rule::NonNegativeInteger;
     fn from_pair_unchecked(pair: Pair<Rule>, _ctx: &Context<'_, A>) -> Result<Self> {
-        Ok(Self::from_str(pair.as_str()).expect("cannot fail with the right rule"))
+        Self::from_str(pair.as_str())
+            .map_err(|_| Error::custom("integer is too large", pair.as_span()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromPair<A> for Variable<A> {
+    const RULE: Rule = Rule::Variable;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        IRI::from_pair(pair.into_inner().next().unwrap(), ctx).map(Variable)
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for IArg<A> {
+    const RULE: Rule = Rule::IArg;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::Variable => Variable::from_pair(inner, ctx).map(IArg::Variable),
+            Rule::Individual => Individual::from_pair(inner, ctx).map(IArg::Individual),
+            rule => unreachable!("unexpected rule in IArg::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for DArg<A> {
+    const RULE: Rule = Rule::DArg;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::Variable => Variable::from_pair(inner, ctx).map(DArg::Variable),
+            Rule::Literal => Literal::from_pair(inner, ctx).map(DArg::Literal),
+            rule => unreachable!("unexpected rule in DArg::from_pair: {:?}", rule),
+        }
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for Atom<A> {
+    const RULE: Rule = Rule::Atom;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let inner = pair.into_inner().next().unwrap();
+        let rule = inner.as_rule();
+        let mut pairs = inner.into_inner();
+        macro_rules! next {
+            () => {
+                FromPair::from_pair(pairs.next().unwrap(), ctx)?
+            };
+        }
+        Ok(match rule {
+            Rule::ClassAtom => Atom::ClassAtom {
+                pred: next!(),
+                arg: next!(),
+            },
+            Rule::DataRangeAtom => Atom::DataRangeAtom {
+                pred: next!(),
+                arg: next!(),
+            },
+            Rule::ObjectPropertyAtom => Atom::ObjectPropertyAtom {
+                pred: next!(),
+                args: (next!(), next!()),
+            },
+            Rule::DataPropertyAtom => Atom::DataPropertyAtom {
+                pred: next!(),
+                args: (next!(), next!()),
+            },
+            Rule::BuiltInAtom => Atom::BuiltInAtom {
+                pred: next!(),
+                args: pairs
+                    .map(|pair| DArg::from_pair(pair, ctx))
+                    .collect::<Result<_>>()?,
+            },
+            Rule::SameIndividualAtom => Atom::SameIndividualAtom(next!(), next!()),
+            Rule::DifferentIndividualsAtom => Atom::DifferentIndividualsAtom(next!(), next!()),
+            rule => unreachable!("unexpected rule in Atom::from_pair: {:?}", rule),
+        })
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for DLSafeRule<A> {
+    const RULE: Rule = Rule::DLSafeRule;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut inner = pair.into_inner();
+        let ann = FromPair::from_pair(inner.next().unwrap(), ctx)?;
+        let atoms = |pair: Pair<Rule>| {
+            pair.into_inner()
+                .map(|pair| Atom::from_pair(pair, ctx))
+                .collect::<Result<Vec<_>>>()
+        };
+        let body = atoms(inner.next().unwrap())?;
+        let head = atoms(inner.next().unwrap())?;
+        Ok(DLSafeRule { ann, body, head })
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+impl<A: ForIRI> FromPair<A> for DGNode<A> {
+    const RULE: Rule = Rule::DGNode;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        IRI::from_pair(pair.into_inner().next().unwrap(), ctx).map(DGNode)
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for NodeAssertion<A> {
+    const RULE: Rule = Rule::NodeAssertion;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut inner = pair.into_inner();
+        Ok(NodeAssertion {
+            class: FromPair::from_pair(inner.next().unwrap(), ctx)?,
+            node: FromPair::from_pair(inner.next().unwrap(), ctx)?,
+        })
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for EdgeAssertion<A> {
+    const RULE: Rule = Rule::EdgeAssertion;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut inner = pair.into_inner();
+        Ok(EdgeAssertion {
+            property: FromPair::from_pair(inner.next().unwrap(), ctx)?,
+            from: FromPair::from_pair(inner.next().unwrap(), ctx)?,
+            to: FromPair::from_pair(inner.next().unwrap(), ctx)?,
+        })
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for DescriptionGraph<A> {
+    const RULE: Rule = Rule::DGAxiom;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut inner = pair.into_inner();
+        let ann = FromPair::from_pair(inner.next().unwrap(), ctx)?;
+        let nodes = inner
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|pair| NodeAssertion::from_pair(pair, ctx))
+            .collect::<Result<_>>()?;
+        let edges = inner
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|pair| EdgeAssertion::from_pair(pair, ctx))
+            .collect::<Result<_>>()?;
+        let main_classes = inner
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|pair| Class::from_pair(pair, ctx))
+            .collect::<Result<_>>()?;
+        Ok(DescriptionGraph {
+            ann,
+            nodes,
+            edges,
+            main_classes,
+        })
+    }
+}
+
+impl<A: ForIRI> FromPair<A> for DescriptionGraphRule<A> {
+    const RULE: Rule = Rule::DGRule;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        let mut inner = pair.into_inner();
+        let ann = FromPair::from_pair(inner.next().unwrap(), ctx)?;
+        // a `DGAtom` has the same structure as an `Atom`
+        let atoms = |pair: Pair<Rule>| {
+            pair.into_inner()
+                .map(|pair| Atom::from_pair_unchecked(pair, ctx))
+                .collect::<Result<Vec<_>>>()
+        };
+        let body = atoms(inner.next().unwrap())?;
+        let head = atoms(inner.next().unwrap())?;
+        Ok(DescriptionGraphRule { ann, body, head })
     }
 }
 
@@ -987,6 +1332,7 @@ mod tests {
 
     use super::*;
     use crate::parser::OwlFunctionalParser;
+    use crate::FromFunctional;
 
     macro_rules! assert_parse_into {
         ($ty:ty, $rule:path, $build:ident, $prefixes:ident, $doc:expr, $expected:expr) => {
@@ -1022,6 +1368,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn anonymous_annotation_value() {
+        let build = Build::default();
+        let prefixes = PrefixMapping::default();
+
+        assert_parse_into!(
+            AnnotationValue<String>,
+            Rule::AnnotationValue,
+            build,
+            prefixes,
+            "_:anon",
+            AnnotationValue::IRI(build.iri(format!("{}anon", ANONYMOUS_VALUE_NAMESPACE)))
+        );
+
+        let ctx = Context::new(&build, &prefixes).with_strict(true);
+        let doc = "Annotation(<http://example.com/p> _:anon)";
+        assert!(Annotation::<String>::from_ofn_ctx(doc, &ctx).is_err());
+        let ctx = ctx.with_backend(crate::Backend::Descent);
+        assert!(Annotation::<String>::from_ofn_ctx(doc, &ctx).is_err());
+    }
+
     #[test]
     fn has_key() {
         let build = Build::default();
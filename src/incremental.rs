@@ -0,0 +1,516 @@
+//! Incremental re-parsing of a document after text edits.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use horned_owl::model::*;
+
+use crate::diagnostic::Location;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::parser::Rule;
+use crate::reader::AxiomReader;
+use crate::reader::OntologyHeader;
+use crate::scan::next_element;
+use crate::scan::skip_trivia;
+use crate::scan::Layout;
+use crate::scan::Scan;
+use crate::sink::read_ontology_axiom;
+use crate::Context;
+
+/// The axioms added and removed by an edit of an [`IncrementalDocument`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxiomChanges<A: ForIRI> {
+    /// The axioms found in the edited text but not in the previous one, in document order.
+    pub added: Vec<AnnotatedAxiom<A>>,
+    /// The axioms found in the previous text but not in the edited one, in document order.
+    pub removed: Vec<AnnotatedAxiom<A>>,
+}
+
+impl<A: ForIRI> AxiomChanges<A> {
+    /// Compare two collections of axioms, counting duplicates.
+    ///
+    /// Added and removed axioms are given in the order of `new` and `old`.
+    fn diff(old: Vec<AnnotatedAxiom<A>>, new: Vec<AnnotatedAxiom<A>>) -> Self {
+        let mut counts = HashMap::<&AnnotatedAxiom<A>, isize>::new();
+        for axiom in &old {
+            *counts.entry(axiom).or_default() -= 1;
+        }
+        for axiom in &new {
+            *counts.entry(axiom).or_default() += 1;
+        }
+        let mut changes = AxiomChanges {
+            added: Vec::new(),
+            removed: Vec::new(),
+        };
+        for axiom in &new {
+            let count = counts.get_mut(axiom).unwrap();
+            if *count > 0 {
+                *count -= 1;
+                changes.added.push(axiom.clone());
+            }
+        }
+        for axiom in &old {
+            let count = counts.get_mut(axiom).unwrap();
+            if *count < 0 {
+                *count += 1;
+                changes.removed.push(axiom.clone());
+            }
+        }
+        changes
+    }
+
+    /// Check whether the edit left the axioms unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A parsed OWL functional document that can be edited incrementally.
+///
+/// Editing the text only re-parses the top-level axioms touched by the
+/// edit, as long as the edit stays within the axioms of the ontology.
+/// Edits of the prefixes or of the ontology header, or edits changing the
+/// structure of the document (such as an unbalanced parenthesis), cause the
+/// whole document to be parsed again.
+///
+/// When an edit leaves the document invalid, the edit is still applied and
+/// the error is returned. The next edit then parses the whole document, and
+/// reports the changes since the last valid version.
+///
+/// # Example
+/// ```rust
+/// use horned_functional::IncrementalDocument;
+///
+/// let text = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n)";
+/// let mut document = IncrementalDocument::<String>::new(text).unwrap();
+///
+/// let offset = text.find("A>").unwrap();
+/// let changes = document.edit(offset..offset + 1, "B").unwrap();
+/// assert_eq!(document.text(), "Ontology(\n  Declaration(Class(<http://example.com/B>))\n)");
+/// assert_eq!(changes.added.len(), 1);
+/// assert_eq!(changes.removed.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct IncrementalDocument<'a, A: ForIRI> {
+    text: String,
+    ctx: Context<'a, A>,
+    build: Build<A>,
+    parsed: Parsed<A>,
+    valid: bool,
+}
+
+/// The result of parsing a valid version of a document.
+#[derive(Debug)]
+struct Parsed<A: ForIRI> {
+    header: OntologyHeader<A>,
+    /// The imports and ontology annotations, as axioms.
+    ontology: Vec<AnnotatedAxiom<A>>,
+    /// The offset of the first axiom, or of the end of the ontology.
+    start: usize,
+    /// The offset of the closing parenthesis of the ontology.
+    close: usize,
+    /// The location of each top-level axiom, with its value.
+    axioms: Vec<(Range<usize>, Option<AnnotatedAxiom<A>>)>,
+}
+
+impl<A: ForIRI> Parsed<A> {
+    /// Parse an entire document.
+    fn new(text: &str, ctx: &Context<'_, A>) -> Result<Self> {
+        let layout = Layout::parse(text, ctx)?;
+        let header = AxiomReader::with_context(&text.as_bytes()[..layout.header], ctx)
+            .map(AxiomReader::into_header)?;
+        let ontology = header
+            .imports
+            .iter()
+            .cloned()
+            .map(AnnotatedAxiom::from)
+            .chain(header.annotations.iter().cloned().map(AnnotatedAxiom::from))
+            .collect();
+
+        let end = layout.axioms.last().map_or(layout.header, |r| r.end);
+        let close = match next_element(&text[end..], true) {
+            Scan::Close(i) => end + i,
+            _ => unreachable!("the layout ends with a closing parenthesis"),
+        };
+
+        let ctx = ctx.with_prefixes(&header.prefixes);
+        let mut axioms = Vec::with_capacity(layout.axioms.len());
+        for range in layout.axioms {
+            let axiom = parse_axiom(text, range.clone(), &ctx)?;
+            axioms.push((range, axiom));
+        }
+
+        Ok(Self {
+            header,
+            ontology,
+            start: layout.header,
+            close,
+            axioms,
+        })
+    }
+}
+
+/// Parse the top-level axiom of a document at the given range.
+fn parse_axiom<A: ForIRI>(
+    text: &str,
+    range: Range<usize>,
+    ctx: &Context<'_, A>,
+) -> Result<Option<AnnotatedAxiom<A>>> {
+    let element = &text[range.clone()];
+    ctx.limits
+        .check_depth(element, 1)
+        .and_then(|_| parse_exact(Rule::OntologyAxiom, element, &ctx.limits))
+        .and_then(|pair| {
+            let location = Location::from_span(&pair.as_span());
+            read_ontology_axiom(pair, ctx, || Some(location.shift_in(text, range.start)))
+        })
+        .map_err(|e| e.shift_in(text, range.start))
+}
+
+impl<'a, A: ForIRI> IncrementalDocument<'a, A> {
+    /// Parse an entire document.
+    pub fn new<S: Into<String>>(text: S) -> Result<Self> {
+        Self::with_context(text, &Context::default())
+    }
+
+    /// Parse an entire document, with a context.
+    ///
+    /// The IRI builder, limits and warnings callback of the context are
+    /// used every time the document is parsed; its prefix mapping is ignored
+    /// in favour of the prefixes declared in the document. Edits going over
+    /// the limits cause the whole document to be parsed again, so that the
+    /// error is reported at the right location.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[macro_use] extern crate matches;
+    /// use horned_functional::Context;
+    /// use horned_functional::IncrementalDocument;
+    /// use horned_functional::Limits;
+    ///
+    /// let limits = Limits {
+    ///     max_axioms: 1,
+    ///     ..Limits::default()
+    /// };
+    /// let ctx = Context::default().with_limits(limits);
+    /// let text = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n)";
+    /// let mut document = IncrementalDocument::<String>::with_context(text, &ctx).unwrap();
+    ///
+    /// let offset = text.find("\n)").unwrap();
+    /// let added = "\n  Declaration(Class(<http://example.com/B>))";
+    /// assert_matches!(
+    ///     document.edit(offset..offset, added),
+    ///     Err(horned_functional::Error::LimitExceeded(_))
+    /// );
+    /// ```
+    pub fn with_context<S: Into<String>>(text: S, ctx: &Context<'a, A>) -> Result<Self> {
+        let text = text.into();
+        let build = Build::new();
+        let parsed = Parsed::new(&text, &ctx.or_build(&build))?;
+        Ok(Self {
+            text,
+            ctx: ctx.clone(),
+            build,
+            parsed,
+            valid: true,
+        })
+    }
+
+    /// Get the current text of the document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Check whether the current text of the document is valid.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// Get the prefixes and ontology header of the document.
+    pub fn header(&self) -> &OntologyHeader<A> {
+        &self.parsed.header
+    }
+
+    /// Iterate over the axioms of the document, including imports and ontology annotations.
+    ///
+    /// If the last edit made the document invalid, these are the axioms of
+    /// the last valid version of the document.
+    pub fn axioms(&self) -> impl Iterator<Item = &AnnotatedAxiom<A>> {
+        self.parsed
+            .ontology
+            .iter()
+            .chain(self.parsed.axioms.iter().filter_map(|(_, a)| a.as_ref()))
+    }
+
+    /// Replace the text at `range` with `replacement`, and parse the changes.
+    ///
+    /// # Panics
+    /// Panics if `range` is out of the bounds of the text, or does not lie
+    /// on character boundaries, like [`String::replace_range`].
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<AxiomChanges<A>> {
+        self.text.replace_range(range.clone(), replacement);
+        let delta = replacement.len() as isize - range.len() as isize;
+        let shift = |offset: usize| (offset as isize + delta) as usize;
+
+        // edits outside of the axioms require parsing the whole document
+        let parsed = &mut self.parsed;
+        if !self.valid || range.start <= parsed.start || range.end >= parsed.close {
+            return self.reparse();
+        }
+
+        // find the axioms touched by the edit, and the text around them
+        let first = parsed.axioms.partition_point(|(r, _)| r.end < range.start);
+        let last = parsed.axioms.partition_point(|(r, _)| r.start <= range.end);
+        let lo = match first {
+            0 => parsed.start,
+            i => parsed.axioms[i - 1].0.end,
+        };
+        let hi = shift(
+            parsed
+                .axioms
+                .get(last)
+                .map_or(parsed.close, |(r, _)| r.start),
+        );
+
+        // split the edited text into elements, and check they still end at `hi`
+        let mut ranges = Vec::new();
+        let mut pos = lo;
+        loop {
+            match skip_trivia(&self.text[pos..]).map(|i| pos + i) {
+                Some(i) if i == hi => break,
+                Some(i) if i < hi => (),
+                _ => return self.reparse(),
+            }
+            match next_element(&self.text[pos..], true) {
+                Scan::Element(r) if pos + r.end <= hi => {
+                    ranges.push(pos + r.start..pos + r.end);
+                    pos += r.end;
+                }
+                _ => return self.reparse(),
+            }
+        }
+
+        // let the whole document be parsed to report limit errors
+        let count = parsed.axioms.len() - (last - first) + ranges.len();
+        if count > self.ctx.limits.max_axioms || self.text.len() > self.ctx.limits.max_input_size {
+            return self.reparse();
+        }
+
+        // parse the new axioms
+        let ctx = self.ctx.or_build(&self.build);
+        let ctx = ctx.with_prefixes(&parsed.header.prefixes);
+        let mut axioms = Vec::with_capacity(ranges.len());
+        for r in ranges {
+            match parse_axiom(&self.text, r.clone(), &ctx) {
+                Ok(axiom) => axioms.push((r, axiom)),
+                Err(e) => {
+                    self.valid = false;
+                    return Err(e);
+                }
+            }
+        }
+
+        // update the locations of the axioms after the edit
+        let new = axioms.iter().filter_map(|(_, a)| a.clone()).collect();
+        for (r, _) in &mut parsed.axioms[last..] {
+            *r = shift(r.start)..shift(r.end);
+        }
+        parsed.close = shift(parsed.close);
+        let old = parsed
+            .axioms
+            .splice(first..last, axioms)
+            .filter_map(|(_, a)| a)
+            .collect();
+        Ok(AxiomChanges::diff(old, new))
+    }
+
+    /// Parse the whole document again, and compare with the last valid version.
+    fn reparse(&mut self) -> Result<AxiomChanges<A>> {
+        let old = self.axioms().cloned().collect();
+        match Parsed::new(&self.text, &self.ctx.or_build(&self.build)) {
+            Ok(parsed) => {
+                self.parsed = parsed;
+                self.valid = true;
+                Ok(AxiomChanges::diff(old, self.axioms().cloned().collect()))
+            }
+            Err(e) => {
+                self.valid = false;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Ontology(:o
+  Annotation(:comment "ontology")
+  Declaration(Class(:A))
+  Declaration(Class(:B)) # B
+  SubClassOf(:A :B)
+)
+"#;
+
+    fn class(iri: &str) -> AnnotatedAxiom<String> {
+        DeclareClass(Build::new().class(iri)).into()
+    }
+
+    /// Apply an edit, and check the document is the same as a full parse.
+    fn edit(
+        document: &mut IncrementalDocument<String>,
+        range: Range<usize>,
+        text: &str,
+    ) -> AxiomChanges<String> {
+        let changes = document.edit(range, text).unwrap();
+        let full = IncrementalDocument::<String>::new(document.text()).unwrap();
+        assert_eq!(full.parsed.axioms, document.parsed.axioms);
+        assert_eq!(full.parsed.close, document.parsed.close);
+        changes
+    }
+
+    #[test]
+    fn replace() {
+        let mut document = IncrementalDocument::new(DOC).unwrap();
+        let offset = DOC.find(":A))").unwrap() + 1;
+        let changes = edit(&mut document, offset..offset + 1, "C");
+        assert_eq!(changes.added, vec![class("http://example.com/C")]);
+        assert_eq!(changes.removed, vec![class("http://example.com/A")]);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut document = IncrementalDocument::new(DOC).unwrap();
+        let offset = DOC.find(" # B").unwrap();
+        let changes = edit(&mut document, offset..offset, " Declaration(Class(:C))");
+        assert_eq!(changes.added, vec![class("http://example.com/C")]);
+        assert!(changes.removed.is_empty());
+
+        let start = document.text().find("  Declaration(Class(:A))").unwrap();
+        let end = start + "  Declaration(Class(:A))\n".len();
+        let changes = edit(&mut document, start..end, "");
+        assert!(changes.added.is_empty());
+        assert_eq!(changes.removed, vec![class("http://example.com/A")]);
+
+        let changes = edit(&mut document, start..start, "");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn comment_out() {
+        let mut document = IncrementalDocument::new(DOC).unwrap();
+        let offset = DOC.find("Declaration(Class(:B))").unwrap();
+        let changes = edit(&mut document, offset..offset, "# ");
+        assert_eq!(changes.removed, vec![class("http://example.com/B")]);
+        let changes = edit(&mut document, offset..offset + 2, "");
+        assert_eq!(changes.added, vec![class("http://example.com/B")]);
+
+        // a comment hiding the start of the next axiom changes the structure
+        let offset = DOC.find(" # B").unwrap();
+        let changes = edit(&mut document, offset + 4..offset + 5, " ");
+        assert!(changes.added.is_empty());
+        assert_eq!(changes.removed.len(), 1);
+        assert!(matches!(changes.removed[0].axiom, Axiom::SubClassOf(_)));
+    }
+
+    #[test]
+    fn header() {
+        let mut document = IncrementalDocument::<String>::new(DOC).unwrap();
+        let offset = DOC.find("ontology").unwrap();
+        let changes = document.edit(offset..offset + 8, "o").unwrap();
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(changes.removed.len(), 1);
+        assert!(matches!(
+            changes.added[0].axiom,
+            Axiom::OntologyAnnotation(_)
+        ));
+
+        let changes = document
+            .edit(0..0, "Prefix(ex:=<http://example.com/>)\n")
+            .unwrap();
+        assert!(changes.is_empty());
+        assert!(document
+            .header()
+            .prefixes
+            .expand_curie_string("ex:A")
+            .is_ok());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut document = IncrementalDocument::<String>::new(DOC).unwrap();
+        let offset = DOC.find("Class(:B)").unwrap();
+        assert!(document.edit(offset..offset + 5, "Klass").is_err());
+        assert!(!document.is_valid());
+        assert_eq!(document.axioms().count(), 4);
+
+        let changes = document.edit(offset..offset + 5, "Class").unwrap();
+        assert!(changes.is_empty());
+        assert!(document.is_valid());
+
+        let end = DOC.find("SubClassOf").unwrap();
+        assert!(document.edit(end..end, "(").is_err());
+        let changes = document.edit(end..end + 1, "").unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn diff_order() {
+        let (a, b, c) = (
+            class("http://example.com/A"),
+            class("http://example.com/B"),
+            class("http://example.com/C"),
+        );
+        let old = vec![c.clone(), a.clone(), b.clone(), a.clone()];
+        let new = vec![b.clone(), c.clone(), a.clone(), b.clone()];
+        for _ in 0..8 {
+            let changes = AxiomChanges::diff(old.clone(), new.clone());
+            assert_eq!(changes.added, vec![b.clone()]);
+            assert_eq!(changes.removed, vec![a.clone()]);
+        }
+        let changes = AxiomChanges::diff(vec![], vec![c.clone(), a.clone(), b.clone()]);
+        assert_eq!(changes.added, vec![c, a, b]);
+    }
+
+    #[test]
+    fn context() {
+        use std::cell::RefCell;
+
+        use crate::Warning;
+        use crate::WarningKind;
+
+        let warnings = RefCell::new(Vec::new());
+        let collect = |w: Warning| {
+            warnings
+                .borrow_mut()
+                .push((w.kind, w.location.unwrap().line))
+        };
+        let ctx = Context::default().with_warnings(&collect);
+        let mut document = IncrementalDocument::<String>::with_context(DOC, &ctx).unwrap();
+        let offset = DOC.find(" # B").unwrap();
+        let changes = document
+            .edit(offset..offset, " DLSafeRule(Body() Head())")
+            .unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(warnings.into_inner(), [(WarningKind::DroppedRule, 5)]);
+
+        let limits = crate::Limits {
+            max_depth: 3,
+            ..crate::Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        let mut document = IncrementalDocument::<String>::with_context(DOC, &ctx).unwrap();
+        let err = document
+            .edit(
+                offset..offset,
+                " SubClassOf(:A ObjectComplementOf(ObjectComplementOf(:B)))",
+            )
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::LimitExceeded(_)));
+        assert_eq!(err.location().unwrap().line, 5);
+    }
+}
@@ -0,0 +1,302 @@
+//! Random access to the axioms of a document by the entities they mention.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use curie::Curie;
+use curie::PrefixMapping;
+use horned_owl::model::*;
+
+use crate::descent::split_pname;
+use crate::diagnostic::Location;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::lexer::Lexer;
+use crate::lexer::Token;
+use crate::parser::Rule;
+use crate::reader::AxiomReader;
+use crate::reader::OntologyHeader;
+use crate::scan::Layout;
+use crate::sink::read_ontology_axiom;
+use crate::Context;
+
+/// Get the IRIs mentioned in the text of an element, in order.
+///
+/// The element is only tokenized, and abbreviated IRIs that cannot be
+/// expanded are ignored.
+pub(crate) fn mentioned_iris(text: &str, prefixes: &PrefixMapping) -> Result<Vec<String>> {
+    let mut iris = Vec::new();
+    let mut lexer = Lexer::new(text);
+    while let Some((_, token)) = lexer.next_token()? {
+        match token {
+            Token::FullIRI(iri) => iris.push(iri.to_string()),
+            Token::Word(w) if !w.starts_with("_:") => {
+                if let Some((prefix, local)) = split_pname(w) {
+                    iris.extend(prefixes.expand_curie(&Curie::new(prefix, local)).ok());
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(iris)
+}
+
+/// An OWL functional document indexed by the IRIs used in its axioms.
+///
+/// Creating the index only tokenizes the axioms of the document to record
+/// the IRIs they mention, which is much cheaper than parsing them. The
+/// axioms mentioning a given IRI can then be parsed on demand. Since the
+/// document text is borrowed, it can come from a memory-mapped file.
+///
+/// Syntax errors in the axioms are only reported once they are parsed.
+/// SWRL rules and description graphs are skipped, with a warning.
+///
+/// # Example
+/// ```rust
+/// use horned_functional::IndexedDocument;
+///
+/// let text = std::fs::read_to_string("tests/data/bfo.ofn").unwrap();
+/// let document = IndexedDocument::<String>::new(&text).unwrap();
+/// let axioms = document
+///     .axioms_for("http://purl.obolibrary.org/obo/BFO_0000040")
+///     .unwrap();
+/// assert!(!axioms.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct IndexedDocument<'t, A: ForIRI> {
+    text: &'t str,
+    ctx: Context<'t, A>,
+    build: Build<A>,
+    header: OntologyHeader<A>,
+    axioms: Vec<Range<usize>>,
+    index: HashMap<String, Vec<usize>>,
+}
+
+impl<'t, A: ForIRI> IndexedDocument<'t, A> {
+    /// Read the header of a document and index its axioms.
+    pub fn new(text: &'t str) -> Result<Self> {
+        Self::with_context(text, &Context::default())
+    }
+
+    /// Read the header of a document and index its axioms, with a context.
+    ///
+    /// The IRI builder, limits and warnings callback of the context are
+    /// used when reading the header and when parsing the axioms; its prefix
+    /// mapping is ignored in favour of the prefixes declared in the document.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[macro_use] extern crate matches;
+    /// use horned_functional::Context;
+    /// use horned_functional::IndexedDocument;
+    /// use horned_functional::Limits;
+    ///
+    /// let limits = Limits {
+    ///     max_depth: 3,
+    ///     ..Limits::default()
+    /// };
+    /// let ctx = Context::default().with_limits(limits);
+    /// let text = "Ontology(SubClassOf(ObjectComplementOf(ObjectComplementOf(<http://a.com/A>)) <http://a.com/B>))";
+    /// let document = IndexedDocument::<String>::with_context(text, &ctx).unwrap();
+    /// assert_matches!(
+    ///     document.axioms_for("http://a.com/A"),
+    ///     Err(horned_functional::Error::LimitExceeded(_))
+    /// );
+    /// ```
+    pub fn with_context(text: &'t str, ctx: &Context<'t, A>) -> Result<Self> {
+        let layout = Layout::parse(text, ctx)?;
+        let header = AxiomReader::with_context(&text.as_bytes()[..layout.header], ctx)
+            .map(AxiomReader::into_header)?;
+
+        let mut index = HashMap::<String, Vec<usize>>::new();
+        for (i, range) in layout.axioms.iter().enumerate() {
+            let iris = mentioned_iris(&text[range.clone()], &header.prefixes)
+                .map_err(|e| e.shift_in(text, range.start))?;
+            for iri in iris {
+                let axioms = index.entry(iri).or_default();
+                if axioms.last() != Some(&i) {
+                    axioms.push(i);
+                }
+            }
+        }
+
+        Ok(Self {
+            text,
+            ctx: ctx.clone(),
+            build: Build::new(),
+            header,
+            axioms: layout.axioms,
+            index,
+        })
+    }
+
+    /// Get the prefixes and ontology header of the document.
+    pub fn header(&self) -> &OntologyHeader<A> {
+        &self.header
+    }
+
+    /// Get the number of axioms in the document.
+    pub fn len(&self) -> usize {
+        self.axioms.len()
+    }
+
+    /// Check whether the document contains no axioms.
+    pub fn is_empty(&self) -> bool {
+        self.axioms.is_empty()
+    }
+
+    /// Iterate over the IRIs used in the axioms of the document.
+    pub fn iris(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    /// Get the byte ranges of the axioms mentioning the given IRI.
+    pub fn ranges_for(&self, iri: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.index
+            .get(iri)
+            .into_iter()
+            .flatten()
+            .map(|&i| self.axioms[i].clone())
+    }
+
+    /// Parse the axioms mentioning the given IRI, in document order.
+    pub fn axioms_for(&self, iri: &str) -> Result<Vec<AnnotatedAxiom<A>>> {
+        let ctx = self.ctx.or_build(&self.build);
+        let ctx = ctx.with_prefixes(&self.header.prefixes);
+        let mut axioms = Vec::new();
+        for range in self.ranges_for(iri) {
+            let text = &self.text[range.clone()];
+            let axiom = ctx
+                .limits
+                .check_depth(text, 1)
+                .and_then(|_| parse_exact(Rule::OntologyAxiom, text, &ctx.limits))
+                .and_then(|pair| {
+                    let location = Location::from_span(&pair.as_span());
+                    read_ontology_axiom(pair, &ctx, || {
+                        Some(location.shift_in(self.text, range.start))
+                    })
+                })
+                .map_err(|e| e.shift_in(self.text, range.start))?;
+            axioms.extend(axiom);
+        }
+        Ok(axioms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use curie::PrefixMapping;
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::FromFunctional;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Prefix(rdfs:=<http://www.w3.org/2000/01/rdf-schema#>)
+Ontology(:o
+  Annotation(rdfs:label "ontology")
+  Declaration(Class(:A))
+  Declaration(Class(<http://example.com/B>))
+  SubClassOf(:A :B)
+  AnnotationAssertion(rdfs:comment :B "a \"quoted\" <http://example.com/A>")
+)"#;
+
+    #[test]
+    fn index() {
+        let document = IndexedDocument::<String>::new(DOC).unwrap();
+        let build = Build::new();
+        assert_eq!(document.len(), 4);
+        assert_eq!(document.header().annotations.len(), 1);
+        assert_eq!(
+            document.header().id.iri,
+            Some(build.iri("http://example.com/o"))
+        );
+
+        let a = document.axioms_for("http://example.com/A").unwrap();
+        assert_eq!(
+            a,
+            vec![
+                AnnotatedAxiom::from(DeclareClass(build.class("http://example.com/A"))),
+                AnnotatedAxiom::from(SubClassOf {
+                    sub: build.class("http://example.com/A").into(),
+                    sup: build.class("http://example.com/B").into(),
+                }),
+            ]
+        );
+        assert_eq!(
+            document.axioms_for("http://example.com/B").unwrap().len(),
+            3
+        );
+        assert!(document
+            .axioms_for("http://example.com/C")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn same_as_from_str() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("data")
+            .join("bfo.ofn");
+        let text = std::fs::read_to_string(path).unwrap();
+        let (ontology, _) = <(SetOntology<String>, PrefixMapping)>::from_ofn(&text).unwrap();
+        let document = IndexedDocument::<String>::new(&text).unwrap();
+
+        let mut axioms = HashSet::new();
+        for iri in document.iris() {
+            axioms.extend(document.axioms_for(iri).unwrap());
+        }
+        let expected = ontology
+            .iter()
+            .filter(|a| !matches!(a.axiom, Axiom::Import(_) | Axiom::OntologyAnnotation(_)))
+            .cloned()
+            .collect::<HashSet<_>>();
+        assert_eq!(axioms, expected);
+    }
+
+    #[test]
+    fn errors() {
+        let doc = "Ontology(\n  Declaration(Klass(<http://example.com/A>))\n)";
+        let document = IndexedDocument::<String>::new(doc).unwrap();
+        let err = document.axioms_for("http://example.com/A").unwrap_err();
+        let location = err.location().unwrap();
+        assert_eq!((location.line, location.column), (2, 15));
+
+        assert!(IndexedDocument::<String>::new("Ontology(").is_err());
+        assert!(IndexedDocument::<String>::new("Ontology(Declaration(Class(\"x)))").is_err());
+    }
+
+    #[test]
+    fn context() {
+        use std::cell::RefCell;
+
+        use crate::Limits;
+        use crate::Warning;
+        use crate::WarningKind;
+
+        let doc = "Ontology(\n  Declaration(Class(<http://a.com/A>))\n  DLSafeRule(Body() Head(ClassAtom(<http://a.com/A> Variable(<urn:swrl#x>))))\n)";
+        let warnings = RefCell::new(Vec::new());
+        let collect = |w: Warning| {
+            warnings
+                .borrow_mut()
+                .push((w.kind, w.location.unwrap().line))
+        };
+        let ctx = Context::default().with_warnings(&collect);
+        let document = IndexedDocument::<String>::with_context(doc, &ctx).unwrap();
+        assert_eq!(document.axioms_for("http://a.com/A").unwrap().len(), 1);
+        assert_eq!(warnings.into_inner(), [(WarningKind::DroppedRule, 3)]);
+
+        let limits = Limits {
+            max_axioms: 1,
+            ..Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        let err = IndexedDocument::<String>::with_context(doc, &ctx).unwrap_err();
+        assert!(matches!(err, crate::Error::LimitExceeded(_)));
+        assert_eq!(err.location().unwrap().line, 3);
+    }
+}
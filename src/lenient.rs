@@ -0,0 +1,591 @@
+//! Error-tolerant parsing of an OWL functional document.
+
+use std::cell::Cell;
+use std::ops::Range;
+
+use curie::PrefixMapping;
+use horned_owl::model::*;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::from_ofn::FromFunctional;
+use crate::from_pair::declare_prefix;
+use crate::parser::Rule;
+use crate::scan::is_bare;
+use crate::scan::keyword;
+use crate::scan::next_element;
+use crate::scan::skip_trivia;
+use crate::scan::Scan;
+use crate::sink::read_ontology_element;
+use crate::sink::OntologySink;
+use crate::warning::SeenAxioms;
+use crate::warning::Warning;
+use crate::Context;
+
+/// A parser that records syntax errors instead of stopping at the first one.
+struct Lenient<'t> {
+    text: &'t str,
+    pos: usize,
+    errors: Vec<Error>,
+    /// The start of the element being parsed, to relocate warnings.
+    start: &'t Cell<usize>,
+}
+
+impl<'t> Lenient<'t> {
+    /// Create an error located at the given range of the text.
+    fn error(&self, message: &str, range: Range<usize>) -> Error {
+        let span = pest::Span::new(self.text, range.start, range.end).unwrap();
+        Error::custom(message, span)
+    }
+
+    /// Create an error for an input ending in the middle of the document.
+    fn error_eof(&self) -> Error {
+        let n = self.text.len();
+        self.error("unexpected end of input", n..n)
+    }
+
+    /// Parse the given range of the text, recording the error on failure.
+    ///
+    /// The element is nested in the ontology, so its depth starts at 1.
+    fn parse<A, T>(&mut self, range: Range<usize>, ctx: &Context<'_, A>) -> Option<T>
+    where
+        A: ForIRI,
+        T: FromFunctional<A>,
+    {
+        self.start.set(range.start);
+        let text = &self.text[range.clone()];
+        ctx.limits
+            .check_depth(text, 1)
+            .and_then(|_| T::from_ofn_ctx(text, ctx))
+            .map_err(|e| self.errors.push(e.shift_in(self.text, range.start)))
+            .ok()
+    }
+
+    /// Read the prefix declarations, up to the opening of the ontology.
+    ///
+    /// Malformed declarations are skipped, but a document without an
+    /// ontology cannot be recovered from.
+    fn read_prefixes<A, S>(&mut self, ctx: &Context<'_, A>, sink: &mut S) -> Result<PrefixMapping>
+    where
+        A: ForIRI,
+        S: OntologySink<A> + ?Sized,
+    {
+        let mut prefixes = PrefixMapping::default();
+        loop {
+            let start = match skip_trivia(&self.text[self.pos..]) {
+                Some(i) => self.pos + i,
+                None => return Err(self.error_eof()),
+            };
+            if keyword(&self.text[start..]) == "Ontology" {
+                let end = start + "Ontology".len();
+                return match skip_trivia(&self.text[end..]) {
+                    Some(i) if self.text[end + i..].starts_with('(') => {
+                        self.pos = end + i + 1;
+                        Ok(prefixes)
+                    }
+                    Some(i) => Err(self.error("expected `(`", end + i..end + i)),
+                    None => Err(self.error_eof()),
+                };
+            }
+            match next_element(&self.text[self.pos..], true) {
+                Scan::Element(range) => {
+                    let range = self.pos + range.start..self.pos + range.end;
+                    self.start.set(range.start);
+                    let result = parse_exact(
+                        Rule::PrefixDeclaration,
+                        &self.text[range.clone()],
+                        &ctx.limits,
+                    )
+                    .and_then(|pair| declare_prefix(pair, ctx, &mut prefixes));
+                    match result {
+                        Ok((prefix, iri)) => sink.prefix(prefix, iri),
+                        Err(e) => self.errors.push(e.shift_in(self.text, range.start)),
+                    }
+                    self.pos = range.end;
+                }
+                Scan::Close(i) => {
+                    let i = self.pos + i;
+                    self.errors.push(self.error("unexpected `)`", i..i + 1));
+                    self.pos = i + 1;
+                }
+                Scan::Incomplete | Scan::Empty => return Err(self.error_eof()),
+            }
+        }
+    }
+
+    /// Read the ontology header, up to the first axiom.
+    fn read_header<A, S>(&mut self, ctx: &Context<'_, A>, sink: &mut S)
+    where
+        A: ForIRI,
+        S: OntologySink<A> + ?Sized,
+    {
+        let mut id = OntologyID::default();
+        let mut iris = 0;
+        let mut imports = Vec::new();
+        let mut annotations = Vec::new();
+        while let Scan::Element(range) = next_element(&self.text[self.pos..], true) {
+            let range = self.pos + range.start..self.pos + range.end;
+            let element = &self.text[range.clone()];
+            if is_bare(element) {
+                if !imports.is_empty() || !annotations.is_empty() {
+                    let e = self.error("unexpected IRI after ontology header", range.clone());
+                    self.errors.push(e);
+                } else if iris < 2 {
+                    let iri = self.parse(range.clone(), ctx);
+                    match iris {
+                        0 => id.iri = iri,
+                        _ => id.viri = iri,
+                    }
+                    iris += 1;
+                } else {
+                    let e = self.error("unexpected IRI after version IRI", range.clone());
+                    self.errors.push(e);
+                }
+            } else {
+                match keyword(element) {
+                    "Import" if !annotations.is_empty() => {
+                        let message = "unexpected import after ontology annotations";
+                        let e = self.error(message, range.clone());
+                        self.errors.push(e);
+                    }
+                    "Import" => imports.extend(self.parse::<_, Import<A>>(range.clone(), ctx)),
+                    "Annotation" => annotations
+                        .extend(self.parse::<_, OntologyAnnotation<A>>(range.clone(), ctx)),
+                    _ => break,
+                }
+            }
+            self.pos = range.end;
+        }
+
+        sink.ontology_id(id);
+        for import in imports {
+            sink.import(import);
+        }
+        for annotation in annotations {
+            sink.ontology_annotation(annotation);
+        }
+    }
+
+    /// Read the axioms, up to the end of the ontology.
+    ///
+    /// Progress is reported before each axiom. Fails if parsing is
+    /// cancelled, or if the document has more axioms than allowed by the
+    /// limits of the context.
+    fn read_axioms<A, S>(&mut self, ctx: &Context<'_, A>, sink: &mut S) -> Result<()>
+    where
+        A: ForIRI,
+        S: OntologySink<A> + ?Sized,
+    {
+        let mut seen = SeenAxioms::new(ctx);
+        let mut count = 0;
+        loop {
+            match next_element(&self.text[self.pos..], true) {
+                Scan::Element(range) => {
+                    let range = self.pos + range.start..self.pos + range.end;
+                    self.start.set(range.start);
+                    ctx.progress(range.start, count)?;
+                    count += 1;
+                    let span = pest::Span::new(self.text, range.start, range.end).unwrap();
+                    ctx.limits.check_axioms(count, span)?;
+                    let text = &self.text[range.clone()];
+                    let result = ctx
+                        .limits
+                        .check_depth(text, 1)
+                        .and_then(|_| parse_exact(Rule::OntologyAxiom, text, &ctx.limits))
+                        .and_then(|pair| read_ontology_element(pair, ctx, sink, &mut seen));
+                    if let Err(e) = result {
+                        self.errors.push(e.shift_in(self.text, range.start));
+                    }
+                    self.pos = range.end;
+                }
+                Scan::Close(i) => {
+                    self.pos += i + 1;
+                    break;
+                }
+                Scan::Incomplete | Scan::Empty => {
+                    self.errors.push(self.error_eof());
+                    self.pos = self.text.len();
+                    break;
+                }
+            }
+        }
+        ctx.progress(self.pos, count)?;
+        if let Some(i) = skip_trivia(&self.text[self.pos..]) {
+            let e = self.error("remaining input", self.pos + i..self.text.len());
+            self.errors.push(e);
+        }
+        Ok(())
+    }
+}
+
+/// Parse an entire OWL document from a string into the given sink, skipping invalid elements.
+///
+/// See [`from_str_lenient`] for the recovery strategy.
+pub fn parse_into_sink_lenient<A, S, T>(src: T, sink: &mut S) -> Result<(PrefixMapping, Vec<Error>)>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    T: AsRef<str>,
+{
+    let build = Build::new();
+    parse_into_sink_lenient_ctx(src, sink, &Context::new(&build, None))
+}
+
+/// Parse an entire OWL document from a string into the given sink with a context, skipping invalid elements.
+///
+/// The limits of the context are checked on the whole document: an input
+/// larger than the maximum input size or with more axioms than the maximum
+/// number of axioms is rejected with an error, while an element nested too
+/// deeply is skipped like any other invalid element. Progress is reported
+/// before each axiom, and parsing stops with [`Error::Cancelled`] when the
+/// cancellation token of the context is cancelled. Warnings are reported
+/// to the callback of the context, located in the whole document. The
+/// prefix mapping of the context is ignored in favour of the prefixes
+/// declared in the document.
+pub fn parse_into_sink_lenient_ctx<A, S, T>(
+    src: T,
+    sink: &mut S,
+    ctx: &Context<'_, A>,
+) -> Result<(PrefixMapping, Vec<Error>)>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    T: AsRef<str>,
+{
+    let text = src.as_ref();
+    ctx.limits.check_input_size(text.len())?;
+    let start = Cell::new(0);
+    let mut context = ctx.clone();
+    // report warnings relative to the whole document
+    let relocated;
+    if let Some(callback) = ctx.warnings {
+        let start = &start;
+        relocated = move |w: Warning| callback(w.shift_in(text, start.get()));
+        context.warnings = Some(&relocated);
+    }
+
+    let mut parser = Lenient {
+        text,
+        pos: 0,
+        errors: Vec::new(),
+        start: &start,
+    };
+    let prefixes = parser.read_prefixes(&context, sink)?;
+    let context = context.with_prefixes(&prefixes);
+    parser.read_header(&context, sink);
+    parser.read_axioms(&context, sink)?;
+    Ok((prefixes, parser.errors))
+}
+
+/// Parse an entire OWL document from a string, skipping invalid elements.
+///
+/// The document is split into its top-level elements using parenthesis
+/// balance, and each element is parsed on its own: a malformed prefix
+/// declaration, import, ontology annotation or axiom is skipped, and the
+/// error is recorded with its location in the whole document. The returned
+/// ontology contains every element that could be parsed.
+///
+/// An error is only returned when the document does not contain an
+/// ontology at all, or when it exceeds the default [`Limits`](crate::Limits).
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_owl::model::Ontology;
+///
+/// let doc = "Ontology(
+///     Declaration(Class(<http://example.com/A>))
+///     Declaration(Klass(<http://example.com/B>))
+///     Declaration(Class(<http://example.com/C>))
+/// )";
+/// let (ontology, _, errors) =
+///     horned_functional::from_str_lenient::<String, SetOntology<String>, _>(doc).unwrap();
+/// assert_eq!(ontology.iter().count(), 2);
+/// assert_eq!(errors.len(), 1);
+/// ```
+pub fn from_str_lenient<A, O, S>(src: S) -> Result<(O, PrefixMapping, Vec<Error>)>
+where
+    A: ForIRI,
+    O: Default + OntologySink<A>,
+    S: AsRef<str>,
+{
+    let mut ontology = O::default();
+    let (prefixes, errors) = parse_into_sink_lenient(src, &mut ontology)?;
+    Ok((ontology, prefixes, errors))
+}
+
+/// Parse an entire OWL document from a string with a context, skipping invalid elements.
+///
+/// See [`from_str_lenient`] for the recovery strategy, and
+/// [`parse_into_sink_lenient_ctx`] for how the context is used.
+///
+/// # Example
+/// ```rust
+/// # use std::cell::RefCell;
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_functional::Context;
+/// use horned_functional::WarningKind;
+///
+/// let warnings = RefCell::new(Vec::new());
+/// let callback = |w| warnings.borrow_mut().push(w);
+/// let ctx = Context::default().with_warnings(&callback);
+///
+/// let doc = "Ontology(
+///     Declaration(Klass(<http://example.com/A>))
+///     DLSafeRule(Body() Head())
+/// )";
+/// let (_, _, errors) =
+///     horned_functional::from_str_lenient_ctx::<String, SetOntology<String>, _>(doc, &ctx)
+///         .unwrap();
+/// assert_eq!(errors.len(), 1);
+///
+/// let warnings = warnings.into_inner();
+/// assert_eq!(warnings[0].kind, WarningKind::DroppedRule);
+/// assert_eq!(warnings[0].location.as_ref().unwrap().line, 3);
+/// ```
+pub fn from_str_lenient_ctx<A, O, S>(
+    src: S,
+    ctx: &Context<'_, A>,
+) -> Result<(O, PrefixMapping, Vec<Error>)>
+where
+    A: ForIRI,
+    O: Default + OntologySink<A>,
+    S: AsRef<str>,
+{
+    let mut ontology = O::default();
+    let (prefixes, errors) = parse_into_sink_lenient_ctx(src, &mut ontology, ctx)?;
+    Ok((ontology, prefixes, errors))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::swrl::DLSafeRule;
+    use crate::CancellationToken;
+    use crate::LimitKind;
+    use crate::Limits;
+    use crate::Progress;
+    use crate::WarningKind;
+
+    fn parse(doc: &str) -> Result<(SetOntology<String>, PrefixMapping, Vec<Error>)> {
+        from_str_lenient(doc)
+    }
+
+    fn location(error: &Error) -> (usize, usize) {
+        match error {
+            Error::Pest(e) => match e.line_col {
+                pest::error::LineColLocation::Pos(pos) => pos,
+                pest::error::LineColLocation::Span(pos, _) => pos,
+            },
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let text = std::fs::read_to_string("tests/data/bfo.ofn").unwrap();
+        let expected = crate::from_str::<String, SetOntology<String>, _>(&text).unwrap();
+        let (ontology, prefixes, errors) = parse(&text).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(ontology, expected.0);
+        assert_eq!(prefixes, expected.1);
+    }
+
+    #[test]
+    fn axioms() {
+        let doc = "Prefix(:=<http://example.com/>)\nOntology(\n  Declaration(Class(:A))\n  SubClassOf(:A)\n  Declaration(Klass(:B))\n  Declaration(Class(:C))\n)";
+        let (ontology, _, errors) = parse(doc).unwrap();
+        let build = Build::<String>::new();
+        let mut expected = SetOntology::default();
+        expected.insert(DeclareClass(build.class("http://example.com/A")));
+        expected.insert(DeclareClass(build.class("http://example.com/C")));
+        assert_eq!(ontology, expected);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(location(&errors[0]), (4, 16));
+        assert_eq!(location(&errors[1]), (5, 15));
+    }
+
+    #[test]
+    fn unbalanced() {
+        // the unclosed axiom swallows the rest of the ontology
+        let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n  Declaration(Class(<http://example.com/B>)\n  Declaration(Class(<http://example.com/C>))\n)";
+        let (ontology, _, errors) = parse(doc).unwrap();
+        assert_eq!(ontology.iter().count(), 1);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(location(&errors[0]), (3, 42));
+        assert_eq!(location(&errors[1]), (5, 2));
+    }
+
+    #[test]
+    fn header() {
+        let doc = "Prefix(ex:=<http://example.com/>)\nPrefix(broken)\nOntology(ex:o\n  Import(<http://example.com/p>)\n  Annotation(ex:label)\n  Annotation(ex:label \"o\")\n  Declaration(Class(ex:A))\n)";
+        let (ontology, prefixes, errors) = parse(doc).unwrap();
+        assert_eq!(
+            prefixes.expand_curie_string("ex:A").unwrap(),
+            "http://example.com/A"
+        );
+        assert_eq!(
+            ontology.id().iri.as_ref().map(|iri| iri.as_ref()),
+            Some("http://example.com/o")
+        );
+        assert_eq!(ontology.iter().count(), 3);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(location(&errors[0]).0, 2);
+        assert_eq!(location(&errors[1]).0, 5);
+    }
+
+    #[test]
+    fn trailer() {
+        let (ontology, _, errors) = parse("Ontology() Ontology()").unwrap();
+        assert_eq!(ontology.iter().count(), 0);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(location(&errors[0]), (1, 12));
+    }
+
+    #[test]
+    fn no_ontology() {
+        assert!(parse("Prefix(:=<http://example.com/>)").is_err());
+        assert!(parse("Ontology").is_err());
+    }
+
+    #[test]
+    fn undefined_prefix() {
+        let doc = "Prefix(ex:=<http://example.com/>)\nOntology(\n  Declaration(Class(ex:A))\n  Declaration(Class(ez:B))\n)";
+        let (ontology, _, errors) = parse(doc).unwrap();
+        assert_eq!(ontology.iter().count(), 1);
+        assert_eq!(errors.len(), 1);
+        let location = errors[0].location().unwrap();
+        assert_eq!((location.line, location.column), (4, 21));
+        assert_eq!(&doc[location.span], "ez:B");
+    }
+
+    #[test]
+    fn warnings() {
+        let doc = "Prefix(ex:=<http://example.com/>)\nPrefix(ex:=<http://example.com/>)\nOntology(\n  Declaration(Class(ex:A))\n  Declaration(Klass(ex:B))\n  DLSafeRule(Body() Head())\n  Declaration(Class(ex:A))\n)";
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w: Warning| {
+            let location = w.location.unwrap();
+            warnings
+                .borrow_mut()
+                .push((w.kind, location.line, location.column))
+        };
+        let ctx = Context::default().with_warnings(&callback);
+        let (ontology, _, errors) =
+            from_str_lenient_ctx::<String, SetOntology<String>, _>(doc, &ctx).unwrap();
+        assert_eq!(ontology.iter().count(), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            warnings.into_inner(),
+            [
+                (WarningKind::DuplicatePrefix, 2, 8),
+                (WarningKind::DroppedRule, 6, 3),
+                (WarningKind::DuplicateAxiom, 7, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn sink() {
+        #[derive(Default)]
+        struct Rules(usize);
+
+        impl OntologySink<String> for Rules {
+            fn axiom(&mut self, _axiom: AnnotatedAxiom<String>) {}
+            fn rule(&mut self, _rule: DLSafeRule<String>) -> bool {
+                self.0 += 1;
+                true
+            }
+        }
+
+        let doc =
+            "Ontology(\n  DLSafeRule(Body() Head())\n  DescriptionGraphRule(Body() Head())\n)";
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w: Warning| warnings.borrow_mut().push(w.kind);
+        let ctx = Context::default().with_warnings(&callback);
+        let mut rules = Rules::default();
+        let (_, errors) = parse_into_sink_lenient_ctx(doc, &mut rules, &ctx).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(rules.0, 1);
+        assert_eq!(
+            warnings.into_inner(),
+            [WarningKind::DroppedDescriptionGraph]
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let doc = "Ontology(\n  Declaration(Class(<http://a.com/A>))\n  SubClassOf(<http://a.com/A> ObjectComplementOf(ObjectComplementOf(<http://a.com/B>)))\n  Declaration(Class(<http://a.com/C>))\n)";
+        let parse = |limits: Limits| {
+            let ctx = Context::default().with_limits(limits);
+            from_str_lenient_ctx::<String, SetOntology<String>, _>(doc, &ctx)
+        };
+
+        // the axiom nested too deeply is skipped
+        let limits = Limits {
+            max_depth: 3,
+            ..Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        assert!(<(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx).is_err());
+        let (ontology, _, errors) = parse(limits).unwrap();
+        assert_eq!(ontology.iter().count(), 2);
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            Error::LimitExceeded(e) => {
+                assert_eq!(e.kind, LimitKind::Depth);
+                assert_eq!(e.location.as_ref().unwrap().line, 3);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        // but too many axioms or too large an input reject the document
+        let limits = Limits {
+            max_axioms: 1,
+            ..Limits::default()
+        };
+        match parse(limits) {
+            Err(Error::LimitExceeded(e)) => {
+                assert_eq!(e.kind, LimitKind::Axioms);
+                assert_eq!(e.location.unwrap().line, 3);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let limits = Limits {
+            max_input_size: 16,
+            ..Limits::default()
+        };
+        match parse(limits) {
+            Err(Error::LimitExceeded(e)) => assert_eq!(e.kind, LimitKind::InputSize),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn progress() {
+        let doc = "Ontology(\n  Declaration(Class(<http://a.com/A>))\n  Declaration(Klass(<http://a.com/B>))\n)";
+        let progress = RefCell::new(Vec::new());
+        let report = |p: Progress| progress.borrow_mut().push((p.bytes, p.axioms));
+        let ctx = Context::default().with_progress(&report);
+        from_str_lenient_ctx::<String, SetOntology<String>, _>(doc, &ctx).unwrap();
+        assert_eq!(
+            progress.into_inner(),
+            [
+                (doc.find("Declaration").unwrap(), 0),
+                (doc.find("Declaration(Klass").unwrap(), 1),
+                (doc.len(), 2)
+            ]
+        );
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let ctx = Context::default().with_cancellation(token);
+        let result = from_str_lenient_ctx::<String, SetOntology<String>, _>(doc, &ctx);
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
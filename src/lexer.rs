@@ -0,0 +1,233 @@
+//! A zero-allocation tokenizer for the OWL2 Functional-style syntax.
+//!
+//! Tokens borrow their text from the input, and whitespace and comments are
+//! skipped between tokens. The tokens are not validated beyond what is needed
+//! to find their boundaries: checking that a word is a valid prefixed name or
+//! that a full IRI is valid is left to the parser.
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::parser::Rule;
+
+/// A token of an OWL2 Functional-style document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'i> {
+    /// An opening parenthesis.
+    LParen,
+    /// A closing parenthesis.
+    RParen,
+    /// An equal sign, used in prefix declarations.
+    Eq,
+    /// A caret, two of which are used before the datatype of a literal.
+    Caret,
+    /// A full IRI, without the angle brackets.
+    FullIRI(&'i str),
+    /// A quoted string, including the quotes.
+    Quoted(&'i str),
+    /// A language tag, without the `@` sign.
+    LanguageTag(&'i str),
+    /// A keyword, a prefixed name, a blank node label or an integer.
+    Word(&'i str),
+}
+
+#[inline]
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+#[inline]
+fn is_delimiter(b: u8) -> bool {
+    is_whitespace(b)
+        || matches!(
+            b,
+            b'(' | b')' | b'"' | b'<' | b'>' | b'#' | b'^' | b'@' | b'='
+        )
+}
+
+/// A tokenizer over a string slice.
+#[derive(Debug, Clone)]
+pub(crate) struct Lexer<'i> {
+    input: &'i str,
+    pos: usize,
+}
+
+impl<'i> Lexer<'i> {
+    /// Create a new lexer over the given input.
+    pub(crate) fn new(input: &'i str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// Get the input of the lexer.
+    pub(crate) fn input(&self) -> &'i str {
+        self.input
+    }
+
+    /// Get the offset of the end of the last token read.
+    pub(crate) fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_trivia(&mut self) {
+        let bytes = self.input.as_bytes();
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b if is_whitespace(b) => self.pos += 1,
+                b'#' => {
+                    while self.pos < bytes.len() && !matches!(bytes[self.pos], b'\n' | b'\r') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Create an error for an unterminated token starting at `start`.
+    fn unterminated(&self, start: usize, rule: Rule) -> Error {
+        let pos = pest::Position::new(self.input, start).unwrap();
+        Error::from(pest::error::Error::new_from_pos(
+            pest::error::ErrorVariant::ParsingError {
+                positives: vec![rule],
+                negatives: vec![],
+            },
+            pos,
+        ))
+    }
+
+    /// Read the next token, along with its offset in the input.
+    pub(crate) fn next_token(&mut self) -> Result<Option<(usize, Token<'i>)>> {
+        self.skip_trivia();
+        let bytes = self.input.as_bytes();
+        let start = self.pos;
+        let token = match bytes.get(start) {
+            None => return Ok(None),
+            Some(b'(') => {
+                self.pos += 1;
+                Token::LParen
+            }
+            Some(b')') => {
+                self.pos += 1;
+                Token::RParen
+            }
+            Some(b'=') => {
+                self.pos += 1;
+                Token::Eq
+            }
+            Some(b'^') => {
+                self.pos += 1;
+                Token::Caret
+            }
+            Some(b'"') => {
+                let mut i = start + 1;
+                loop {
+                    match bytes.get(i) {
+                        None => return Err(self.unterminated(start, Rule::QuotedString)),
+                        Some(b'\\') if matches!(bytes.get(i + 1), Some(b'\\') | Some(b'"')) => {
+                            i += 2
+                        }
+                        Some(b'"') => break,
+                        Some(_) => i += 1,
+                    }
+                }
+                self.pos = i + 1;
+                Token::Quoted(&self.input[start..self.pos])
+            }
+            Some(b'<') => match self.input[start..].find('>') {
+                Some(i) => {
+                    self.pos = start + i + 1;
+                    Token::FullIRI(&self.input[start + 1..start + i])
+                }
+                None => return Err(self.unterminated(start, Rule::FullIRI)),
+            },
+            Some(b'@') => {
+                let mut i = start + 1;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-') {
+                    i += 1;
+                }
+                if i == start + 1 {
+                    return Err(self.unterminated(start, Rule::LanguageTag));
+                }
+                self.pos = i;
+                Token::LanguageTag(&self.input[start + 1..i])
+            }
+            Some(b'>') => return Err(self.unterminated(start, Rule::IRI)),
+            Some(_) => {
+                let mut i = start;
+                while i < bytes.len() && !is_delimiter(bytes[i]) {
+                    i += 1;
+                }
+                self.pos = i;
+                Token::Word(&self.input[start..i])
+            }
+        };
+        Ok(Some((start, token)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<Token<'_>> {
+        let mut lexer = Lexer::new(input);
+        let mut tokens = Vec::new();
+        while let Some((_, token)) = lexer.next_token().unwrap() {
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    #[test]
+    fn axiom() {
+        assert_eq!(
+            tokens("SubClassOf( # comment\n obo:A <http://example.com/B>)"),
+            vec![
+                Token::Word("SubClassOf"),
+                Token::LParen,
+                Token::Word("obo:A"),
+                Token::FullIRI("http://example.com/B"),
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(
+            tokens(r#""a \"b\""^^xsd:string "c"@en-GB"#),
+            vec![
+                Token::Quoted(r#""a \"b\"""#),
+                Token::Caret,
+                Token::Caret,
+                Token::Word("xsd:string"),
+                Token::Quoted(r#""c""#),
+                Token::LanguageTag("en-GB"),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(
+            tokens("Prefix(:=<http://example.com/>)"),
+            vec![
+                Token::Word("Prefix"),
+                Token::LParen,
+                Token::Word(":"),
+                Token::Eq,
+                Token::FullIRI("http://example.com/"),
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated() {
+        let mut lexer = Lexer::new("\"abc");
+        assert!(lexer.next_token().is_err());
+        let mut lexer = Lexer::new("<http://example.com");
+        assert!(lexer.next_token().is_err());
+    }
+}
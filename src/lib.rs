@@ -12,19 +12,43 @@ extern crate memmap;
 extern crate pest;
 
 mod as_ofn;
+pub mod ast;
+mod comments;
+mod cst;
+mod descent;
+pub mod dg;
+mod diagnostic;
+mod edit;
 mod error;
 mod from_ofn;
 mod from_pair;
-mod parser;
+mod incremental;
+mod index;
+mod lenient;
+mod lexer;
+mod limits;
+mod nested;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod parser;
+mod progress;
+mod reader;
+mod scan;
+mod side_tables;
+mod sink;
+mod source_map;
+pub mod swrl;
+mod warning;
 
 use std::borrow::Borrow;
-use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Write;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::sync::Mutex;
+use std::sync::PoisonError;
 
 use curie::PrefixMapping;
 use horned_owl::model::Build;
@@ -36,22 +60,123 @@ use horned_owl::ontology::indexed::ForIndex;
 
 pub use self::as_ofn::AsFunctional;
 pub use self::as_ofn::Functional;
+pub use self::comments::Comments;
+pub use self::cst::parse_cst;
+pub use self::cst::SyntaxElement;
+pub use self::cst::SyntaxNode;
+pub use self::cst::SyntaxToken;
+pub use self::cst::TokenKind;
+pub use self::diagnostic::Diagnostic;
+pub use self::diagnostic::Location;
+pub use self::edit::edit_str;
+pub use self::edit::edit_str_ctx;
 pub use self::error::Error;
+pub use self::error::ExpansionError;
+pub use self::error::FacetError;
+pub use self::error::LimitError;
 pub use self::error::Result;
 pub use self::from_ofn::FromFunctional;
+pub use self::from_pair::anonymous_node_id;
+pub use self::from_pair::is_anonymous_value;
+pub use self::from_pair::ANONYMOUS_VALUE_NAMESPACE;
+pub use self::incremental::AxiomChanges;
+pub use self::incremental::IncrementalDocument;
+pub use self::index::IndexedDocument;
+pub use self::lenient::from_str_lenient;
+pub use self::lenient::from_str_lenient_ctx;
+pub use self::lenient::parse_into_sink_lenient;
+pub use self::lenient::parse_into_sink_lenient_ctx;
+pub use self::limits::LimitKind;
+pub use self::limits::Limits;
+pub use self::nested::NestedAnnotations;
+#[cfg(feature = "parallel")]
+pub use self::parallel::from_str_parallel;
+#[cfg(feature = "parallel")]
+pub use self::parallel::from_str_parallel_ctx;
+pub use self::progress::CancellationToken;
+pub use self::progress::Progress;
+pub use self::reader::AxiomReader;
+pub use self::reader::OntologyHeader;
+pub use self::side_tables::from_str_with_side_tables;
+pub use self::side_tables::SideTables;
+pub use self::sink::OntologySink;
+pub use self::source_map::SourceMap;
+pub use self::warning::Warning;
+pub use self::warning::WarningKind;
+
+use self::dg::DescriptionGraphs;
+use self::swrl::DLSafeRule;
+
+/// The parser implementation used to read OWL functional documents.
+///
+/// Both backends accept the same syntax and produce the same values, so
+/// they can be used interchangeably, except to collect side tables: only
+/// the `pest` backend keeps the syntax tree they are built from, so
+/// [`from_str_with_side_tables`] rejects the descent backend.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// The reference parser generated by `pest` from the OWL2 grammar.
+    #[default]
+    Pest,
+    /// A hand-written recursive-descent parser, faster than `pest` on
+    /// large documents.
+    Descent,
+}
 
 /// A context to pass around while parsing and writing OWL functional documents.
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct Context<'a, A: ForIRI> {
     build: Option<&'a Build<A>>,
+    shared_build: Option<&'a Mutex<Build<A>>>,
     prefixes: Option<&'a PrefixMapping>,
+    backend: Backend,
+    limits: Limits,
+    progress: Option<&'a dyn Fn(Progress)>,
+    cancellation: Option<CancellationToken>,
+    comments: Option<&'a Comments<A>>,
+    nested_annotations: Option<&'a NestedAnnotations<A>>,
+    rules: Option<&'a [DLSafeRule<A>]>,
+    description_graphs: Option<&'a DescriptionGraphs<A>>,
+    warnings: Option<&'a dyn Fn(Warning)>,
+    strict: bool,
+}
+
+impl<'a, A: ForIRI> Debug for Context<'a, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("build", &self.build)
+            .field("shared_build", &self.shared_build)
+            .field("prefixes", &self.prefixes)
+            .field("backend", &self.backend)
+            .field("limits", &self.limits)
+            .field("progress", &self.progress.map(|_| ".."))
+            .field("cancellation", &self.cancellation)
+            .field("comments", &self.comments)
+            .field("nested_annotations", &self.nested_annotations)
+            .field("rules", &self.rules)
+            .field("description_graphs", &self.description_graphs)
+            .field("warnings", &self.warnings.map(|_| ".."))
+            .field("strict", &self.strict)
+            .finish()
+    }
 }
 
 impl<'a, A: ForIRI> Default for Context<'a, A> {
     fn default() -> Self {
         Self {
             build: None,
+            shared_build: None,
             prefixes: None,
+            backend: Backend::default(),
+            limits: Limits::default(),
+            progress: None,
+            cancellation: None,
+            comments: None,
+            nested_annotations: None,
+            rules: None,
+            description_graphs: None,
+            warnings: None,
+            strict: false,
         }
     }
 }
@@ -66,6 +191,191 @@ impl<'a, A: ForIRI> Context<'a, A> {
         Self {
             build: build.into(),
             prefixes: prefixes.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Use the given parser backend when parsing with this context.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use horned_owl::model::*;
+    /// use horned_functional::Backend;
+    /// use horned_functional::Context;
+    /// use horned_functional::FromFunctional;
+    ///
+    /// let ctx = Context::default().with_backend(Backend::Descent);
+    /// let decl = DeclareClass::<String>::from_ofn_ctx("Class(<http://example.com/A>)", &ctx);
+    /// assert!(decl.is_ok());
+    /// ```
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Use the given resource limits when parsing with this context.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Reject constructs that `horned-owl` cannot represent directly.
+    ///
+    /// Anonymous individuals used as annotation values are otherwise stored
+    /// as IRIs in the [`ANONYMOUS_VALUE_NAMESPACE`], and written back as
+    /// anonymous individuals.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Report the progress of the parser to the given callback.
+    ///
+    /// The callback is called before reading each axiom of an ontology, and
+    /// once all the axioms have been read.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use std::cell::Cell;
+    /// # use curie::PrefixMapping;
+    /// # use horned_owl::ontology::set::SetOntology;
+    /// use horned_functional::Context;
+    /// use horned_functional::FromFunctional;
+    ///
+    /// let axioms = Cell::new(0);
+    /// let callback = |p: horned_functional::Progress| axioms.set(p.axioms);
+    /// let ctx = Context::default().with_progress(&callback);
+    /// let doc = "Ontology(Declaration(Class(<http://example.com/A>)))";
+    /// let res = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx);
+    /// assert_eq!(axioms.get(), 1);
+    /// ```
+    pub fn with_progress<F: Fn(Progress)>(mut self, callback: &'a F) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Report the non-fatal problems found by the parser to the given callback.
+    ///
+    /// See [`Warning`] for an example. Reporting duplicate axioms needs a
+    /// copy of every axiom of the ontology, so parsing uses more memory
+    /// when a callback is given.
+    pub fn with_warnings<F: Fn(Warning)>(mut self, callback: &'a F) -> Self {
+        self.warnings = Some(callback);
+        self
+    }
+
+    /// Stop parsing with an error once the given token is cancelled.
+    ///
+    /// See [`CancellationToken`] for an example.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Write the given comments back when serializing an ontology.
+    ///
+    /// See [`Comments`] for an example.
+    pub fn with_comments(mut self, comments: &'a Comments<A>) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Write the given nested annotations back when serializing annotations.
+    ///
+    /// See [`NestedAnnotations`] for an example.
+    pub fn with_nested_annotations(mut self, nested: &'a NestedAnnotations<A>) -> Self {
+        self.nested_annotations = Some(nested);
+        self
+    }
+
+    /// Write the given SWRL rules back when serializing an ontology.
+    pub fn with_rules(mut self, rules: &'a [DLSafeRule<A>]) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// Write the given description graphs back when serializing an ontology.
+    pub fn with_description_graphs(mut self, graphs: &'a DescriptionGraphs<A>) -> Self {
+        self.description_graphs = Some(graphs);
+        self
+    }
+
+    /// Write the collected side tables back when serializing an ontology.
+    ///
+    /// The source map is not used for serializing. See [`SideTables`] for
+    /// an example.
+    pub fn with_side_tables(mut self, tables: &'a SideTables<A>) -> Self {
+        self.comments = tables.comments.as_ref().or(self.comments);
+        self.nested_annotations = tables
+            .nested_annotations
+            .as_ref()
+            .or(self.nested_annotations);
+        self.rules = tables.rules.as_deref().or(self.rules);
+        self.description_graphs = tables
+            .description_graphs
+            .as_ref()
+            .or(self.description_graphs);
+        self
+    }
+
+    /// Create a copy of this context using the given prefix mapping.
+    pub(crate) fn with_prefixes<'b>(&self, prefixes: &'b PrefixMapping) -> Context<'b, A>
+    where
+        'a: 'b,
+    {
+        Context {
+            prefixes: Some(prefixes),
+            ..self.clone()
+        }
+    }
+
+    /// Create a copy of this context using the given IRI builder if it has none.
+    pub(crate) fn or_build<'b>(&self, build: &'b Build<A>) -> Context<'b, A>
+    where
+        'a: 'b,
+    {
+        match (self.build, self.shared_build) {
+            (None, None) => Context {
+                build: Some(build),
+                ..self.clone()
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// Use the given builder, shared between threads, instead of the IRI builder.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn with_shared_build(mut self, build: &'a Mutex<Build<A>>) -> Self {
+        self.build = None;
+        self.shared_build = Some(build);
+        self
+    }
+
+    /// Report the progress of the parser, or fail if parsing was cancelled.
+    pub(crate) fn progress(&self, bytes: usize, axioms: usize) -> Result<()> {
+        if let Some(callback) = self.progress {
+            callback(Progress { bytes, axioms });
+        }
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Report a warning to the callback, if any.
+    ///
+    /// The message and location are only computed when needed.
+    pub(crate) fn warn<M, L>(&self, kind: WarningKind, message: M, location: L)
+    where
+        M: FnOnce() -> String,
+        L: FnOnce() -> Option<Location>,
+    {
+        if let Some(callback) = self.warnings {
+            callback(Warning {
+                kind,
+                message: message(),
+                location: location(),
+            });
         }
     }
 
@@ -74,18 +384,24 @@ impl<'a, A: ForIRI> Context<'a, A> {
     where
         S: Borrow<str>,
     {
-        match self.build {
-            Some(b) => b.iri(s),
-            None => Build::new().iri(s),
+        match (self.build, self.shared_build) {
+            (Some(b), _) => b.iri(s),
+            (None, Some(b)) => b.lock().unwrap_or_else(PoisonError::into_inner).iri(s),
+            (None, None) => Build::new().iri(s),
         }
     }
+
+    /// Get the resource limits used when parsing with this context.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
 }
 
 impl<'a, A: ForIRI> From<&'a Build<A>> for Context<'a, A> {
     fn from(build: &'a Build<A>) -> Self {
         Self {
             build: Some(build),
-            prefixes: None,
+            ..Default::default()
         }
     }
 }
@@ -93,13 +409,19 @@ impl<'a, A: ForIRI> From<&'a Build<A>> for Context<'a, A> {
 impl<'a, A: ForIRI> From<&'a PrefixMapping> for Context<'a, A> {
     fn from(prefixes: &'a PrefixMapping) -> Self {
         Self {
-            build: None,
             prefixes: Some(prefixes),
+            ..Default::default()
         }
     }
 }
 
 /// Parse an entire OWL document from a string.
+///
+/// The document can be parsed directly into any of the ontology types from
+/// `horned-owl`, such as `SetOntology`, `AxiomMappedOntology` or
+/// `IRIMappedOntology`. Use [`parse_into_sink`] to parse a document into an
+/// existing value of any type implementing [`OntologySink`], which includes
+/// the mutable ontology types of `horned-owl` as well as custom stores.
 #[inline]
 pub fn from_str<A, O, S>(src: S) -> Result<(O, PrefixMapping)>
 where
@@ -131,15 +453,33 @@ where
     O: Ontology<A> + FromFunctional<A>,
     P: AsRef<Path>,
 {
-    let f = File::open(path)?; // .and_then(from_reader)
+    from_file_ctx(path, &Context::default())
+}
+
+/// Parse an entire OWL document from a file, using the given context.
+///
+/// Use this to report the progress of the parser or to cancel it while
+/// loading large files, with [`Context::with_progress`] and
+/// [`Context::with_cancellation`].
+pub fn from_file_ctx<A, O, P>(path: P, ctx: &Context<'_, A>) -> Result<(O, PrefixMapping)>
+where
+    A: ForIRI,
+    O: Ontology<A> + FromFunctional<A>,
+    P: AsRef<Path>,
+{
+    let f = File::open(path)?;
     #[cfg(not(feature = "memmap"))]
-    return from_reader(f);
+    {
+        let mut s = String::new();
+        { f }.read_to_string(&mut s)?;
+        FromFunctional::from_ofn_ctx(&s, ctx)
+    }
 
     #[cfg(feature = "memmap")]
     unsafe {
         let map = memmap::Mmap::map(&f)?;
         match std::str::from_utf8(&map) {
-            Ok(text) => from_str(text),
+            Ok(text) => FromFunctional::from_ofn_ctx(text, ctx),
             Err(error) => Err(Error::IO(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 error,
@@ -148,6 +488,73 @@ where
     }
 }
 
+/// Read the prefixes and ontology header of an OWL document.
+///
+/// Input is only read up to the end of the ontology annotations, so the
+/// axioms of the document are neither parsed nor validated.
+///
+/// # Example
+/// ```rust
+/// let file = std::fs::File::open("tests/data/bfo.ofn").unwrap();
+/// let header = horned_functional::read_header::<String, _>(file).unwrap();
+/// assert_eq!(
+///     header.id.iri.as_ref().map(|iri| iri.as_ref()),
+///     Some("http://purl.obolibrary.org/obo/bfo.owl")
+/// );
+/// ```
+pub fn read_header<A, R>(r: R) -> Result<OntologyHeader<A>>
+where
+    A: ForIRI,
+    R: Read,
+{
+    AxiomReader::new(r).map(AxiomReader::into_header)
+}
+
+/// Parse an entire OWL document from a string into the given sink.
+///
+/// The elements of the document are given to the sink as they are parsed,
+/// and the prefixes declared in the document are returned.
+pub fn parse_into_sink<A, S, T>(src: T, sink: &mut S) -> Result<PrefixMapping>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    T: AsRef<str>,
+{
+    parse_into_sink_ctx(src, sink, &Context::default())
+}
+
+/// Parse an entire OWL document from a string into the given sink, with a context.
+///
+/// The limits, progress callback, cancellation token and warning callback
+/// of the context are used while parsing the document.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_functional::Context;
+/// use horned_functional::Limits;
+///
+/// let doc = "Ontology(<http://a.com/o> Declaration(Class(<http://a.com/A>)) Declaration(Class(<http://a.com/B>)))";
+/// let limits = Limits { max_axioms: 1, ..Limits::default() };
+/// let ctx = Context::<String>::default().with_limits(limits);
+///
+/// let mut ontology = SetOntology::<String>::new();
+/// assert!(horned_functional::parse_into_sink_ctx(doc, &mut ontology, &ctx).is_err());
+/// ```
+pub fn parse_into_sink_ctx<A, S, T>(
+    src: T,
+    sink: &mut S,
+    ctx: &Context<'_, A>,
+) -> Result<PrefixMapping>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    T: AsRef<str>,
+{
+    let pair = from_ofn::parse_exact(parser::Rule::OntologyDocument, src.as_ref(), &ctx.limits)?;
+    sink::read_document(pair, ctx, sink)
+}
+
 /// Render an entire OWL document to a string.
 #[inline]
 pub fn to_string<'a, A, AA, P>(ontology: &AxiomMappedOntology<A, AA>, prefixes: P) -> String
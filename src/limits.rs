@@ -0,0 +1,262 @@
+//! Resource limits for parsing untrusted input.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use pest::Span;
+
+use crate::diagnostic::Location;
+use crate::error::Error;
+use crate::error::LimitError;
+use crate::error::Result;
+use crate::scan::too_deep;
+
+/// A kind of resource limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    /// The nesting depth of parenthesized elements.
+    Depth,
+    /// The length of a quoted string, in bytes.
+    LiteralLength,
+    /// The length of an IRI, in bytes, after expansion of abbreviated IRIs.
+    IRILength,
+    /// The number of axioms in an ontology.
+    Axioms,
+    /// The size of the input, in bytes.
+    InputSize,
+}
+
+impl Display for LimitKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            LimitKind::Depth => "nesting depth",
+            LimitKind::LiteralLength => "literal length",
+            LimitKind::IRILength => "IRI length",
+            LimitKind::Axioms => "number of axioms",
+            LimitKind::InputSize => "input size",
+        })
+    }
+}
+
+/// Limits on the input accepted by the parsers.
+///
+/// Exceeding any of the limits makes the parsers return an
+/// [`Error::LimitExceeded`] instead of using an unbounded amount of stack
+/// or memory. Only the nesting depth is limited by default, to 128 levels
+/// counted from the start of the parsed text, since both parsers are
+/// recursive: raising it may overflow the stack on deeply nested inputs.
+/// Documents nested deeper than that, which were accepted by earlier
+/// versions, now need a higher `max_depth`.
+///
+/// # Example
+/// ```rust
+/// # #[macro_use] extern crate matches;
+/// # use horned_owl::model::*;
+/// use horned_functional::Context;
+/// use horned_functional::FromFunctional;
+/// use horned_functional::Limits;
+///
+/// let limits = Limits {
+///     max_literal_length: 1024,
+///     ..Limits::default()
+/// };
+/// let ctx = Context::default().with_limits(limits);
+/// let literal = format!("\"{}\"", "x".repeat(2048));
+/// let res = Literal::<String>::from_ofn_ctx(&literal, &ctx);
+/// assert_matches!(res, Err(horned_functional::Error::LimitExceeded(_)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// The maximum nesting depth of parenthesized elements.
+    pub max_depth: usize,
+    /// The maximum length of a quoted string, in bytes.
+    pub max_literal_length: usize,
+    /// The maximum length of an IRI, in bytes.
+    pub max_iri_length: usize,
+    /// The maximum number of axioms of an ontology.
+    pub max_axioms: usize,
+    /// The maximum size of the input, in bytes.
+    pub max_input_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_literal_length: usize::MAX,
+            max_iri_length: usize::MAX,
+            max_axioms: usize::MAX,
+            max_input_size: usize::MAX,
+        }
+    }
+}
+
+impl Limits {
+    /// Fail if `value` exceeds the limit of the given kind.
+    fn check(&self, kind: LimitKind, value: usize, span: Option<Span>) -> Result<()> {
+        let max = match kind {
+            LimitKind::Depth => self.max_depth,
+            LimitKind::LiteralLength => self.max_literal_length,
+            LimitKind::IRILength => self.max_iri_length,
+            LimitKind::Axioms => self.max_axioms,
+            LimitKind::InputSize => self.max_input_size,
+        };
+        if value <= max {
+            return Ok(());
+        }
+        Err(Error::LimitExceeded(Box::new(LimitError {
+            kind,
+            max,
+            location: span.map(|span| Location::from_span(&span)),
+        })))
+    }
+
+    /// Check the size of an input and the nesting depth of its elements.
+    pub(crate) fn check_input(&self, s: &str) -> Result<()> {
+        self.check_input_size(s.len())?;
+        self.check_depth(s, 0)
+    }
+
+    /// Check the nesting depth of the elements of an input nested `depth` levels deep.
+    pub(crate) fn check_depth(&self, s: &str, depth: usize) -> Result<()> {
+        match too_deep(s, self.max_depth.saturating_sub(depth)) {
+            None => Ok(()),
+            Some(i) => {
+                let span = Span::new(s, i, i + 1).unwrap();
+                self.check(LimitKind::Depth, self.max_depth + 1, Some(span))
+            }
+        }
+    }
+
+    /// Check the size of an input of which `size` bytes have been read.
+    pub(crate) fn check_input_size(&self, size: usize) -> Result<()> {
+        self.check(LimitKind::InputSize, size, None)
+    }
+
+    /// Check the length of the quoted string at the given span.
+    pub(crate) fn check_literal(&self, span: Span) -> Result<()> {
+        let len = span.as_str().len().saturating_sub(2);
+        self.check(LimitKind::LiteralLength, len, Some(span))
+    }
+
+    /// Check the length of an IRI found at the given span.
+    pub(crate) fn check_iri(&self, iri: &str, span: Span) -> Result<()> {
+        self.check(LimitKind::IRILength, iri.len(), Some(span))
+    }
+
+    /// Check the number of axioms read so far, the last one being at the given span.
+    pub(crate) fn check_axioms(&self, count: usize, span: Span) -> Result<()> {
+        self.check(LimitKind::Axioms, count, Some(span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use curie::PrefixMapping;
+    use horned_owl::model::*;
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::Backend;
+    use crate::Context;
+    use crate::FromFunctional;
+
+    fn kind(error: Error) -> (LimitKind, Option<Location>) {
+        match error {
+            Error::LimitExceeded(e) => (e.kind, e.location),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    fn contexts(limits: Limits) -> Vec<Context<'static, String>> {
+        [Backend::Pest, Backend::Descent]
+            .into_iter()
+            .map(|backend| Context::default().with_backend(backend).with_limits(limits))
+            .collect()
+    }
+
+    #[test]
+    fn depth() {
+        let limits = Limits {
+            max_depth: 3,
+            ..Limits::default()
+        };
+        for ctx in contexts(limits) {
+            let ok = "ObjectComplementOf(ObjectComplementOf(<http://a>))";
+            assert!(ClassExpression::<String>::from_ofn_ctx(ok, &ctx).is_ok());
+            let deep = "ObjectComplementOf(ObjectComplementOf(ObjectComplementOf(ObjectComplementOf(<http://a>))))";
+            let (kind, location) =
+                kind(ClassExpression::<String>::from_ofn_ctx(deep, &ctx).unwrap_err());
+            assert_eq!(kind, LimitKind::Depth);
+            assert_eq!(location.unwrap().span, 75..76);
+        }
+    }
+
+    #[test]
+    fn literal_length() {
+        let limits = Limits {
+            max_literal_length: 3,
+            ..Limits::default()
+        };
+        for ctx in contexts(limits) {
+            assert!(Literal::<String>::from_ofn_ctx("\"abc\"@en", &ctx).is_ok());
+            let err = Literal::<String>::from_ofn_ctx("\"abcd\"@en", &ctx).unwrap_err();
+            let (kind, location) = kind(err);
+            assert_eq!(kind, LimitKind::LiteralLength);
+            assert_eq!(location.unwrap().span, 0..6);
+        }
+    }
+
+    #[test]
+    fn iri_length() {
+        let limits = Limits {
+            max_iri_length: 16,
+            ..Limits::default()
+        };
+        let mut prefixes = PrefixMapping::default();
+        prefixes.add_prefix("ex", "http://example.com/").unwrap();
+        for ctx in contexts(limits) {
+            assert!(IRI::<String>::from_ofn_ctx("<http://a.com/>", &ctx).is_ok());
+            let err = IRI::<String>::from_ofn_ctx("<http://example.com/>", &ctx).unwrap_err();
+            assert_eq!(kind(err).0, LimitKind::IRILength);
+            let ctx = Context::new(None, &prefixes)
+                .with_backend(ctx.backend)
+                .with_limits(limits);
+            let err = IRI::<String>::from_ofn_ctx("ex:A", &ctx).unwrap_err();
+            let (kind, location) = kind(err);
+            assert_eq!(kind, LimitKind::IRILength);
+            assert_eq!(location.unwrap().span, 0..4);
+        }
+    }
+
+    #[test]
+    fn axioms() {
+        let limits = Limits {
+            max_axioms: 1,
+            ..Limits::default()
+        };
+        let doc =
+            "Ontology(\n  Declaration(Class(<http://a>))\n  Declaration(Class(<http://b>))\n)";
+        for ctx in contexts(limits) {
+            let err = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx).unwrap_err();
+            let (kind, location) = kind(err);
+            assert_eq!(kind, LimitKind::Axioms);
+            let location = location.unwrap();
+            assert_eq!((location.line, location.column), (3, 3));
+        }
+    }
+
+    #[test]
+    fn input_size() {
+        let limits = Limits {
+            max_input_size: 16,
+            ..Limits::default()
+        };
+        for ctx in contexts(limits) {
+            let err = IRI::<String>::from_ofn_ctx("<http://example.com/>", &ctx).unwrap_err();
+            assert_eq!(kind(err), (LimitKind::InputSize, None));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Preservation of the annotations of annotations.
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use horned_owl::model::*;
+use pest::iterators::Pair;
+
+use crate::error::Result;
+use crate::from_pair::FromPair;
+use crate::parser::Rule;
+use crate::sink::Element;
+use crate::sink::Observer;
+use crate::Context;
+
+/// The nested annotations of an axiom, keyed by the path to the annotated annotation.
+type Paths<A> = HashMap<Vec<Annotation<A>>, BTreeSet<Annotation<A>>>;
+
+/// The annotations of the annotations of an OWL functional document.
+///
+/// The `horned-owl` model has no place for the annotations of an
+/// annotation, so they are stored in this side table. Each set of nested
+/// annotations is keyed by its position: the axiom it appears in, and the
+/// path of annotations leading to the annotated annotation, starting with
+/// an annotation of the axiom. For an ontology annotation, the path starts
+/// with the ontology annotation itself. The nested annotations of an axiom
+/// appearing several times in the document are merged.
+///
+/// The table is filled in [`SideTables::nested_annotations`](crate::SideTables::nested_annotations),
+/// and written back by a context built with [`Context::with_nested_annotations`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedAnnotations<A: ForIRI> {
+    /// The nested annotations of each axiom, keyed by their path.
+    pub annotations: HashMap<AnnotatedAxiom<A>, Paths<A>>,
+}
+
+impl<A: ForIRI> Default for NestedAnnotations<A> {
+    fn default() -> Self {
+        Self {
+            annotations: HashMap::new(),
+        }
+    }
+}
+
+impl<A: ForIRI> NestedAnnotations<A> {
+    /// Get the nested annotations of the annotation at the end of a path in an axiom.
+    pub fn get(
+        &self,
+        axiom: &AnnotatedAxiom<A>,
+        path: &[Annotation<A>],
+    ) -> Option<&BTreeSet<Annotation<A>>> {
+        self.annotations.get(axiom)?.get(path)
+    }
+
+    /// Add a nested annotation to the annotation at the end of a path in an axiom.
+    pub fn insert(
+        &mut self,
+        axiom: AnnotatedAxiom<A>,
+        path: Vec<Annotation<A>>,
+        nested: Annotation<A>,
+    ) -> bool {
+        self.annotations
+            .entry(axiom)
+            .or_default()
+            .entry(path)
+            .or_default()
+            .insert(nested)
+    }
+
+    /// Record the annotations nested in an `Annotation` pair.
+    fn visit(
+        &mut self,
+        axiom: &AnnotatedAxiom<A>,
+        path: &mut Vec<Annotation<A>>,
+        pair: Pair<Rule>,
+        ctx: &Context<'_, A>,
+    ) -> Result<()> {
+        let annotations = pair.clone().into_inner().next().unwrap();
+        if annotations.clone().into_inner().next().is_none() {
+            return Ok(());
+        }
+        path.push(Annotation::from_pair(pair, ctx)?);
+        for inner in annotations.into_inner() {
+            let nested = Annotation::from_pair(inner.clone(), ctx)?;
+            self.insert(axiom.clone(), path.clone(), nested);
+            self.visit(axiom, path, inner, ctx)?;
+        }
+        path.pop();
+        Ok(())
+    }
+}
+
+impl<A: ForIRI> Observer<A> for NestedAnnotations<A> {
+    fn element(
+        &mut self,
+        pair: &Pair<Rule>,
+        element: &Element<A>,
+        ctx: &Context<'_, A>,
+    ) -> Result<()> {
+        let axiom = match element {
+            Element::Axiom(axiom) => axiom,
+            _ => return Ok(()),
+        };
+        let mut path = Vec::new();
+        match pair.as_rule() {
+            Rule::Annotation => self.visit(axiom, &mut path, pair.clone(), ctx),
+            Rule::OntologyAxiom => {
+                // the annotations of an axiom are the first child of its rule
+                let mut inner = pair.clone();
+                while inner.as_rule() != Rule::Annotations {
+                    match inner.into_inner().next() {
+                        Some(child) => inner = child,
+                        None => return Ok(()),
+                    }
+                }
+                for annotation in inner.into_inner() {
+                    self.visit(axiom, &mut path, annotation, ctx)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::side_tables::collect;
+
+    const DOC: &str = r#"Prefix(ex:=<http://example.com/>)
+Ontology(ex:o
+Annotation(Annotation(ex:date "2024") ex:version "1")
+SubClassOf(Annotation(Annotation(Annotation(ex:by ex:Curator) ex:xref "PMID:1") ex:definition "A kind of B") ex:A ex:B)
+)"#;
+
+    fn annotation(build: &Build<String>, ap: &str, av: &str) -> Annotation<String> {
+        Annotation {
+            ap: build.annotation_property(format!("http://example.com/{}", ap)),
+            av: Literal::Simple { literal: av.into() }.into(),
+        }
+    }
+
+    /// Parse a document, and write it back with its nested annotations.
+    fn roundtrip(doc: &str) -> (String, NestedAnnotations<String>) {
+        let nested = collect(doc).2.nested_annotations.unwrap();
+        (crate::side_tables::roundtrip(doc), nested)
+    }
+
+    #[test]
+    fn load() {
+        let (ontology, _, tables) = collect(DOC);
+        let nested = tables.nested_annotations.unwrap();
+        let build = Build::new();
+        assert_eq!(nested.annotations.len(), 2);
+
+        let subclass = ontology
+            .iter()
+            .find(|a| matches!(a.axiom, Axiom::SubClassOf(_)))
+            .unwrap();
+        let definition = annotation(&build, "definition", "A kind of B");
+        let xref = annotation(&build, "xref", "PMID:1");
+        assert_eq!(
+            nested.get(subclass, std::slice::from_ref(&definition)),
+            Some(&BTreeSet::from([xref.clone()]))
+        );
+        assert_eq!(
+            nested
+                .get(subclass, &[definition, xref.clone()])
+                .map(BTreeSet::len),
+            Some(1)
+        );
+        assert_eq!(nested.get(subclass, &[xref]), None);
+
+        let version = annotation(&build, "version", "1");
+        let date = annotation(&build, "date", "2024");
+        let header = AnnotatedAxiom::from(OntologyAnnotation(version.clone()));
+        assert_eq!(
+            nested.get(&header, &[version]),
+            Some(&BTreeSet::from([date]))
+        );
+    }
+
+    #[test]
+    fn write() {
+        let (written, _) = roundtrip(DOC);
+        assert_eq!(written, DOC.split_once('\n').unwrap().1);
+    }
+
+    #[test]
+    fn positions() {
+        // the same annotation is nested differently in two axioms
+        let doc = r#"Prefix(ex:=<http://example.com/>)
+Ontology(ex:o
+SubClassOf(Annotation(Annotation(ex:source "a") ex:comment "x") ex:A ex:B)
+SubClassOf(Annotation(ex:comment "x") ex:B ex:C)
+)"#;
+        let (written, nested) = roundtrip(doc);
+        assert_eq!(nested.annotations.len(), 1);
+        assert_eq!(written, doc.split_once('\n').unwrap().1);
+    }
+
+    #[test]
+    fn merge() {
+        // the nested annotations of the copies of an axiom are merged
+        let doc = r#"Prefix(ex:=<http://example.com/>)
+Ontology(ex:o
+SubClassOf(Annotation(Annotation(ex:source "a") ex:comment "x") ex:A ex:B)
+SubClassOf(Annotation(Annotation(ex:source "b") ex:comment "x") ex:A ex:B)
+)"#;
+        let (written, nested) = roundtrip(doc);
+        let paths = nested.annotations.values().next().unwrap();
+        assert_eq!(paths.values().next().map(BTreeSet::len), Some(2));
+        assert_eq!(
+            written,
+            "Ontology(ex:o\nSubClassOf(Annotation(Annotation(ex:source \"a\") Annotation(ex:source \"b\") ex:comment \"x\") ex:A ex:B)\n)"
+        );
+    }
+
+    #[test]
+    fn self_similar() {
+        // an annotation can be nested in a copy of itself
+        let doc = r#"Prefix(ex:=<http://example.com/>)
+Ontology(ex:o
+SubClassOf(Annotation(Annotation(Annotation(ex:a "1") ex:a "1") ex:a "1") ex:A ex:B)
+)"#;
+        let (written, nested) = roundtrip(doc);
+        assert_eq!(nested.annotations.values().next().unwrap().len(), 2);
+        assert_eq!(written, doc.split_once('\n').unwrap().1);
+    }
+}
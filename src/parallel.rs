@@ -0,0 +1,376 @@
+//! Multi-threaded parsing of the axioms of an OWL functional document.
+
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::thread;
+
+use curie::PrefixMapping;
+use horned_owl::model::*;
+use pest::Span;
+
+use crate::diagnostic::Location;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::from_ofn::FromFunctional;
+use crate::limits::Limits;
+use crate::parser::Rule;
+use crate::progress::CancellationToken;
+use crate::scan::next_element;
+use crate::scan::Layout;
+use crate::scan::Scan;
+use crate::sink::add_element;
+use crate::sink::read_element;
+use crate::sink::Element;
+use crate::sink::OntologySink;
+use crate::warning::SeenAxioms;
+use crate::Context;
+
+/// The settings of a context that can be shared between threads.
+///
+/// Callbacks cannot be called from other threads, so progress and warnings
+/// are only reported by the thread adding the axioms to the ontology.
+struct Settings<'a, A: ForIRI> {
+    build: &'a Mutex<Build<A>>,
+    prefixes: &'a PrefixMapping,
+    limits: Limits,
+    cancellation: Option<CancellationToken>,
+    strict: bool,
+}
+
+impl<'a, A: ForIRI> Settings<'a, A> {
+    /// Create a context with these settings.
+    fn context(&self) -> Context<'a, A> {
+        let ctx = Context::from(self.prefixes)
+            .with_limits(self.limits)
+            .with_strict(self.strict)
+            .with_shared_build(self.build);
+        match &self.cancellation {
+            Some(token) => ctx.with_cancellation(token.clone()),
+            None => ctx,
+        }
+    }
+}
+
+/// Parse the elements at the given ranges of `text`.
+fn read_chunk<A: ForIRI>(
+    text: &str,
+    ranges: &[Range<usize>],
+    settings: &Settings<'_, A>,
+) -> Result<Vec<Element<A>>> {
+    let ctx = settings.context();
+    let mut elements = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        // fail early if parsing was cancelled
+        ctx.progress(range.start, 0)?;
+        // the element is nested in the ontology
+        let element = &text[range.clone()];
+        let element = ctx
+            .limits
+            .check_depth(element, 1)
+            .and_then(|_| parse_exact(Rule::OntologyAxiom, element, &ctx.limits))
+            .and_then(|pair| read_element(pair, &ctx))
+            .map_err(|e| e.shift_in(text, range.start))?;
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+/// Parse the elements at the given ranges of `text` using several threads.
+///
+/// The ranges are split in contiguous chunks of similar size, so that the
+/// elements can be returned in document order. If several chunks contain a
+/// syntax error, the error of the first one is returned.
+fn read_elements<A: ForIRI + Send>(
+    text: &str,
+    ranges: &[Range<usize>],
+    settings: &Settings<'_, A>,
+    threads: usize,
+) -> Result<Vec<Element<A>>> {
+    let total: usize = ranges.iter().map(|r| r.len()).sum();
+    let target = total / threads + 1;
+
+    let mut chunks = Vec::with_capacity(threads);
+    let mut start = 0;
+    let mut size = 0;
+    for (i, range) in ranges.iter().enumerate() {
+        size += range.len();
+        if size >= target {
+            chunks.push(&ranges[start..=i]);
+            start = i + 1;
+            size = 0;
+        }
+    }
+    if start < ranges.len() {
+        chunks.push(&ranges[start..]);
+    }
+
+    let results = thread::scope(|scope| {
+        let handles = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || read_chunk(text, chunk, settings)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut elements = Vec::with_capacity(ranges.len());
+    for result in results {
+        elements.extend(result?);
+    }
+    Ok(elements)
+}
+
+/// Parse an entire OWL document with the given number of threads.
+fn parse<A, O>(text: &str, ctx: &Context<'_, A>, threads: usize) -> Result<(O, PrefixMapping)>
+where
+    A: ForIRI + Send,
+    O: Ontology<A> + FromFunctional<A> + OntologySink<A>,
+{
+    // let the sequential parser handle invalid documents, so that
+    // errors are reported exactly the same way
+    let layout = match Layout::scan(text) {
+        Some(layout) if threads > 1 => layout,
+        _ => return FromFunctional::from_ofn_ctx(text, ctx),
+    };
+    ctx.limits.check_input_size(text.len())?;
+
+    // parse the header as an empty ontology, interning IRIs in the
+    // builder shared by all threads
+    let build = Mutex::new(Build::new());
+    let ctx = ctx.clone().with_shared_build(&build);
+    let mut header = String::with_capacity(layout.header + 1);
+    header.push_str(&text[..layout.header]);
+    header.push(')');
+    let (mut ontology, prefixes) = <(O, PrefixMapping)>::from_ofn_ctx(&header, &ctx)?;
+
+    // parse the axioms, up to the maximum number of axioms
+    let ctx = ctx.with_prefixes(&prefixes);
+    let settings = Settings {
+        build: &build,
+        prefixes: &prefixes,
+        limits: ctx.limits,
+        cancellation: ctx.cancellation.clone(),
+        strict: ctx.strict,
+    };
+    let count = layout.axioms.len().min(ctx.limits.max_axioms);
+    let elements = read_elements(text, &layout.axioms[..count], &settings, threads)?;
+
+    // add the axioms to the ontology in document order
+    let mut seen = SeenAxioms::new(&ctx);
+    let mut elements = elements.into_iter();
+    for (i, range) in layout.axioms.iter().enumerate() {
+        let span = Span::new(text, range.start, range.end).unwrap();
+        ctx.progress(range.start, i)?;
+        ctx.limits.check_axioms(i + 1, span)?;
+        let location = || Some(Location::from_span(&span));
+        let element = elements.next().expect("axioms are parsed up to the limit");
+        add_element(element, &ctx, &mut ontology, &mut seen, location);
+    }
+    let end = layout.axioms.last().map_or(layout.header, |r| r.end);
+    let close = match next_element(&text[end..], true) {
+        Scan::Close(i) => end + i + 1,
+        _ => unreachable!("the layout ends with a closing parenthesis"),
+    };
+    ctx.progress(close, layout.axioms.len())?;
+
+    Ok((ontology, prefixes))
+}
+
+/// Parse an entire OWL document from a string using several threads.
+///
+/// The axioms of the document are parsed on all the available cores, and
+/// added to the ontology in the order they appear in the document. Errors
+/// are reported at the same location as with [`from_str`](crate::from_str).
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::ontology::set::SetOntology;
+/// let s = std::fs::read_to_string("tests/data/bfo.ofn").unwrap();
+/// let (ontology, prefixes) =
+///     horned_functional::from_str_parallel::<String, SetOntology<String>, _>(&s).unwrap();
+/// ```
+pub fn from_str_parallel<A, O, S>(src: S) -> Result<(O, PrefixMapping)>
+where
+    A: ForIRI + Send,
+    O: Ontology<A> + FromFunctional<A> + OntologySink<A>,
+    S: AsRef<str>,
+{
+    from_str_parallel_ctx(src, &Context::default())
+}
+
+/// Parse an entire OWL document from a string using several threads and the given context.
+///
+/// The limits and cancellation token of the context are used by every
+/// thread. The progress and warnings callbacks are called on the calling
+/// thread, while the parsed axioms are added to the ontology.
+///
+/// `Build` cannot be used from several threads, so the IRIs of the ontology
+/// are interned in a builder shared by all threads instead of the builder
+/// of the context.
+pub fn from_str_parallel_ctx<A, O, S>(src: S, ctx: &Context<'_, A>) -> Result<(O, PrefixMapping)>
+where
+    A: ForIRI + Send,
+    O: Ontology<A> + FromFunctional<A> + OntologySink<A>,
+    S: AsRef<str>,
+{
+    let threads = thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1);
+    parse(src.as_ref(), ctx, threads)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::error::Error;
+    use crate::WarningKind;
+
+    #[test]
+    fn same_as_from_str() {
+        let text = std::fs::read_to_string("tests/data/bfo.ofn").unwrap();
+        let expected = crate::from_str::<String, SetOntology<String>, _>(&text).unwrap();
+        let ctx = Context::default();
+        for threads in 2..5 {
+            let actual = parse::<String, SetOntology<String>>(&text, &ctx, threads).unwrap();
+            assert_eq!(actual.0, expected.0);
+            assert_eq!(actual.1, expected.1);
+        }
+    }
+
+    #[test]
+    fn axiom_order() {
+        let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n  Declaration(Class(<http://example.com/B>))\n  Declaration(Class(<http://example.com/C>))\n)";
+        let build = Mutex::new(Build::new());
+        let settings = Settings {
+            build: &build,
+            prefixes: &PrefixMapping::default(),
+            limits: Limits::default(),
+            cancellation: None,
+            strict: false,
+        };
+        let elements =
+            read_elements::<String>(doc, &Layout::scan(doc).unwrap().axioms, &settings, 3).unwrap();
+        let axioms = elements
+            .into_iter()
+            .map(|element| match element {
+                Element::Axiom(axiom) => axiom,
+                _ => panic!("unexpected element"),
+            })
+            .collect::<Vec<_>>();
+        let build = Build::new();
+        assert_eq!(
+            axioms,
+            ["A", "B", "C"]
+                .iter()
+                .map(|x| AnnotatedAxiom::from(DeclareClass(
+                    build.class(format!("http://example.com/{}", x))
+                )))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn shared_build() {
+        let doc = "Ontology(<http://example.com/o>\n  Declaration(Class(<http://example.com/o>))\n  Declaration(Class(<http://example.com/o>))\n)";
+        let ctx = Context::default();
+        let (ontology, _) = parse::<Arc<str>, SetOntology<Arc<str>>>(doc, &ctx, 2).unwrap();
+        let iri = ontology.id().iri.clone().unwrap();
+        for axiom in ontology.iter() {
+            match &axiom.axiom {
+                Axiom::DeclareClass(DeclareClass(cls)) => {
+                    assert!(Arc::ptr_eq(&cls.0.underlying(), &iri.underlying()))
+                }
+                other => panic!("unexpected axiom: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn context() {
+        let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n  DLSafeRule(Body() Head())\n  Declaration(Class(<http://example.com/A>))\n)";
+
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w: crate::Warning| {
+            let line = w.location.map(|l| l.line);
+            warnings.borrow_mut().push((w.kind, line))
+        };
+        let progress = RefCell::new(Vec::new());
+        let report = |p: crate::Progress| progress.borrow_mut().push(p.axioms);
+        let ctx = Context::default()
+            .with_warnings(&callback)
+            .with_progress(&report);
+        parse::<String, SetOntology<String>>(doc, &ctx, 2).unwrap();
+        assert_eq!(
+            warnings.into_inner(),
+            [
+                (WarningKind::DroppedRule, Some(3)),
+                (WarningKind::DuplicateAxiom, Some(4))
+            ]
+        );
+        assert_eq!(progress.into_inner().last(), Some(&3));
+
+        let limits = Limits {
+            max_axioms: 2,
+            ..Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        match parse::<String, SetOntology<String>>(doc, &ctx, 2) {
+            Err(Error::LimitExceeded(e)) => assert_eq!(e.location.unwrap().line, 4),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let ctx = Context::default().with_cancellation(token);
+        let result = parse::<String, SetOntology<String>>(doc, &ctx, 2);
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn depth() {
+        let doc = "Ontology(\n  Declaration(Class(<http://a.com/A>))\n  SubClassOf(<http://a.com/A> ObjectComplementOf(ObjectComplementOf(<http://a.com/B>)))\n)";
+        let limits = Limits {
+            max_depth: 3,
+            ..Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        for threads in 1..3 {
+            match parse::<String, SetOntology<String>>(doc, &ctx, threads) {
+                Err(Error::LimitExceeded(e)) => assert_eq!(e.location.unwrap().line, 3),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn error_location() {
+        let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n  Declaration(Class(<http://example.com/B>))\n  SubClassOf(<http://example.com/A>)\n  Declaration(Class(<http://example.com/C>))\n  Declaration(Class(:D))\n)";
+        let expected = match crate::from_str::<String, SetOntology<String>, _>(doc) {
+            Err(Error::Pest(e)) => e,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        let ctx = Context::default();
+        for threads in 2..5 {
+            match parse::<String, SetOntology<String>>(doc, &ctx, threads) {
+                Err(Error::Pest(e)) => {
+                    assert_eq!(e.location, expected.location);
+                    assert_eq!(e.line_col, expected.line_col);
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+}
@@ -1,10 +1,38 @@
-use super::error::Result;
+//! Low-level access to the `pest` parser of the OWL2 functional grammar.
+//!
+//! This module lets downstream crates parse any production of the grammar,
+//! and implement [`FromPair`] and [`FromFunctional`] for their own types.
+//! New variants may be added to [`Rule`] in minor releases when the grammar
+//! is extended, so matches on it should have a wildcard arm.
+//!
+//! # Example
+//! ```rust
+//! use horned_functional::parser::{OwlFunctionalParser, Rule};
+//! use horned_functional::Limits;
+//!
+//! let pair = OwlFunctionalParser::parse_exact(
+//!     Rule::ObjectPropertyExpression,
+//!     "ObjectInverseOf(<http://example.com/p>)",
+//!     &Limits::default(),
+//! ).unwrap();
+//! assert_eq!(pair.into_inner().next().unwrap().as_rule(), Rule::InverseObjectProperty);
+//! ```
+//!
+//! [`FromFunctional`]: ../trait.FromFunctional.html
+
+use pest::iterators::Pair;
 use pest::iterators::Pairs;
 
+use crate::error::Result;
+use crate::from_ofn;
+use crate::limits::Limits;
+
+pub use crate::from_pair::FromPair;
+
 /// The OWL2 Functional-style Syntax parser.
 ///
 /// You shouldn't have to use this type directly: instead, use the top level
-/// `parse` function to parse an ontology document.
+/// `from_str` function to parse an ontology document.
 #[derive(Debug, Parser)]
 #[grammar = "bcp47.pest"]
 #[grammar = "rfc3987.pest"]
@@ -21,9 +49,17 @@ impl OwlFunctionalParser {
     ///
     /// [`Rule`]: ./enum.Rule.html
     /// [`pest::Parser::parse`]: https://docs.rs/pest/latest/pest/trait.Parser.html
-    pub fn parse(rule: Rule, input: &str) -> Result<Pairs<Rule>> {
+    pub fn parse(rule: Rule, input: &str) -> Result<Pairs<'_, Rule>> {
         <Self as pest::Parser<Rule>>::parse(rule, input).map_err(From::from)
     }
+
+    /// Parse an input string using the given production rule, consuming all of it.
+    ///
+    /// The input is checked against the given resource limits first, and
+    /// an error is returned if the rule does not match the whole input.
+    pub fn parse_exact<'i>(rule: Rule, input: &'i str, limits: &Limits) -> Result<Pair<'i, Rule>> {
+        from_ofn::parse_exact(rule, input, limits)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,122 @@
+//! Progress reporting and cancellation of long parsing operations.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// The progress of the parser through the axioms of an ontology.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Progress {
+    /// The number of bytes of the input processed so far.
+    pub bytes: usize,
+    /// The number of axioms read so far.
+    pub axioms: usize,
+}
+
+/// A token to cooperatively cancel a parsing operation from another thread.
+///
+/// Cloned tokens share the same state, so a token can be given to a
+/// [`Context`](crate::Context) and cancelled later from a clone. The parser
+/// checks the token between axioms, and returns [`Error::Cancelled`] once
+/// it has been cancelled.
+///
+/// # Example
+/// ```rust
+/// # #[macro_use] extern crate matches;
+/// # use curie::PrefixMapping;
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_functional::CancellationToken;
+/// use horned_functional::Context;
+/// use horned_functional::FromFunctional;
+///
+/// let token = CancellationToken::new();
+/// let ctx = Context::default().with_cancellation(token.clone());
+/// token.cancel();
+///
+/// let doc = "Ontology(Declaration(Class(<http://example.com/A>)))";
+/// let res = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx);
+/// assert_matches!(res, Err(horned_functional::Error::Cancelled));
+/// ```
+///
+/// [`Error::Cancelled`]: crate::Error::Cancelled
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the parsing operations using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+
+    use curie::PrefixMapping;
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::Backend;
+    use crate::Context;
+    use crate::Error;
+    use crate::FromFunctional;
+
+    const DOC: &str = "Prefix(ex:=<http://example.com/>)
+Ontology(
+  Declaration(Class(ex:A))
+  Declaration(Class(ex:B))
+  Declaration(Class(ex:C))
+)";
+
+    #[test]
+    fn progress() {
+        for backend in [Backend::Pest, Backend::Descent] {
+            let reports = RefCell::new(Vec::new());
+            let callback = |p: Progress| reports.borrow_mut().push(p);
+            let ctx = Context::default()
+                .with_backend(backend)
+                .with_progress(&callback);
+            let (ontology, _) =
+                <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(DOC, &ctx).unwrap();
+            assert_eq!(ontology.iter().count(), 3);
+
+            let reports = reports.into_inner();
+            let axioms = reports.iter().map(|p| p.axioms).collect::<Vec<_>>();
+            assert_eq!(axioms, vec![0, 1, 2, 3]);
+            assert_eq!(reports[0].bytes, DOC.find("Declaration").unwrap());
+            assert_eq!(reports[3].bytes, DOC.len());
+        }
+    }
+
+    #[test]
+    fn cancellation() {
+        for backend in [Backend::Pest, Backend::Descent] {
+            let token = CancellationToken::new();
+            let callback = |p: Progress| {
+                if p.axioms == 2 {
+                    token.cancel();
+                }
+            };
+            let ctx = Context::default()
+                .with_backend(backend)
+                .with_progress(&callback)
+                .with_cancellation(token.clone());
+            let res = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(DOC, &ctx);
+            assert!(matches!(res, Err(Error::Cancelled)), "{:?}", res);
+        }
+    }
+}
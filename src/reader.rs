@@ -0,0 +1,642 @@
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::ops::Range;
+
+use curie::PrefixMapping;
+use horned_owl::model::*;
+
+use crate::diagnostic::Location;
+use crate::error::Error;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::from_ofn::FromFunctional;
+use crate::from_pair::declare_prefix;
+use crate::parser::Rule;
+use crate::scan::is_bare;
+use crate::scan::keyword;
+use crate::scan::skip_trivia;
+use crate::scan::Scan;
+use crate::scan::Scanner;
+use crate::warning::WarningKind;
+use crate::Context;
+
+/// The prefixes and ontology header of an OWL functional document.
+///
+/// Use [`read_header`](crate::read_header) to read the header of a document
+/// without parsing its axioms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OntologyHeader<A: ForIRI> {
+    /// The prefixes declared in the document.
+    pub prefixes: PrefixMapping,
+    /// The IRI and version IRI of the ontology.
+    pub id: OntologyID<A>,
+    /// The imports of the ontology.
+    pub imports: Vec<Import<A>>,
+    /// The ontology annotations.
+    pub annotations: Vec<OntologyAnnotation<A>>,
+}
+
+/// A streaming reader for the axioms of an OWL functional document.
+///
+/// The prefix declarations and the ontology header (IRI, version IRI,
+/// imports and ontology annotations) are read when the reader is created.
+/// Axioms are then read one at a time while iterating, so that only the
+/// text of the current axiom needs to be kept in memory.
+///
+/// A syntax error in an axiom is returned by the iterator but does not
+/// stop the iteration: the reader resumes after the end of the malformed
+/// axiom. SWRL rules and description graphs are skipped, and reported to
+/// the warnings callback of the context.
+///
+/// The [`Limits`] of the context given to [`AxiomReader::with_context`] are
+/// checked while reading: in particular, no more than
+/// [`Limits::max_input_size`] bytes are ever read from the input, so that
+/// the buffer stays bounded even if the document has no line breaks.
+///
+/// [`Limits`]: crate::Limits
+/// [`Limits::max_input_size`]: crate::Limits::max_input_size
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::AnnotatedAxiom;
+/// use horned_functional::AxiomReader;
+///
+/// let file = std::fs::File::open("tests/data/bfo.ofn").unwrap();
+/// let reader = AxiomReader::<String, _>::new(file).unwrap();
+/// assert_eq!(
+///     reader.ontology_id().iri.as_ref().map(|iri| iri.as_ref()),
+///     Some("http://purl.obolibrary.org/obo/bfo.owl")
+/// );
+///
+/// let axioms = reader.collect::<Result<Vec<AnnotatedAxiom<String>>, _>>().unwrap();
+/// assert!(!axioms.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct AxiomReader<'a, A: ForIRI, R: Read> {
+    reader: BufReader<R>,
+    buffer: String,
+    eof: bool,
+    done: bool,
+    // location of the start of the buffer in the whole document
+    offset: usize,
+    line: usize,
+    column: usize,
+    // the number of axioms read so far
+    axioms: usize,
+    // the document header
+    ctx: Context<'a, A>,
+    prefixes: PrefixMapping,
+    id: OntologyID<A>,
+    imports: Vec<Import<A>>,
+    annotations: Vec<OntologyAnnotation<A>>,
+}
+
+impl<'a, A: ForIRI, R: Read> AxiomReader<'a, A, R> {
+    /// Create a new reader, and read the document header.
+    pub fn new(reader: R) -> Result<Self> {
+        Self::with_context(reader, &Context::default())
+    }
+
+    /// Create a new reader using the given IRI builder, and read the document header.
+    pub fn with_build<B>(reader: R, build: B) -> Result<Self>
+    where
+        B: Into<Option<&'a Build<A>>>,
+    {
+        Self::with_context(reader, &Context::new(build, None))
+    }
+
+    /// Create a new reader using the given context, and read the document header.
+    ///
+    /// The IRI builder, parser backend, limits, progress and warnings
+    /// callbacks and cancellation token of the context are used while reading; its prefix
+    /// mapping is ignored in favour of the prefixes declared in the document.
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[macro_use] extern crate matches;
+    /// use horned_functional::AxiomReader;
+    /// use horned_functional::Context;
+    /// use horned_functional::Limits;
+    ///
+    /// let limits = Limits {
+    ///     max_axioms: 1,
+    ///     ..Limits::default()
+    /// };
+    /// let ctx = Context::default().with_limits(limits);
+    /// let doc = "Ontology(Declaration(Class(<http://a.com/A>)) Declaration(Class(<http://a.com/B>)))";
+    /// let mut reader = AxiomReader::<String, _>::with_context(doc.as_bytes(), &ctx).unwrap();
+    /// assert_matches!(reader.next(), Some(Ok(_)));
+    /// assert_matches!(reader.next(), Some(Err(horned_functional::Error::LimitExceeded(_))));
+    /// ```
+    pub fn with_context(reader: R, ctx: &Context<'a, A>) -> Result<Self> {
+        let mut r = Self {
+            reader: BufReader::new(reader),
+            buffer: String::new(),
+            eof: false,
+            done: false,
+            offset: 0,
+            line: 1,
+            column: 1,
+            axioms: 0,
+            ctx: ctx.clone(),
+            prefixes: PrefixMapping::default(),
+            id: OntologyID::default(),
+            imports: Vec::new(),
+            annotations: Vec::new(),
+        };
+        r.read_prefixes()?;
+        r.read_header()?;
+        Ok(r)
+    }
+
+    /// Get the prefixes declared in the document.
+    pub fn prefixes(&self) -> &PrefixMapping {
+        &self.prefixes
+    }
+
+    /// Get the ontology ID declared in the document header.
+    pub fn ontology_id(&self) -> &OntologyID<A> {
+        &self.id
+    }
+
+    /// Get the imports declared in the document header.
+    pub fn imports(&self) -> &[Import<A>] {
+        &self.imports
+    }
+
+    /// Get the ontology annotations declared in the document header.
+    pub fn annotations(&self) -> &[OntologyAnnotation<A>] {
+        &self.annotations
+    }
+
+    /// Get the document header, discarding the rest of the document.
+    pub fn into_header(self) -> OntologyHeader<A> {
+        OntologyHeader {
+            prefixes: self.prefixes,
+            id: self.id,
+            imports: self.imports,
+            annotations: self.annotations,
+        }
+    }
+
+    /// Read another line of input into the buffer.
+    ///
+    /// Fails if the input is larger than the maximum input size, without
+    /// reading more than one byte past it.
+    fn fill(&mut self) -> Result<()> {
+        let read = self.offset + self.buffer.len();
+        let left = self.ctx.limits.max_input_size.saturating_sub(read);
+        let mut reader = (&mut self.reader).take((left as u64).saturating_add(1));
+        match reader.read_line(&mut self.buffer) {
+            Ok(0) => self.eof = true,
+            Ok(_) => (),
+            // the limit may cut a character in the middle
+            Err(_) if reader.limit() == 0 => {
+                let size = self.ctx.limits.max_input_size.saturating_add(1);
+                return self.ctx.limits.check_input_size(size);
+            }
+            Err(e) => return Err(Error::from(e)),
+        }
+        self.ctx
+            .limits
+            .check_input_size(self.offset + self.buffer.len())
+    }
+
+    /// Discard the first `n` bytes of the buffer.
+    fn consume(&mut self, n: usize) {
+        let text = &self.buffer[..n];
+        match text.rfind('\n') {
+            Some(i) => {
+                self.line += text.matches('\n').count();
+                self.column = text[i + 1..].chars().count() + 1;
+            }
+            None => self.column += text.chars().count(),
+        }
+        self.offset += n;
+        self.buffer.drain(..n);
+    }
+
+    /// Find the next element, reading more input if needed.
+    ///
+    /// Any whitespace or comment before the element is consumed, so that
+    /// the element returned always starts at the beginning of the buffer.
+    /// Only the lines read since the last scan are scanned again.
+    fn next_scan(&mut self) -> Result<Scan> {
+        let mut scanner = Scanner::default();
+        loop {
+            match scanner.scan(&self.buffer, self.eof) {
+                Scan::Incomplete | Scan::Empty if !self.eof => self.fill()?,
+                Scan::Element(range) => {
+                    self.consume(range.start);
+                    return Ok(Scan::Element(0..range.end - range.start));
+                }
+                Scan::Close(i) => {
+                    self.consume(i);
+                    return Ok(Scan::Close(0));
+                }
+                other => return Ok(other),
+            }
+        }
+    }
+
+    /// Create an error located in the buffer.
+    fn error(&self, message: &str, range: Range<usize>) -> Error {
+        let span = pest::Span::new(&self.buffer, range.start, range.end).unwrap();
+        Error::custom(message, span).shift(self.offset, self.line, self.column)
+    }
+
+    /// Create an error for an input ending in the middle of the document.
+    fn error_eof(&self) -> Error {
+        let n = self.buffer.len();
+        self.error("unexpected end of input", n..n)
+    }
+
+    /// Parse the given range of the buffer.
+    fn parse<T: FromFunctional<A>>(&self, range: Range<usize>) -> Result<T> {
+        let context = self.ctx.with_prefixes(&self.prefixes);
+        T::from_ofn_ctx(&self.buffer[range], &context)
+            .map_err(|e| e.shift(self.offset, self.line, self.column))
+    }
+
+    /// Parse the axiom in the given range of the buffer.
+    fn parse_axiom(&mut self, range: Range<usize>) -> Result<AnnotatedAxiom<A>> {
+        self.ctx.progress(self.offset, self.axioms)?;
+        self.axioms += 1;
+        let span = pest::Span::new(&self.buffer, range.start, range.end).unwrap();
+        self.ctx
+            .limits
+            .check_axioms(self.axioms, span)
+            .map_err(|e| e.shift(self.offset, self.line, self.column))?;
+        self.parse(range)
+    }
+
+    /// Report the SWRL rule or description graph in the given range of the buffer as skipped.
+    fn warn_skipped(&self, kind: WarningKind, range: Range<usize>) {
+        let message = match kind {
+            WarningKind::DroppedRule => "SWRL rule is skipped by the axiom reader",
+            _ => "description graph is skipped by the axiom reader",
+        };
+        self.ctx.warn(
+            kind,
+            || String::from(message),
+            || {
+                let span = pest::Span::new(&self.buffer, range.start, range.end).unwrap();
+                let location = Location::from_span(&span);
+                Some(location.shift(self.offset, self.line, self.column))
+            },
+        );
+    }
+
+    /// Read input until the buffer contains something else than whitespace or comments.
+    fn read_trivia(&mut self) -> Result<usize> {
+        loop {
+            match skip_trivia(&self.buffer) {
+                Some(i) => return Ok(i),
+                None if self.eof => return Err(self.error_eof()),
+                None => self.fill()?,
+            }
+        }
+    }
+
+    /// Read the prefix declarations, up to the opening of the ontology.
+    fn read_prefixes(&mut self) -> Result<()> {
+        loop {
+            // make sure the buffer contains the start of the next element
+            let start = self.read_trivia()?;
+            if keyword(&self.buffer[start..]) == "Ontology" {
+                self.consume(start + "Ontology".len());
+                let start = self.read_trivia()?;
+                if !self.buffer[start..].starts_with('(') {
+                    return Err(self.error("expected `(`", start..start));
+                }
+                self.consume(start + 1);
+                return Ok(());
+            }
+            match self.next_scan()? {
+                Scan::Element(range) => {
+                    let pair = parse_exact(
+                        Rule::PrefixDeclaration,
+                        &self.buffer[range.clone()],
+                        &self.ctx.limits,
+                    )
+                    .map_err(|e| e.shift(self.offset, self.line, self.column))?;
+                    declare_prefix(pair, &self.ctx, &mut self.prefixes)?;
+                    self.consume(range.end);
+                }
+                Scan::Close(_) => return Err(self.error("unexpected `)`", 0..1)),
+                _ => return Err(self.error_eof()),
+            }
+        }
+    }
+
+    /// Read the ontology header, up to the first axiom.
+    fn read_header(&mut self) -> Result<()> {
+        while let Scan::Element(range) = self.next_scan()? {
+            let element = &self.buffer[range.clone()];
+            let kw = keyword(element);
+            if is_bare(element) {
+                if !self.imports.is_empty() || !self.annotations.is_empty() {
+                    return Err(self.error("unexpected IRI after ontology header", range));
+                } else if self.id.iri.is_none() {
+                    self.id.iri = Some(self.parse(range.clone())?);
+                } else if self.id.viri.is_none() {
+                    self.id.viri = Some(self.parse(range.clone())?);
+                } else {
+                    return Err(self.error("unexpected IRI after version IRI", range));
+                }
+            } else if kw == "Import" {
+                if !self.annotations.is_empty() {
+                    return Err(self.error("unexpected import after ontology annotations", range));
+                }
+                let import = self.parse(range.clone())?;
+                self.imports.push(import);
+            } else if kw == "Annotation" {
+                let annotation = self.parse(range.clone())?;
+                self.annotations.push(annotation);
+            } else {
+                break;
+            }
+            self.consume(range.end);
+        }
+        Ok(())
+    }
+
+    /// Check only whitespace and comments follow the end of the ontology.
+    fn read_trailer(&mut self) -> Result<()> {
+        loop {
+            match skip_trivia(&self.buffer) {
+                Some(i) => return Err(self.error("remaining input", i..self.buffer.len())),
+                None if self.eof => return Ok(()),
+                None => {
+                    self.consume(self.buffer.len());
+                    self.fill()?;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, A: ForIRI, R: Read> Iterator for AxiomReader<'a, A, R> {
+    type Item = Result<AnnotatedAxiom<A>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let scan = match self.next_scan() {
+                Ok(scan) => scan,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            match scan {
+                Scan::Element(range) => {
+                    let kw = keyword(&self.buffer[range.clone()]);
+                    let kind = match kw {
+                        "DLSafeRule" => WarningKind::DroppedRule,
+                        "DescriptionGraphRule" | "DescriptionGraph" => {
+                            WarningKind::DroppedDescriptionGraph
+                        }
+                        _ => {
+                            let result = self.parse_axiom(range.clone());
+                            self.consume(range.end);
+                            if matches!(result, Err(Error::Cancelled)) {
+                                self.done = true;
+                            }
+                            return Some(result);
+                        }
+                    };
+                    self.warn_skipped(kind, range.clone());
+                    self.consume(range.end);
+                }
+                Scan::Close(_) => {
+                    self.consume(1);
+                    self.done = true;
+                    if let Err(e) = self.ctx.progress(self.offset, self.axioms) {
+                        return Some(Err(e));
+                    }
+                    return self.read_trailer().err().map(Err);
+                }
+                Scan::Incomplete | Scan::Empty => {
+                    self.done = true;
+                    return Some(Err(self.error_eof()));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::LimitKind;
+    use crate::Limits;
+    use crate::Warning;
+
+    #[test]
+    fn header() {
+        let doc = r#"
+            Prefix(ex:=<http://example.com/>)
+            # a comment
+            Ontology(ex:ontology <http://example.com/ontology/1.0>
+                Import(<http://example.com/other>)
+                Annotation(ex:label "ontology")
+                Declaration(Class(ex:A))
+            )
+        "#;
+        let reader = AxiomReader::<String, _>::new(doc.as_bytes()).unwrap();
+        let build = Build::new();
+        assert_eq!(
+            reader.prefixes().expand_curie_string("ex:A").unwrap(),
+            "http://example.com/A"
+        );
+        assert_eq!(
+            reader.ontology_id().iri,
+            Some(build.iri("http://example.com/ontology"))
+        );
+        assert_eq!(
+            reader.ontology_id().viri,
+            Some(build.iri("http://example.com/ontology/1.0"))
+        );
+        assert_eq!(
+            reader.imports(),
+            &[Import(build.iri("http://example.com/other"))]
+        );
+        assert_eq!(reader.annotations().len(), 1);
+
+        let axioms = reader.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(
+            axioms,
+            vec![AnnotatedAxiom::from(DeclareClass(
+                build.class("http://example.com/A")
+            ))]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let reader = AxiomReader::<String, _>::new("Ontology()".as_bytes()).unwrap();
+        assert_eq!(reader.count(), 0);
+    }
+
+    #[test]
+    fn error_location() {
+        let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n  Declaration(Klass(<http://example.com/B>))\n  Declaration(Class(<http://example.com/C>))\n)";
+        let mut reader = AxiomReader::<String, _>::new(doc.as_bytes()).unwrap();
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        match reader.next() {
+            Some(Err(Error::Pest(e))) => {
+                assert_eq!(e.line_col, pest::error::LineColLocation::Pos((3, 15)));
+                assert_eq!(e.location, pest::error::InputLocation::Pos(69));
+                assert_eq!(&doc[69..74], "Klass");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn remaining_input() {
+        let doc = "Ontology() Ontology()";
+        let reader = AxiomReader::<String, _>::new(doc.as_bytes()).unwrap();
+        let result = reader.collect::<Result<Vec<_>>>();
+        assert!(matches!(result, Err(Error::Pest(_))));
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let doc = "Ontology( Declaration(Class(<http://example.com/A>)) ";
+        let reader = AxiomReader::<String, _>::new(doc.as_bytes()).unwrap();
+        let result = reader.collect::<Result<Vec<_>>>();
+        assert!(matches!(result, Err(Error::Pest(_))));
+    }
+
+    #[test]
+    fn into_header() {
+        // axioms are not parsed, so the header of an invalid document can be read
+        let doc = "Ontology(<http://example.com/o> Annotation(<http://example.com/p> \"o\") Klass(";
+        let reader = AxiomReader::<String, _>::new(doc.as_bytes()).unwrap();
+        let header = reader.into_header();
+        assert_eq!(
+            header.id.iri,
+            Some(Build::new().iri("http://example.com/o"))
+        );
+        assert!(header.imports.is_empty());
+        assert_eq!(header.annotations.len(), 1);
+    }
+
+    #[test]
+    fn input_size() {
+        let limits = Limits {
+            max_input_size: 64,
+            ..Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+
+        // the whole input is not read, although it has no line break
+        let doc = format!(
+            "Ontology({})",
+            "Declaration(Class(<http://a.com/A>)) ".repeat(1024)
+        );
+        let mut input = doc.as_bytes();
+        match AxiomReader::<String, _>::with_context(&mut input, &ctx) {
+            Err(Error::LimitExceeded(e)) => assert_eq!(e.kind, LimitKind::InputSize),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(!input.is_empty());
+
+        let doc = "Ontology(\nDeclaration(Class(<http://a.com/A>))\nDeclaration(Class(<http://a.com/B>))\n)";
+        let mut reader = AxiomReader::<String, _>::with_context(doc.as_bytes(), &ctx).unwrap();
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        assert!(matches!(reader.next(), Some(Err(Error::LimitExceeded(_)))));
+    }
+
+    #[test]
+    fn context() {
+        let doc = "Prefix(:=<http://example.com/>)\nOntology(Declaration(Class(:A)) Declaration(Class(:B)))";
+        let ctx = Context::default().with_backend(crate::Backend::Descent);
+        let reader = AxiomReader::<String, _>::with_context(doc.as_bytes(), &ctx).unwrap();
+        assert_eq!(reader.collect::<Result<Vec<_>>>().unwrap().len(), 2);
+
+        let token = crate::CancellationToken::new();
+        token.cancel();
+        let ctx = Context::default().with_cancellation(token);
+        let mut reader = AxiomReader::<String, _>::with_context(doc.as_bytes(), &ctx).unwrap();
+        assert!(matches!(reader.next(), Some(Err(Error::Cancelled))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn long_element() {
+        // an element spanning many lines is scanned once
+        let classes = (0..20_000)
+            .map(|i| format!("  <http://a.com/C{}>\n", i))
+            .collect::<String>();
+        let doc = format!("Ontology(\nDisjointClasses(\n{})\n)", classes);
+        let mut reader = AxiomReader::<String, _>::new(doc.as_bytes()).unwrap();
+        match reader.next() {
+            Some(Ok(axiom)) => assert!(matches!(axiom.axiom, Axiom::DisjointClasses(_))),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn warnings() {
+        let doc = "Ontology(\nDeclaration(Class(<http://a.com/A>))\n  DLSafeRule(Body() Head())\nDescriptionGraphRule(Body() Head())\n)";
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w: Warning| {
+            let location = w.location.unwrap();
+            warnings
+                .borrow_mut()
+                .push((w.kind, location.line, location.column))
+        };
+        let ctx = Context::default().with_warnings(&callback);
+        let reader = AxiomReader::<String, _>::with_context(doc.as_bytes(), &ctx).unwrap();
+        assert_eq!(reader.collect::<Result<Vec<_>>>().unwrap().len(), 1);
+        assert_eq!(
+            warnings.into_inner(),
+            [
+                (WarningKind::DroppedRule, 3, 3),
+                (WarningKind::DroppedDescriptionGraph, 4, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_as_from_str() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("data")
+            .join("bfo.ofn");
+        let text = std::fs::read_to_string(&path).unwrap();
+        let (ontology, prefixes) =
+            crate::from_str::<String, SetOntology<String>, _>(&text).unwrap();
+
+        let reader = AxiomReader::new(text.as_bytes()).unwrap();
+        assert_eq!(
+            reader.prefixes().mappings().collect::<HashSet<_>>(),
+            prefixes.mappings().collect::<HashSet<_>>()
+        );
+        assert_eq!(reader.ontology_id(), ontology.id());
+
+        let mut axioms = reader
+            .imports()
+            .iter()
+            .cloned()
+            .map(AnnotatedAxiom::from)
+            .chain(reader.annotations().iter().cloned().map(From::from))
+            .collect::<HashSet<_>>();
+        for result in reader {
+            axioms.insert(result.unwrap());
+        }
+        assert_eq!(axioms, ontology.into_iter().collect::<HashSet<_>>());
+    }
+}
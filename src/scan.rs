@@ -0,0 +1,482 @@
+//! A lightweight scanner to split a document into top-level elements.
+//!
+//! This does not validate the syntax of the elements in any way: it only
+//! keeps track of quoted strings, full IRIs, comments and parenthesis
+//! balance so that the proper parser can be run on a single element at
+//! a time.
+
+use std::ops::Range;
+
+use curie::PrefixMapping;
+use horned_owl::model::ForIRI;
+use horned_owl::ontology::set::SetOntology;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::Context;
+use crate::FromFunctional;
+
+/// The outcome of scanning a text for its next top-level element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Scan {
+    /// A complete element was found at the given range.
+    Element(Range<usize>),
+    /// An unbalanced closing parenthesis was found at the given offset.
+    Close(usize),
+    /// More input is needed to find the end of the next element.
+    Incomplete,
+    /// The text only contains whitespace and comments.
+    Empty,
+}
+
+#[inline]
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// Get the offset of the first byte that is not whitespace or a comment.
+pub(crate) fn skip_trivia(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b if is_whitespace(b) => i += 1,
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' && bytes[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            _ => return Some(i),
+        }
+    }
+    None
+}
+
+/// Get the keyword an element starts with, if any.
+pub(crate) fn keyword(element: &str) -> &str {
+    let end = element
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(element.len());
+    &element[..end]
+}
+
+/// Check whether an element is a bare token rather than a keyword call.
+pub(crate) fn is_bare(element: &str) -> bool {
+    let rest = &element[keyword(element).len()..];
+    !matches!(skip_trivia(rest), Some(i) if rest[i..].starts_with('('))
+}
+
+/// What the byte at the position of a [`Scanner`] is part of.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Whitespace before the element.
+    #[default]
+    Leading,
+    /// A comment before the element.
+    LeadingComment,
+    /// The element itself.
+    Code,
+    /// A quoted string.
+    String,
+    /// The character after a backslash in a quoted string.
+    Escape,
+    /// A full IRI.
+    Iri,
+    /// A comment inside the element.
+    Comment,
+    /// Whitespace after a token, which may be followed by a parenthesis.
+    Gap,
+    /// A comment after a token, which may be followed by a parenthesis.
+    GapComment,
+}
+
+/// A scanner for the next top-level element of a text growing at its end.
+///
+/// The scanner keeps its state between calls to [`Scanner::scan`], so that
+/// only the text appended since the previous call is scanned.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Scanner {
+    state: State,
+    /// The offset of the next byte to scan.
+    pos: usize,
+    /// The start of the element.
+    start: usize,
+    /// The parenthesis depth at `pos`.
+    depth: usize,
+    /// The start of the whitespace after a token.
+    gap: usize,
+}
+
+impl Scanner {
+    /// Scan `text` for the next top-level element, resuming the previous scan.
+    ///
+    /// `text` must start with the text given to the previous calls.
+    pub(crate) fn scan(&mut self, text: &str, eof: bool) -> Scan {
+        let bytes = text.as_bytes();
+        while self.pos < bytes.len() {
+            let b = bytes[self.pos];
+            match self.state {
+                State::Leading => match b {
+                    b'#' => self.state = State::LeadingComment,
+                    b')' => return Scan::Close(self.pos),
+                    b if is_whitespace(b) => (),
+                    _ => {
+                        self.start = self.pos;
+                        self.state = State::Code;
+                        continue;
+                    }
+                },
+                State::LeadingComment | State::Comment | State::GapComment => {
+                    if matches!(b, b'\n' | b'\r') {
+                        self.state = match self.state {
+                            State::LeadingComment => State::Leading,
+                            State::Comment => State::Code,
+                            _ => State::Gap,
+                        };
+                        continue;
+                    }
+                }
+                State::Code => match b {
+                    b'"' => self.state = State::String,
+                    b'<' => self.state = State::Iri,
+                    b'(' => self.depth += 1,
+                    b')' if self.depth == 0 => return Scan::Element(self.start..self.pos),
+                    b')' => {
+                        self.depth -= 1;
+                        if self.depth == 0 {
+                            return Scan::Element(self.start..self.pos + 1);
+                        }
+                    }
+                    b'#' if self.depth > 0 => self.state = State::Comment,
+                    b if self.depth == 0 && (is_whitespace(b) || b == b'#') => {
+                        // a keyword may be separated from its opening parenthesis
+                        self.gap = self.pos;
+                        self.state = State::Gap;
+                        continue;
+                    }
+                    _ => (),
+                },
+                State::String => match b {
+                    b'\\' => self.state = State::Escape,
+                    b'"' => self.state = State::Code,
+                    _ => (),
+                },
+                State::Escape => self.state = State::String,
+                State::Iri => {
+                    if b == b'>' {
+                        self.state = State::Code;
+                    }
+                }
+                State::Gap => match b {
+                    b'#' => self.state = State::GapComment,
+                    b'(' => {
+                        self.state = State::Code;
+                        continue;
+                    }
+                    b if is_whitespace(b) => (),
+                    _ => return Scan::Element(self.start..self.gap),
+                },
+            }
+            self.pos += 1;
+        }
+
+        match self.state {
+            State::Leading | State::LeadingComment => Scan::Empty,
+            State::Gap | State::GapComment if eof => Scan::Element(self.start..self.gap),
+            _ if eof => Scan::Element(self.start..bytes.len()),
+            _ => Scan::Incomplete,
+        }
+    }
+}
+
+/// Find the next top-level element in `text`.
+///
+/// An element is either a balanced expression such as `Class(obo:X)`, or
+/// a bare token such as an IRI. When `eof` is `true`, the end of `text` is
+/// considered to be the end of the input, otherwise `Scan::Incomplete` is
+/// returned when an element could continue past the end of `text`.
+pub(crate) fn next_element(text: &str, eof: bool) -> Scan {
+    Scanner::default().scan(text, eof)
+}
+
+/// Find the first opening parenthesis nested deeper than `limit`, if any.
+pub(crate) fn too_deep(text: &str, limit: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'<' => match text[i..].find('>') {
+                Some(j) => i += j,
+                None => return None,
+            },
+            b'#' => match text[i..].find(['\n', '\r']) {
+                Some(j) => i += j,
+                None => return None,
+            },
+            b'(' => {
+                depth += 1;
+                if depth > limit {
+                    return Some(i);
+                }
+            }
+            b')' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Get the text of the comments of `text`, without the leading `#`.
+pub(crate) fn comments(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'<' => match text[i..].find('>') {
+                Some(j) => i += j,
+                None => break,
+            },
+            b'#' => {
+                let end = text[i..].find(['\n', '\r']).map_or(text.len(), |j| i + j);
+                comments.push(&text[i + 1..end]);
+                i = end;
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    comments
+}
+
+/// The location of the top-level elements of a document.
+#[derive(Debug)]
+pub(crate) struct Layout {
+    /// The end of the ontology header, which is where the axioms start.
+    pub(crate) header: usize,
+    /// The ranges of the elements of `OntologyAxioms`.
+    pub(crate) axioms: Vec<Range<usize>>,
+}
+
+impl Layout {
+    /// Scan a document, or return `None` if its structure is invalid.
+    pub(crate) fn scan(text: &str) -> Option<Self> {
+        // skip the prefix declarations, up to the opening of the ontology
+        let mut pos = 0;
+        loop {
+            let start = pos + skip_trivia(&text[pos..])?;
+            if keyword(&text[start..]) == "Ontology" {
+                let end = start + "Ontology".len();
+                let open = end + skip_trivia(&text[end..])?;
+                if !text[open..].starts_with('(') {
+                    return None;
+                }
+                pos = open + 1;
+                break;
+            }
+            match next_element(&text[pos..], true) {
+                Scan::Element(range) => pos += range.end,
+                _ => return None,
+            }
+        }
+
+        // skip the ontology IRIs, the imports and the ontology annotations
+        let header = loop {
+            match next_element(&text[pos..], true) {
+                Scan::Element(range) => {
+                    let element = &text[pos + range.start..pos + range.end];
+                    if !is_bare(element) && !matches!(keyword(element), "Import" | "Annotation") {
+                        break pos + range.start;
+                    }
+                    pos += range.end;
+                }
+                Scan::Close(i) => break pos + i,
+                _ => return None,
+            }
+        };
+
+        // collect the axioms, up to the end of the ontology
+        let mut axioms = Vec::new();
+        pos = header;
+        loop {
+            match next_element(&text[pos..], true) {
+                Scan::Element(range) => {
+                    axioms.push(pos + range.start..pos + range.end);
+                    pos += range.end;
+                }
+                Scan::Close(i) => {
+                    pos += i + 1;
+                    break;
+                }
+                _ => return None,
+            }
+        }
+
+        match skip_trivia(&text[pos..]) {
+            None => Some(Layout { header, axioms }),
+            Some(_) => None,
+        }
+    }
+
+    /// Scan a document, or return the error reported by the parser if its structure is invalid.
+    ///
+    /// The input size and axiom count limits of the context are checked as well.
+    pub(crate) fn parse<A: ForIRI>(text: &str, ctx: &Context<'_, A>) -> Result<Self> {
+        ctx.limits.check_input_size(text.len())?;
+        match Layout::scan(text) {
+            Some(layout) => {
+                if let Some(range) = layout.axioms.get(ctx.limits.max_axioms) {
+                    let span = pest::Span::new(text, range.start, range.end).unwrap();
+                    ctx.limits.check_axioms(ctx.limits.max_axioms + 1, span)?;
+                }
+                Ok(layout)
+            }
+            // let the sequential parser report errors in the document structure
+            None => Err(
+                match <(SetOntology<A>, PrefixMapping)>::from_ofn_ctx(text, ctx) {
+                    Err(e) => e,
+                    Ok(_) => Error::custom(
+                        "invalid document structure",
+                        pest::Span::new(text, 0, 0).unwrap(),
+                    ),
+                },
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn element() {
+        let text = " # comment\n Declaration(Class(<http://example.com/a(b)>)) ";
+        assert_eq!(next_element(text, false), Scan::Element(12..57));
+        assert_eq!(
+            &text[12..57],
+            "Declaration(Class(<http://example.com/a(b)>))"
+        );
+    }
+
+    #[test]
+    fn element_with_string() {
+        let text = r#"AnnotationAssertion(rdfs:label obo:X "a (\"quoted\")") "#;
+        assert_eq!(next_element(text, false), Scan::Element(0..text.len() - 1));
+    }
+
+    #[test]
+    fn element_with_space() {
+        let text = "Ontology ( <http://example.com/o> )";
+        assert_eq!(next_element(text, true), Scan::Element(0..text.len()));
+    }
+
+    #[test]
+    fn bare_token() {
+        assert_eq!(next_element("obo:o Import(", false), Scan::Element(0..5));
+        assert_eq!(
+            next_element("<http://a.com/>)", false),
+            Scan::Element(0..15)
+        );
+        assert_eq!(next_element("<http://a.com/> ", false), Scan::Incomplete);
+        assert_eq!(next_element("<http://a.com/> ", true), Scan::Element(0..15));
+    }
+
+    #[test]
+    fn resume() {
+        let text = "# a\n  SubClassOf(\n  # (\n  Annotation(<http://a.com/p> \"x\\\" (\")\n  :A :B)  Class (:C)";
+        let expected = next_element(text, false);
+        match &expected {
+            Scan::Element(range) => assert!(text[range.clone()].ends_with(":A :B)")),
+            other => panic!("unexpected scan: {:?}", other),
+        }
+        for step in 1..text.len() {
+            let mut scanner = Scanner::default();
+            let mut end = 0;
+            let scan = loop {
+                end = (end + step).min(text.len());
+                match scanner.scan(&text[..end], false) {
+                    Scan::Incomplete | Scan::Empty => (),
+                    scan => break scan,
+                }
+            };
+            assert_eq!(scan, expected, "step {}", step);
+        }
+    }
+
+    #[test]
+    fn bare() {
+        assert!(is_bare("<http://a.com/>"));
+        assert!(is_bare("obo:o"));
+        assert!(!is_bare("Import (<http://a.com/>)"));
+    }
+
+    #[test]
+    fn incomplete() {
+        assert_eq!(next_element("Class(obo:X", false), Scan::Incomplete);
+        assert_eq!(next_element("Class(\"a)", false), Scan::Incomplete);
+        assert_eq!(next_element("Class(<http://", false), Scan::Incomplete);
+        assert_eq!(next_element("Class(obo:X", true), Scan::Element(0..11));
+    }
+
+    #[test]
+    fn comments() {
+        let text = "# a\nPrefix(owl:=<http://www.w3.org/2002/07/owl#>) \"#\" #b\r\n#";
+        assert_eq!(super::comments(text), vec![" a", "b", ""]);
+    }
+
+    #[test]
+    fn depth() {
+        let text = "A(B(\"((\" <http://a.com/(> # (\n C(D)))";
+        assert_eq!(too_deep(text, 3), None);
+        assert_eq!(too_deep(text, 2), Some(32));
+        assert_eq!(too_deep("(()(()))", 2), Some(4));
+    }
+
+    #[test]
+    fn close_and_empty() {
+        assert_eq!(next_element("  )", false), Scan::Close(2));
+        assert_eq!(next_element("  # nothing", false), Scan::Empty);
+    }
+
+    #[test]
+    fn layout() {
+        let doc = "Prefix(:=<http://example.com/>)\nOntology(:o\n  Import(:p)\n  Declaration(Class(:A))\n  SubClassOf(:A :B)\n)\n# end\n";
+        let layout = Layout::scan(doc).unwrap();
+        assert_eq!(
+            &doc[..layout.header],
+            "Prefix(:=<http://example.com/>)\nOntology(:o\n  Import(:p)\n  "
+        );
+        assert_eq!(layout.axioms.len(), 2);
+        assert_eq!(&doc[layout.axioms[1].clone()], "SubClassOf(:A :B)");
+        assert!(Layout::scan("Ontology() x").is_none());
+        assert!(Layout::scan("Ontology(").is_none());
+    }
+
+    #[test]
+    fn layout_error() {
+        let err = Layout::parse::<String>(
+            "Ontology(\n  Declaration(Class(<http://a.com/A>))\n",
+            &Context::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Pest(_)));
+        assert_eq!(err.location().unwrap().line, 3);
+        assert!(Layout::parse::<String>("Ontology()", &Context::default()).is_ok());
+    }
+}
@@ -0,0 +1,327 @@
+//! Collection of what `horned-owl` ontologies cannot store, in one parse.
+
+use curie::PrefixMapping;
+use horned_owl::model::*;
+use pest::iterators::Pair;
+
+use crate::comments::CommentCollector;
+use crate::comments::Comments;
+use crate::dg::DescriptionGraph;
+use crate::dg::DescriptionGraphRule;
+use crate::dg::DescriptionGraphs;
+use crate::error::Error;
+use crate::error::Result;
+use crate::from_ofn::parse_exact;
+use crate::nested::NestedAnnotations;
+use crate::parser::Rule;
+use crate::sink::read_document_with;
+use crate::sink::Element;
+use crate::sink::Observer;
+use crate::sink::OntologySink;
+use crate::source_map::SourceMap;
+use crate::swrl::DLSafeRule;
+use crate::Backend;
+use crate::Context;
+
+/// The side tables of an OWL functional document.
+///
+/// Each table is only collected if it is `Some` when the document is
+/// parsed with [`from_str_with_side_tables`]. Pass the tables to
+/// [`Context::with_side_tables`] to write them back when serializing an
+/// ontology.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_functional::AsFunctional;
+/// use horned_functional::Context;
+/// use horned_functional::SideTables;
+///
+/// let doc = "Ontology(<http://example.com/o>\n# Class: A\nDeclaration(Class(<http://example.com/A>))\n)";
+/// let mut tables = SideTables::all();
+/// let (ontology, prefixes) = horned_functional::from_str_with_side_tables::<
+///     String,
+///     SetOntology<String>,
+///     _,
+/// >(doc, &Context::default(), &mut tables)
+/// .unwrap();
+///
+/// let ctx = Context::from(&prefixes).with_side_tables(&tables);
+/// assert_eq!(ontology.as_ofn_ctx(&ctx).to_string(), doc);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SideTables<A: ForIRI> {
+    /// The comments of the document.
+    pub comments: Option<Comments<A>>,
+    /// The annotations of the annotations of the document.
+    pub nested_annotations: Option<NestedAnnotations<A>>,
+    /// The locations of the elements of the document.
+    pub source_map: Option<SourceMap<A>>,
+    /// The SWRL rules of the document, in document order.
+    pub rules: Option<Vec<DLSafeRule<A>>>,
+    /// The description graphs and graph rules of the document.
+    pub description_graphs: Option<DescriptionGraphs<A>>,
+}
+
+impl<A: ForIRI> Default for SideTables<A> {
+    fn default() -> Self {
+        Self {
+            comments: None,
+            nested_annotations: None,
+            source_map: None,
+            rules: None,
+            description_graphs: None,
+        }
+    }
+}
+
+impl<A: ForIRI> SideTables<A> {
+    /// Create empty side tables, collecting all of them.
+    pub fn all() -> Self {
+        Self {
+            comments: Some(Comments::default()),
+            nested_annotations: Some(NestedAnnotations::default()),
+            source_map: Some(SourceMap::default()),
+            rules: Some(Vec::new()),
+            description_graphs: Some(DescriptionGraphs::default()),
+        }
+    }
+}
+
+/// An observer filling the side tables built from the pairs of a document.
+struct Observers<'t, 's, A: ForIRI> {
+    comments: Option<CommentCollector<'t, A>>,
+    nested_annotations: Option<&'s mut NestedAnnotations<A>>,
+    source_map: Option<&'s mut SourceMap<A>>,
+}
+
+impl<'t, 's, A: ForIRI> Observer<A> for Observers<'t, 's, A> {
+    fn ontology(&mut self, pair: &Pair<Rule>) {
+        if let Some(comments) = &mut self.comments {
+            comments.ontology(pair);
+        }
+    }
+
+    fn element(
+        &mut self,
+        pair: &Pair<Rule>,
+        element: &Element<A>,
+        ctx: &Context<'_, A>,
+    ) -> Result<()> {
+        if let Some(comments) = &mut self.comments {
+            comments.element(pair, element, ctx)?;
+        }
+        if let Some(nested) = &mut self.nested_annotations {
+            nested.element(pair, element, ctx)?;
+        }
+        if let Some(source_map) = &mut self.source_map {
+            source_map.element(pair, element, ctx)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sink collecting rules and description graphs, and passing everything
+/// else to an ontology.
+struct TableSink<'o, 's, A: ForIRI, O> {
+    ontology: &'o mut O,
+    rules: Option<&'s mut Vec<DLSafeRule<A>>>,
+    description_graphs: Option<&'s mut DescriptionGraphs<A>>,
+}
+
+impl<'o, 's, A: ForIRI, O: OntologySink<A>> OntologySink<A> for TableSink<'o, 's, A, O> {
+    fn prefix(&mut self, prefix: Option<&str>, iri: &str) {
+        self.ontology.prefix(prefix, iri)
+    }
+
+    fn ontology_id(&mut self, id: OntologyID<A>) {
+        self.ontology.ontology_id(id)
+    }
+
+    fn import(&mut self, import: Import<A>) {
+        self.ontology.import(import)
+    }
+
+    fn ontology_annotation(&mut self, annotation: OntologyAnnotation<A>) {
+        self.ontology.ontology_annotation(annotation)
+    }
+
+    fn axiom(&mut self, axiom: AnnotatedAxiom<A>) {
+        self.ontology.axiom(axiom)
+    }
+
+    fn rule(&mut self, rule: DLSafeRule<A>) -> bool {
+        match &mut self.rules {
+            Some(rules) => {
+                rules.push(rule);
+                true
+            }
+            None => self.ontology.rule(rule),
+        }
+    }
+
+    fn description_graph(&mut self, graph: DescriptionGraph<A>) -> bool {
+        match &mut self.description_graphs {
+            Some(graphs) => {
+                graphs.graphs.push(graph);
+                true
+            }
+            None => self.ontology.description_graph(graph),
+        }
+    }
+
+    fn graph_rule(&mut self, rule: DescriptionGraphRule<A>) -> bool {
+        match &mut self.description_graphs {
+            Some(graphs) => {
+                graphs.rules.push(rule);
+                true
+            }
+            None => self.ontology.graph_rule(rule),
+        }
+    }
+}
+
+/// Parse an entire OWL document from a string with a context, filling the given side tables.
+///
+/// Only the tables which are `Some` are filled. The source map requires the
+/// annotations and class expressions nested in the elements to be converted
+/// a second time, so collecting it makes parsing slower.
+///
+/// The side tables are built from the syntax tree of the `pest` parser, so
+/// an [`Error::Unsupported`](crate::Error::Unsupported) is returned if the
+/// context selects [`Backend::Descent`](crate::Backend::Descent).
+pub fn from_str_with_side_tables<A, O, S>(
+    src: S,
+    ctx: &Context<'_, A>,
+    tables: &mut SideTables<A>,
+) -> Result<(O, PrefixMapping)>
+where
+    A: ForIRI,
+    O: Default + OntologySink<A>,
+    S: AsRef<str>,
+{
+    if ctx.backend == Backend::Descent {
+        return Err(Error::Unsupported(String::from(
+            "side tables cannot be collected with the descent backend",
+        )));
+    }
+    let text = src.as_ref();
+    let pair = parse_exact(Rule::OntologyDocument, text, &ctx.limits)?;
+
+    let mut ontology = O::default();
+    let mut sink = TableSink {
+        ontology: &mut ontology,
+        rules: tables.rules.as_mut(),
+        description_graphs: tables.description_graphs.as_mut(),
+    };
+    let mut observers = Observers {
+        comments: tables
+            .comments
+            .as_ref()
+            .map(|_| CommentCollector::new(text)),
+        nested_annotations: tables.nested_annotations.as_mut(),
+        source_map: tables.source_map.as_mut(),
+    };
+    let prefixes = read_document_with(pair, ctx, &mut sink, &mut observers)?;
+
+    if let Some(collector) = observers.comments {
+        tables.comments = Some(collector.finish());
+    }
+    Ok((ontology, prefixes))
+}
+
+/// Parse a document with all its side tables.
+#[cfg(test)]
+pub(crate) fn collect(
+    doc: &str,
+) -> (
+    horned_owl::ontology::set::SetOntology<String>,
+    PrefixMapping,
+    SideTables<String>,
+) {
+    let mut tables = SideTables::all();
+    let (ontology, prefixes) =
+        from_str_with_side_tables(doc, &Context::default(), &mut tables).unwrap();
+    (ontology, prefixes, tables)
+}
+
+/// Parse a document with all its side tables, and write it back without its prefixes.
+#[cfg(test)]
+pub(crate) fn roundtrip(doc: &str) -> String {
+    use crate::AsFunctional;
+
+    let (ontology, prefixes, tables) = collect(doc);
+    let ctx = Context::from(&prefixes).with_side_tables(&tables);
+    ontology.as_ofn_ctx(&ctx).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::Warning;
+    use crate::WarningKind;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Prefix(var:=<urn:swrl#>)
+# the ontology
+Ontology(:o
+# a class
+Declaration(Class(:A))
+SubClassOf(Annotation(Annotation(:source "x") :comment "y") :A :B)
+DLSafeRule(Body(ClassAtom(:A Variable(var:x))) Head(ClassAtom(:B Variable(var:x))))
+DescriptionGraph(Nodes(NodeAssertion(:A :n)) Edges(EdgeAssertion(:r :n :n)) MainClasses(:A))
+)"#;
+
+    #[test]
+    fn all() {
+        let (ontology, _, tables) = collect(DOC);
+        assert_eq!(tables.comments.unwrap().header, vec![" the ontology"]);
+        assert_eq!(tables.nested_annotations.unwrap().annotations.len(), 1);
+        assert_eq!(tables.rules.unwrap().len(), 1);
+        assert_eq!(tables.description_graphs.unwrap().graphs.len(), 1);
+        let declaration = ontology
+            .iter()
+            .find(|a| matches!(a.axiom, Axiom::DeclareClass(_)))
+            .unwrap();
+        assert_eq!(tables.source_map.unwrap().axiom(declaration)[0].line, 6);
+
+        let expected = DOC.lines().filter(|l| !l.starts_with("Prefix"));
+        assert_eq!(roundtrip(DOC), expected.collect::<Vec<_>>().join("\n"));
+    }
+
+    #[test]
+    fn some() {
+        // tables which are not collected are left empty, and rules are
+        // given to the ontology
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w: Warning| warnings.borrow_mut().push(w.kind);
+        let ctx = Context::default().with_warnings(&callback);
+        let mut tables = SideTables {
+            rules: Some(Vec::new()),
+            ..SideTables::default()
+        };
+        from_str_with_side_tables::<String, SetOntology<String>, _>(DOC, &ctx, &mut tables)
+            .unwrap();
+        assert_eq!(tables.rules.map(|r| r.len()), Some(1));
+        assert!(tables.comments.is_none());
+        assert!(tables.source_map.is_none());
+        assert_eq!(
+            warnings.into_inner(),
+            vec![WarningKind::DroppedDescriptionGraph]
+        );
+    }
+
+    #[test]
+    fn descent() {
+        let ctx = Context::default().with_backend(Backend::Descent);
+        let mut tables = SideTables::all();
+        let result =
+            from_str_with_side_tables::<String, SetOntology<String>, _>(DOC, &ctx, &mut tables);
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+}
@@ -0,0 +1,533 @@
+use curie::PrefixMapping;
+use horned_owl::model::*;
+use horned_owl::ontology::axiom_mapped::AxiomMappedOntology;
+use horned_owl::ontology::indexed::ForIndex;
+use horned_owl::ontology::indexed::FourIndexedOntology;
+use horned_owl::ontology::indexed::OneIndexedOntology;
+use horned_owl::ontology::indexed::OntologyIndex;
+use horned_owl::ontology::indexed::ThreeIndexedOntology;
+use horned_owl::ontology::indexed::TwoIndexedOntology;
+use horned_owl::ontology::iri_mapped::IRIMappedOntology;
+use horned_owl::ontology::set::SetOntology;
+use pest::iterators::Pair;
+
+use crate::dg::DescriptionGraph;
+use crate::dg::DescriptionGraphRule;
+use crate::diagnostic::Location;
+use crate::error::Result;
+use crate::from_pair::declare_prefix;
+use crate::from_pair::FromPair;
+use crate::parser::Rule;
+use crate::swrl::DLSafeRule;
+use crate::warning::check_kept;
+use crate::warning::SeenAxioms;
+use crate::Context;
+use crate::WarningKind;
+
+/// A trait for types that can receive the elements of an OWL document.
+///
+/// Implement this trait to load an OWL functional document into a custom
+/// store without building an intermediate ontology first. The elements are
+/// given to the sink in the order they appear in the document: prefix
+/// declarations first, then the ontology ID, the imports, the ontology
+/// annotations, and finally the axioms.
+///
+/// This trait is implemented for all the mutable ontology types from
+/// `horned-owl`, so that a document can be parsed into any kind of indexed
+/// ontology.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::*;
+/// use horned_functional::OntologySink;
+///
+/// #[derive(Default)]
+/// struct Counter(usize);
+///
+/// impl<A: ForIRI> OntologySink<A> for Counter {
+///     fn axiom(&mut self, _axiom: AnnotatedAxiom<A>) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let doc = "Ontology(Declaration(Class(<http://example.com/A>)))";
+/// let mut counter = Counter::default();
+/// horned_functional::parse_into_sink::<String, _, _>(doc, &mut counter).unwrap();
+/// assert_eq!(counter.0, 1);
+/// ```
+pub trait OntologySink<A: ForIRI> {
+    /// Receive a prefix declaration.
+    ///
+    /// `prefix` is `None` when the declaration is for the default prefix.
+    #[allow(unused_variables)]
+    fn prefix(&mut self, prefix: Option<&str>, iri: &str) {}
+
+    /// Receive the ontology ID declared in the ontology header.
+    #[allow(unused_variables)]
+    fn ontology_id(&mut self, id: OntologyID<A>) {}
+
+    /// Receive an import declared in the ontology header.
+    #[allow(unused_variables)]
+    fn import(&mut self, import: Import<A>) {}
+
+    /// Receive an annotation of the ontology.
+    #[allow(unused_variables)]
+    fn ontology_annotation(&mut self, annotation: OntologyAnnotation<A>) {}
+
+    /// Receive an axiom of the ontology.
+    fn axiom(&mut self, axiom: AnnotatedAxiom<A>);
+
+    /// Receive a SWRL rule of the ontology, and return whether it was kept.
+    ///
+    /// `horned-owl` ontologies cannot store rules, so they are dropped
+    /// unless this method is overridden.
+    #[allow(unused_variables)]
+    fn rule(&mut self, rule: DLSafeRule<A>) -> bool {
+        false
+    }
+
+    /// Receive a description graph of the ontology, and return whether it
+    /// was kept.
+    #[allow(unused_variables)]
+    fn description_graph(&mut self, graph: DescriptionGraph<A>) -> bool {
+        false
+    }
+
+    /// Receive a description graph rule of the ontology, and return whether
+    /// it was kept.
+    #[allow(unused_variables)]
+    fn graph_rule(&mut self, rule: DescriptionGraphRule<A>) -> bool {
+        false
+    }
+}
+
+macro_rules! impl_sink {
+    ($([$($gen:tt)*] $ty:ty),+ $(,)?) => {
+        $(impl<A: ForIRI, $($gen)*> OntologySink<A> for $ty {
+            fn ontology_id(&mut self, id: OntologyID<A>) {
+                *self.mut_id() = id;
+            }
+
+            fn import(&mut self, import: Import<A>) {
+                self.insert(import);
+            }
+
+            fn ontology_annotation(&mut self, annotation: OntologyAnnotation<A>) {
+                self.insert(annotation);
+            }
+
+            fn axiom(&mut self, axiom: AnnotatedAxiom<A>) {
+                self.insert(axiom);
+            }
+        })*
+    };
+}
+
+impl_sink!(
+    [] SetOntology<A>,
+    [AA: ForIndex<A>] AxiomMappedOntology<A, AA>,
+    [AA: ForIndex<A>] IRIMappedOntology<A, AA>,
+    [AA: ForIndex<A>, I: OntologyIndex<A, AA>] OneIndexedOntology<A, AA, I>,
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA>,
+        J: OntologyIndex<A, AA>
+    ] TwoIndexedOntology<A, AA, I, J>,
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA>,
+        J: OntologyIndex<A, AA>,
+        K: OntologyIndex<A, AA>
+    ] ThreeIndexedOntology<A, AA, I, J, K>,
+    [
+        AA: ForIndex<A>,
+        I: OntologyIndex<A, AA>,
+        J: OntologyIndex<A, AA>,
+        K: OntologyIndex<A, AA>,
+        L: OntologyIndex<A, AA>
+    ] FourIndexedOntology<A, AA, I, J, K, L>,
+);
+
+/// Read an `OntologyDocument` pair into a sink.
+///
+/// The prefixes declared in the document are used to expand the IRIs of
+/// the ontology, and are returned once the document has been read.
+pub(crate) fn read_document<A, S>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+) -> Result<PrefixMapping>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+{
+    read_document_with(pair, ctx, sink, &mut ())
+}
+
+/// Read an `OntologyDocument` pair into a sink, giving its elements to an observer.
+pub(crate) fn read_document_with<A, S, V>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+    observer: &mut V,
+) -> Result<PrefixMapping>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    V: Observer<A> + ?Sized,
+{
+    let mut pairs = pair.into_inner();
+
+    // Build the prefix mapping and use it to build the ontology
+    let mut prefixes = PrefixMapping::default();
+    let mut inner = pairs.next().unwrap();
+    while inner.as_rule() == Rule::PrefixDeclaration {
+        let (prefix, iri) = declare_prefix(inner, ctx, &mut prefixes)?;
+        sink.prefix(prefix, iri);
+        inner = pairs.next().unwrap();
+    }
+
+    read_ontology_with(inner, &ctx.with_prefixes(&prefixes), sink, observer)?;
+    Ok(prefixes)
+}
+
+/// Read an `Ontology` pair into a sink.
+pub(crate) fn read_ontology<A, S>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+) -> Result<()>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+{
+    read_ontology_with(pair, ctx, sink, &mut ())
+}
+
+/// A hook receiving the elements of an ontology along with their pair.
+///
+/// This is used to collect information that `horned-owl` ontologies cannot
+/// store, without parsing the document a second time.
+pub(crate) trait Observer<A: ForIRI> {
+    /// Receive the `Ontology` pair, before any of its elements.
+    fn ontology(&mut self, _pair: &Pair<Rule>) {}
+
+    /// Receive an element before it is given to the sink.
+    ///
+    /// Imports and ontology annotations are given as axioms, with their
+    /// `Import` or `Annotation` pair. Other elements are given with their
+    /// `OntologyAxiom` pair.
+    fn element(
+        &mut self,
+        _pair: &Pair<Rule>,
+        _element: &Element<A>,
+        _ctx: &Context<'_, A>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<A: ForIRI> Observer<A> for () {}
+
+/// Read an `Ontology` pair into a sink, giving its elements to an observer.
+pub(crate) fn read_ontology_with<A, S, V>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+    observer: &mut V,
+) -> Result<()>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    V: Observer<A> + ?Sized,
+{
+    debug_assert!(pair.as_rule() == Rule::Ontology);
+    observer.ontology(&pair);
+    let end = pair.as_span().end();
+    let mut pairs = pair.into_inner();
+    let mut pair = pairs.next().unwrap();
+
+    // Parse ontology IRI and Version IRI if any
+    let mut ontology_id = OntologyID::default();
+    if pair.as_rule() == Rule::OntologyIRI {
+        let inner = pair.into_inner().next().unwrap();
+        ontology_id.iri = Some(IRI::from_pair(inner, ctx)?);
+        pair = pairs.next().unwrap();
+        if pair.as_rule() == Rule::VersionIRI {
+            let inner = pair.into_inner().next().unwrap();
+            ontology_id.viri = Some(IRI::from_pair(inner, ctx)?);
+            pair = pairs.next().unwrap();
+        }
+    }
+    sink.ontology_id(ontology_id);
+
+    // Process imports and ontology annotations
+    let annotations = pairs.next().unwrap();
+    for p in pair.into_inner().chain(annotations.into_inner()) {
+        let axiom = match p.as_rule() {
+            Rule::Import => AnnotatedAxiom::from(Import::from_pair(p.clone(), ctx)?),
+            _ => AnnotatedAxiom::from(OntologyAnnotation::from_pair(p.clone(), ctx)?),
+        };
+        let element = Element::Axiom(axiom);
+        observer.element(&p, &element, ctx)?;
+        match element {
+            Element::Axiom(AnnotatedAxiom {
+                axiom: Axiom::Import(import),
+                ..
+            }) => sink.import(import),
+            Element::Axiom(AnnotatedAxiom {
+                axiom: Axiom::OntologyAnnotation(annotation),
+                ..
+            }) => sink.ontology_annotation(annotation),
+            _ => unreachable!(),
+        }
+    }
+
+    // Process axioms, SWRL rules and description graphs
+    let axioms = pairs.next().unwrap();
+    let mut count = 0;
+    let mut seen = SeenAxioms::new(ctx);
+    for pair in axioms.into_inner() {
+        let span = pair.as_span();
+        ctx.progress(span.start(), count)?;
+        count += 1;
+        ctx.limits.check_axioms(count, span)?;
+        let element = read_element(pair.clone(), ctx)?;
+        observer.element(&pair, &element, ctx)?;
+        add_element(element, ctx, sink, &mut seen, || {
+            Some(Location::from_span(&span))
+        });
+    }
+    ctx.progress(end, count)?;
+
+    Ok(())
+}
+
+/// A top-level element of an ontology, read from an `OntologyAxiom` pair.
+pub(crate) enum Element<A> {
+    Axiom(AnnotatedAxiom<A>),
+    Rule(DLSafeRule<A>),
+    DescriptionGraph(DescriptionGraph<A>),
+    GraphRule(DescriptionGraphRule<A>),
+}
+
+/// Read an `OntologyAxiom` pair.
+pub(crate) fn read_element<A: ForIRI>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+) -> Result<Element<A>> {
+    debug_assert!(pair.as_rule() == Rule::OntologyAxiom);
+    let inner = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::Rule => {
+            let rule = inner.into_inner().next().unwrap();
+            if rule.as_rule() == Rule::DLSafeRule {
+                DLSafeRule::from_pair(rule, ctx).map(Element::Rule)
+            } else {
+                DescriptionGraphRule::from_pair(rule, ctx).map(Element::GraphRule)
+            }
+        }
+        Rule::DGAxiom => DescriptionGraph::from_pair(inner, ctx).map(Element::DescriptionGraph),
+        Rule::Axiom => AnnotatedAxiom::from_pair(inner, ctx).map(Element::Axiom),
+        rule => unreachable!("unexpected rule in OntologyAxiom: {:?}", rule),
+    }
+}
+
+/// Give an element to a sink.
+///
+/// Axioms already in `seen` and elements dropped by the sink are reported
+/// as warnings, located with `location`.
+pub(crate) fn add_element<A, S, L>(
+    element: Element<A>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+    seen: &mut SeenAxioms<A>,
+    location: L,
+) where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+    L: FnOnce() -> Option<Location>,
+{
+    match element {
+        Element::Axiom(axiom) => {
+            seen.check(ctx, &axiom, location);
+            sink.axiom(axiom);
+        }
+        Element::Rule(rule) => {
+            let kept = sink.rule(rule);
+            check_kept(ctx, kept, WarningKind::DroppedRule, location);
+        }
+        Element::DescriptionGraph(graph) => {
+            let kept = sink.description_graph(graph);
+            check_kept(ctx, kept, WarningKind::DroppedDescriptionGraph, location);
+        }
+        Element::GraphRule(rule) => {
+            let kept = sink.graph_rule(rule);
+            check_kept(ctx, kept, WarningKind::DroppedDescriptionGraph, location);
+        }
+    }
+}
+
+/// Read an `OntologyAxiom` pair into a sink.
+///
+/// Axioms already in `seen` and elements dropped by the sink are reported
+/// as warnings.
+pub(crate) fn read_ontology_element<A, S>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+    sink: &mut S,
+    seen: &mut SeenAxioms<A>,
+) -> Result<()>
+where
+    A: ForIRI,
+    S: OntologySink<A> + ?Sized,
+{
+    let span = pair.as_span();
+    let element = read_element(pair, ctx)?;
+    add_element(element, ctx, sink, seen, || {
+        Some(Location::from_span(&span))
+    });
+    Ok(())
+}
+
+/// Read an `OntologyAxiom` pair, skipping SWRL rules and description graphs.
+///
+/// Skipped elements are still parsed, so that their errors are reported,
+/// and a warning located with `location` is raised for each of them.
+pub(crate) fn read_ontology_axiom<A, L>(
+    pair: Pair<Rule>,
+    ctx: &Context<'_, A>,
+    location: L,
+) -> Result<Option<AnnotatedAxiom<A>>>
+where
+    A: ForIRI,
+    L: FnOnce() -> Option<Location>,
+{
+    let kind = match read_element(pair, ctx)? {
+        Element::Axiom(axiom) => return Ok(Some(axiom)),
+        Element::Rule(_) => WarningKind::DroppedRule,
+        Element::DescriptionGraph(_) | Element::GraphRule(_) => {
+            WarningKind::DroppedDescriptionGraph
+        }
+    };
+    check_kept(ctx, false, kind, location);
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        prefixes: Vec<(Option<String>, String)>,
+        id: Option<OntologyID<String>>,
+        imports: Vec<Import<String>>,
+        annotations: Vec<OntologyAnnotation<String>>,
+        axioms: Vec<AnnotatedAxiom<String>>,
+    }
+
+    impl OntologySink<String> for Recorder {
+        fn prefix(&mut self, prefix: Option<&str>, iri: &str) {
+            self.prefixes
+                .push((prefix.map(String::from), String::from(iri)));
+        }
+        fn ontology_id(&mut self, id: OntologyID<String>) {
+            self.id = Some(id);
+        }
+        fn import(&mut self, import: Import<String>) {
+            self.imports.push(import);
+        }
+        fn ontology_annotation(&mut self, annotation: OntologyAnnotation<String>) {
+            self.annotations.push(annotation);
+        }
+        fn axiom(&mut self, axiom: AnnotatedAxiom<String>) {
+            self.axioms.push(axiom);
+        }
+    }
+
+    #[test]
+    fn parse_into_sink() {
+        let doc = r#"
+            Prefix(:=<http://example.com/>)
+            Prefix(rdfs:=<http://www.w3.org/2000/01/rdf-schema#>)
+            Ontology(<http://example.com/ontology>
+                Import(<http://example.com/other>)
+                Annotation(rdfs:comment "an ontology")
+                Declaration(Class(:A))
+                Declaration(Class(:B))
+                SubClassOf(:A :B)
+            )
+        "#;
+
+        let build = Build::new();
+        let mut recorder = Recorder::default();
+        crate::parse_into_sink(doc.trim(), &mut recorder).unwrap();
+
+        assert_eq!(
+            recorder.prefixes,
+            vec![
+                (None, String::from("http://example.com/")),
+                (
+                    Some(String::from("rdfs")),
+                    String::from("http://www.w3.org/2000/01/rdf-schema#")
+                ),
+            ]
+        );
+        assert_eq!(
+            recorder.id.unwrap().iri,
+            Some(build.iri("http://example.com/ontology"))
+        );
+        assert_eq!(
+            recorder.imports,
+            vec![Import(build.iri("http://example.com/other"))]
+        );
+        assert_eq!(recorder.annotations.len(), 1);
+        assert_eq!(
+            recorder.axioms,
+            vec![
+                AnnotatedAxiom::from(DeclareClass(build.class("http://example.com/A"))),
+                AnnotatedAxiom::from(DeclareClass(build.class("http://example.com/B"))),
+                AnnotatedAxiom::from(SubClassOf::new(
+                    ClassExpression::Class(build.class("http://example.com/B")),
+                    ClassExpression::Class(build.class("http://example.com/A")),
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_ontology_axiom() {
+        use std::cell::RefCell;
+
+        use crate::from_ofn::parse_exact;
+        use crate::Warning;
+
+        let read = |text: &str, ctx: &Context<'_, String>| {
+            let pair = parse_exact(Rule::OntologyAxiom, text, &ctx.limits)?;
+            let span = pair.as_span();
+            super::read_ontology_axiom(pair, ctx, || Some(Location::from_span(&span)))
+        };
+
+        let warnings = RefCell::new(Vec::new());
+        let collect = |w: Warning| warnings.borrow_mut().push(w.kind);
+        let ctx = Context::default().with_warnings(&collect);
+
+        let rule = "DLSafeRule(Body() Head(ClassAtom(<http://a.com/A> Variable(<urn:swrl#x>))))";
+        assert_eq!(read(rule, &ctx).unwrap(), None);
+        let graph = "DescriptionGraphRule(Body() Head())";
+        assert_eq!(read(graph, &ctx).unwrap(), None);
+        assert_eq!(
+            warnings.into_inner(),
+            [
+                WarningKind::DroppedRule,
+                WarningKind::DroppedDescriptionGraph
+            ]
+        );
+
+        // errors in skipped elements are still reported
+        let rule = "DLSafeRule(Body() Head(ClassAtom(ex:A Variable(<urn:swrl#x>))))";
+        assert!(read(rule, &Context::default()).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Locations of the elements of a parsed document.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use horned_owl::model::*;
+use pest::iterators::Pair;
+
+use crate::diagnostic::Location;
+use crate::error::Result;
+use crate::from_pair::FromPair;
+use crate::parser::Rule;
+use crate::sink::Element;
+use crate::sink::Observer;
+use crate::Context;
+
+/// The locations of the axioms, annotations and class expressions of a document.
+///
+/// An element appearing several times in the document has one location for
+/// each of its occurrences, in document order.
+///
+/// # Example
+/// ```rust
+/// # use horned_owl::model::*;
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_functional::Context;
+/// use horned_functional::SideTables;
+///
+/// let doc = "Ontology(\n  Declaration(Class(<http://example.com/A>))\n)";
+/// let mut tables = SideTables {
+///     source_map: Some(Default::default()),
+///     ..SideTables::default()
+/// };
+/// let (ontology, _) = horned_functional::from_str_with_side_tables::<
+///     String,
+///     SetOntology<String>,
+///     _,
+/// >(doc, &Context::default(), &mut tables)
+/// .unwrap();
+/// let source_map = tables.source_map.unwrap();
+/// for axiom in ontology.iter() {
+///     let location = &source_map.axiom(axiom)[0];
+///     assert_eq!((location.line, location.column), (2, 3));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMap<A: ForIRI> {
+    axioms: HashMap<AnnotatedAxiom<A>, Vec<Location>>,
+    annotations: HashMap<Annotation<A>, Vec<Location>>,
+    class_expressions: HashMap<ClassExpression<A>, Vec<Location>>,
+}
+
+impl<A: ForIRI> Default for SourceMap<A> {
+    fn default() -> Self {
+        Self {
+            axioms: HashMap::new(),
+            annotations: HashMap::new(),
+            class_expressions: HashMap::new(),
+        }
+    }
+}
+
+impl<A: ForIRI> SourceMap<A> {
+    /// Get the locations of an axiom of the ontology.
+    pub fn axiom(&self, axiom: &AnnotatedAxiom<A>) -> &[Location] {
+        self.axioms
+            .get(axiom)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get the locations of an annotation, including ontology annotations.
+    pub fn annotation(&self, annotation: &Annotation<A>) -> &[Location] {
+        self.annotations
+            .get(annotation)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get the locations of a class expression, including named classes.
+    pub fn class_expression(&self, expression: &ClassExpression<A>) -> &[Location] {
+        self.class_expressions
+            .get(expression)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Record the location of the value read from a pair.
+    fn record<T>(
+        map: &mut HashMap<T, Vec<Location>>,
+        pair: Pair<Rule>,
+        ctx: &Context<'_, A>,
+    ) -> Result<()>
+    where
+        T: FromPair<A> + Eq + Hash,
+    {
+        let location = Location::from_span(&pair.as_span());
+        let value = T::from_pair(pair, ctx)?;
+        map.entry(value).or_default().push(location);
+        Ok(())
+    }
+}
+
+impl<A: ForIRI> Observer<A> for SourceMap<A> {
+    fn element(
+        &mut self,
+        pair: &Pair<Rule>,
+        element: &Element<A>,
+        ctx: &Context<'_, A>,
+    ) -> Result<()> {
+        // the values of top-level elements are already known
+        let location = Location::from_span(&pair.as_span());
+        if let Element::Axiom(axiom) = element {
+            if let Axiom::OntologyAnnotation(annotation) = &axiom.axiom {
+                self.annotations
+                    .entry(annotation.0.clone())
+                    .or_default()
+                    .push(location.clone());
+            }
+            self.axioms.entry(axiom.clone()).or_default().push(location);
+        }
+        // but the values of nested elements have to be read again
+        for inner in pair.clone().into_inner().flatten() {
+            match inner.as_rule() {
+                Rule::Annotation => Self::record(&mut self.annotations, inner, ctx)?,
+                Rule::ClassExpression => Self::record(&mut self.class_expressions, inner, ctx)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::side_tables::collect;
+    use crate::SideTables;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Ontology(
+  Annotation(:comment "ontology")
+  Declaration(Class(:A))
+  SubClassOf(
+    Annotation(:comment "axiom")
+    :A
+    ObjectSomeValuesFrom(:p :B)
+  )
+  Declaration(Class(:A))
+)"#;
+
+    #[test]
+    fn axioms() {
+        let (ontology, _, tables) = collect(DOC);
+        let source_map = tables.source_map.unwrap();
+        let build = Build::new();
+
+        let declaration = AnnotatedAxiom::from(DeclareClass(build.class("http://example.com/A")));
+        let locations = source_map.axiom(&declaration);
+        assert_eq!(locations.len(), 2);
+        assert_eq!((locations[0].line, locations[0].column), (4, 3));
+        assert_eq!((locations[1].line, locations[1].column), (10, 3));
+        assert_eq!(&DOC[locations[0].span.clone()], "Declaration(Class(:A))");
+
+        let subclass = ontology
+            .iter()
+            .find(|a| matches!(a.axiom, Axiom::SubClassOf(_)))
+            .unwrap();
+        let location = &source_map.axiom(subclass)[0];
+        assert_eq!((location.line, location.column), (5, 3));
+        assert_eq!((location.end_line, location.end_column), (9, 4));
+    }
+
+    #[test]
+    fn elements() {
+        let (_, _, tables) = collect(DOC);
+        let source_map = tables.source_map.unwrap();
+        let build = Build::new();
+
+        let annotation = Annotation {
+            ap: build.annotation_property("http://example.com/comment"),
+            av: AnnotationValue::Literal(Literal::Simple {
+                literal: String::from("axiom"),
+            }),
+        };
+        let location = &source_map.annotation(&annotation)[0];
+        assert_eq!((location.line, location.column), (6, 5));
+
+        let expression = ClassExpression::ObjectSomeValuesFrom {
+            ope: build.object_property("http://example.com/p").into(),
+            bce: Box::new(build.class("http://example.com/B").into()),
+        };
+        let location = &source_map.class_expression(&expression)[0];
+        assert_eq!((location.line, location.column), (8, 5));
+        assert_eq!(
+            source_map
+                .class_expression(&build.class("http://example.com/B").into())
+                .len(),
+            1
+        );
+        assert!(source_map
+            .class_expression(&build.class("http://example.com/C").into())
+            .is_empty());
+    }
+
+    #[test]
+    fn header() {
+        let doc = "Ontology(<http://example.com/o>\r\n  Import(<http://example.com/p>)\r\n  Annotation(<http://example.com/a> \"x\")\r\n)";
+        let (_, _, tables) = collect(doc);
+        let source_map = tables.source_map.unwrap();
+        let build = Build::new();
+
+        let import = AnnotatedAxiom::from(Import(build.iri("http://example.com/p")));
+        let location = &source_map.axiom(&import)[0];
+        assert_eq!((location.line, location.column), (2, 3));
+        assert_eq!((location.end_line, location.end_column), (2, 33));
+
+        let annotation = Annotation {
+            ap: build.annotation_property("http://example.com/a"),
+            av: AnnotationValue::Literal(Literal::Simple {
+                literal: String::from("x"),
+            }),
+        };
+        let axiom = AnnotatedAxiom::from(OntologyAnnotation(annotation.clone()));
+        let location = &source_map.axiom(&axiom)[0];
+        assert_eq!((location.line, location.column), (3, 3));
+        assert_eq!(
+            &doc[location.span.clone()],
+            "Annotation(<http://example.com/a> \"x\")"
+        );
+        assert_eq!(
+            source_map.annotation(&annotation),
+            std::slice::from_ref(location)
+        );
+    }
+
+    #[test]
+    fn context() {
+        let limits = crate::Limits {
+            max_axioms: 1,
+            ..crate::Limits::default()
+        };
+        let ctx = Context::default().with_limits(limits);
+        let mut tables = SideTables {
+            source_map: Some(SourceMap::default()),
+            ..SideTables::default()
+        };
+        let err = crate::from_str_with_side_tables::<String, SetOntology<String>, _>(
+            DOC,
+            &ctx,
+            &mut tables,
+        )
+        .unwrap_err();
+        assert_eq!(err.location().unwrap().line, 5);
+    }
+}
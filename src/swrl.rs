@@ -0,0 +1,189 @@
+//! SWRL rules, as written in `DLSafeRule` elements.
+//!
+//! `horned-owl` ontologies cannot store rules: they are collected in
+//! [`SideTables::rules`] when parsing, and written back when serializing an
+//! ontology with [`Context::with_rules`].
+//!
+//! [`SideTables::rules`]: crate::SideTables::rules
+//! [`Context::with_rules`]: crate::Context::with_rules
+
+use std::collections::BTreeSet;
+
+use horned_owl::model::*;
+
+/// A variable of a rule.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Variable<A>(pub IRI<A>);
+
+/// An argument of a rule atom standing for an individual.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum IArg<A> {
+    /// A variable of the rule.
+    Variable(Variable<A>),
+    /// A named or anonymous individual.
+    Individual(Individual<A>),
+}
+
+/// An argument of a rule atom standing for a literal.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DArg<A> {
+    /// A variable of the rule.
+    Variable(Variable<A>),
+    /// A literal value.
+    Literal(Literal<A>),
+}
+
+/// An atom of the body or head of a rule.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Atom<A> {
+    /// An individual is an instance of a class expression.
+    ClassAtom {
+        /// The class expression.
+        pred: ClassExpression<A>,
+        /// The individual.
+        arg: IArg<A>,
+    },
+    /// A literal is in a data range.
+    DataRangeAtom {
+        /// The data range.
+        pred: DataRange<A>,
+        /// The literal.
+        arg: DArg<A>,
+    },
+    /// Two individuals are connected by an object property.
+    ObjectPropertyAtom {
+        /// The object property expression.
+        pred: ObjectPropertyExpression<A>,
+        /// The subject and the object.
+        args: (IArg<A>, IArg<A>),
+    },
+    /// An individual is connected to a literal by a data property.
+    DataPropertyAtom {
+        /// The data property.
+        pred: DataProperty<A>,
+        /// The subject and the value.
+        args: (IArg<A>, DArg<A>),
+    },
+    /// A built-in predicate holds for some literals.
+    BuiltInAtom {
+        /// The IRI of the built-in.
+        pred: IRI<A>,
+        /// The arguments of the built-in.
+        args: Vec<DArg<A>>,
+    },
+    /// Two individuals are the same.
+    SameIndividualAtom(IArg<A>, IArg<A>),
+    /// Two individuals are different.
+    DifferentIndividualsAtom(IArg<A>, IArg<A>),
+}
+
+/// A DL-safe SWRL rule.
+///
+/// # Example
+/// ```rust
+/// use horned_functional::swrl::DLSafeRule;
+/// use horned_functional::AsFunctional;
+/// use horned_functional::FromFunctional;
+///
+/// let text = "DLSafeRule(Body(ClassAtom(<http://a.com/A> Variable(<urn:swrl#x>))) Head(ClassAtom(<http://a.com/B> Variable(<urn:swrl#x>))))";
+/// let rule = DLSafeRule::<String>::from_ofn(text).unwrap();
+/// assert_eq!(rule.body.len(), 1);
+/// assert_eq!(rule.as_ofn().to_string(), text);
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DLSafeRule<A> {
+    /// The annotations of the rule.
+    pub ann: BTreeSet<Annotation<A>>,
+    /// The atoms which must hold for the rule to apply.
+    pub body: Vec<Atom<A>>,
+    /// The atoms inferred when the rule applies.
+    pub head: Vec<Atom<A>>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use curie::PrefixMapping;
+
+    use super::*;
+    use crate::side_tables::collect;
+    use crate::side_tables::roundtrip;
+    use crate::AsFunctional;
+    use crate::Backend;
+    use crate::Context;
+    use crate::FromFunctional;
+
+    const DOC: &str = r#"Prefix(:=<http://example.com/>)
+Prefix(var:=<urn:swrl#>)
+Ontology(:o
+Declaration(Class(:Person))
+DLSafeRule(Annotation(:comment "parents") Body(ClassAtom(:Person Variable(var:x)) ObjectPropertyAtom(:hasChild Variable(var:x) Variable(var:y))) Head(ClassAtom(:Parent Variable(var:x))))
+DLSafeRule(Body(DataPropertyAtom(:age :Alice Variable(var:a)) BuiltInAtom(<http://www.w3.org/2003/11/swrlb#greaterThan> Variable(var:a) "17"^^<http://www.w3.org/2001/XMLSchema#integer>)) Head(SameIndividualAtom(:Alice :Adult) DifferentIndividualsAtom(_:b0 :Bob)))
+)"#;
+
+    #[test]
+    fn atoms() {
+        let build = Build::<String>::new();
+        let x = IArg::Variable(Variable(build.iri("urn:swrl#x")));
+
+        let atom = Atom::<String>::from_ofn("ClassAtom(<http://a.com/A> Variable(<urn:swrl#x>))");
+        assert_eq!(
+            atom.unwrap(),
+            Atom::ClassAtom {
+                pred: build.class("http://a.com/A").into(),
+                arg: x,
+            }
+        );
+
+        let atom = Atom::<String>::from_ofn(
+            "DataRangeAtom(<http://www.w3.org/2001/XMLSchema#integer> \"1\")",
+        );
+        assert!(matches!(
+            atom.unwrap(),
+            Atom::DataRangeAtom {
+                arg: DArg::Literal(_),
+                ..
+            }
+        ));
+
+        let same = Atom::<String>::from_ofn("SameIndividualAtom(Variable(<urn:swrl#x>) _:b)");
+        assert!(matches!(same.unwrap(), Atom::SameIndividualAtom(..)));
+
+        // data arguments cannot be individuals
+        assert!(DArg::<String>::from_ofn("<http://a.com/i>").is_err());
+    }
+
+    #[test]
+    fn backends() {
+        let mut prefixes = PrefixMapping::default();
+        prefixes.set_default("http://example.com/");
+        prefixes.add_prefix("var", "urn:swrl#").unwrap();
+        let pest = Context::from(&prefixes);
+        let descent = Context::from(&prefixes).with_backend(Backend::Descent);
+        for line in DOC.lines().filter(|l| l.starts_with("DLSafeRule")) {
+            let expected = DLSafeRule::<String>::from_ofn_ctx(line, &pest).unwrap();
+            let actual = DLSafeRule::<String>::from_ofn_ctx(line, &descent).unwrap();
+            assert_eq!(actual, expected);
+            assert_eq!(actual.as_ofn_ctx(&pest).to_string(), line);
+        }
+    }
+
+    #[test]
+    fn document() {
+        let (ontology, _, tables) = collect(DOC);
+        let rules = tables.rules.unwrap();
+        assert_eq!(ontology.iter().count(), 1);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].ann.len(), 1);
+        assert_eq!(rules[0].head.len(), 1);
+        assert!(matches!(
+            rules[1].body[1],
+            Atom::BuiltInAtom { ref args, .. } if args.len() == 2
+        ));
+        assert!(matches!(
+            rules[1].head[1],
+            Atom::DifferentIndividualsAtom(IArg::Individual(Individual::Anonymous(_)), _)
+        ));
+        assert_eq!(roundtrip(DOC), &DOC[DOC.find("Ontology").unwrap()..]);
+    }
+}
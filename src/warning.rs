@@ -0,0 +1,261 @@
+//! Non-fatal problems found while parsing a document.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use curie::Curie;
+use curie::PrefixMapping;
+use horned_owl::model::AnnotatedAxiom;
+use horned_owl::model::ForIRI;
+
+use crate::diagnostic::Location;
+use crate::Context;
+
+/// A kind of warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// A SWRL rule was not kept by the ontology it was read into.
+    DroppedRule,
+    /// A description graph or graph rule was not kept by the ontology it
+    /// was read into.
+    DroppedDescriptionGraph,
+    /// The annotations of an annotation were dropped while lowering a
+    /// syntax tree.
+    DroppedAnnotation,
+    /// An axiom appears several times in the ontology, and its copies are
+    /// merged by most ontology types.
+    DuplicateAxiom,
+    /// A prefix name is declared several times, the last declaration wins.
+    DuplicatePrefix,
+    /// The default prefix is declared several times, the last declaration
+    /// wins.
+    DuplicateDefaultPrefix,
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            WarningKind::DroppedRule => "dropped rule",
+            WarningKind::DroppedDescriptionGraph => "dropped description graph",
+            WarningKind::DroppedAnnotation => "dropped annotation",
+            WarningKind::DuplicateAxiom => "duplicate axiom",
+            WarningKind::DuplicatePrefix => "duplicate prefix",
+            WarningKind::DuplicateDefaultPrefix => "duplicate default prefix",
+        })
+    }
+}
+
+/// A non-fatal problem found while parsing a document.
+///
+/// Warnings are given to the callback passed to [`Context::with_warnings`]
+/// as they are found.
+///
+/// # Example
+/// ```rust
+/// # use std::cell::RefCell;
+/// # use curie::PrefixMapping;
+/// # use horned_owl::ontology::set::SetOntology;
+/// use horned_functional::Context;
+/// use horned_functional::FromFunctional;
+/// use horned_functional::WarningKind;
+///
+/// let warnings = RefCell::new(Vec::new());
+/// let callback = |w| warnings.borrow_mut().push(w);
+/// let ctx = Context::default().with_warnings(&callback);
+///
+/// let doc = "Ontology(\nDeclaration(Class(<http://example.com/A>))\nDeclaration(Class(<http://example.com/A>))\n)";
+/// let res = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(doc, &ctx);
+/// assert!(res.is_ok());
+///
+/// let warnings = warnings.into_inner();
+/// assert_eq!(warnings[0].kind, WarningKind::DuplicateAxiom);
+/// assert_eq!(warnings[0].location.as_ref().unwrap().line, 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Warning {
+    /// The kind of the warning.
+    pub kind: WarningKind,
+    /// A human-readable description of the problem.
+    pub message: String,
+    /// The location of the element the warning is about, if known.
+    pub location: Option<Location>,
+}
+
+impl Warning {
+    /// Relocate a warning found in `text[offset..]` to be relative to `text`.
+    pub(crate) fn shift_in(mut self, text: &str, offset: usize) -> Self {
+        self.location = self.location.map(|l| l.shift_in(text, offset));
+        self
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match &self.location {
+            Some(l) => write!(f, "{}:{}: {}", l.line, l.column, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Report a warning if `prefix` is already declared in `prefixes`.
+pub(crate) fn check_prefix<A, L>(
+    ctx: &Context<'_, A>,
+    prefixes: &PrefixMapping,
+    prefix: Option<&str>,
+    location: L,
+) where
+    A: ForIRI,
+    L: FnOnce() -> Option<Location>,
+{
+    if ctx.warnings.is_none() || prefixes.expand_curie(&Curie::new(prefix, "")).is_err() {
+        return;
+    }
+    match prefix {
+        Some(name) => ctx.warn(
+            WarningKind::DuplicatePrefix,
+            || format!("prefix `{}:` is declared more than once", name),
+            location,
+        ),
+        None => ctx.warn(
+            WarningKind::DuplicateDefaultPrefix,
+            || String::from("the default prefix is declared more than once"),
+            location,
+        ),
+    }
+}
+
+/// Report a warning if a rule or description graph was not kept by a sink.
+pub(crate) fn check_kept<A, L>(ctx: &Context<'_, A>, kept: bool, kind: WarningKind, location: L)
+where
+    A: ForIRI,
+    L: FnOnce() -> Option<Location>,
+{
+    if !kept {
+        let message = match kind {
+            WarningKind::DroppedRule => "SWRL rule is not kept by the ontology",
+            _ => "description graph is not kept by the ontology",
+        };
+        ctx.warn(kind, || String::from(message), location);
+    }
+}
+
+/// The axioms read so far, to report duplicate axioms.
+///
+/// Axioms are only recorded when warnings are collected.
+pub(crate) struct SeenAxioms<A: ForIRI>(Option<HashSet<AnnotatedAxiom<A>>>);
+
+impl<A: ForIRI> SeenAxioms<A> {
+    /// Create an empty record for parsing with the given context.
+    pub(crate) fn new(ctx: &Context<'_, A>) -> Self {
+        Self(ctx.warnings.map(|_| HashSet::new()))
+    }
+
+    /// Record an axiom, and report a warning if it was seen before.
+    pub(crate) fn check<L>(&mut self, ctx: &Context<'_, A>, axiom: &AnnotatedAxiom<A>, location: L)
+    where
+        L: FnOnce() -> Option<Location>,
+    {
+        if let Some(seen) = &mut self.0 {
+            if !seen.insert(axiom.clone()) {
+                ctx.warn(
+                    WarningKind::DuplicateAxiom,
+                    || String::from("axiom is declared more than once"),
+                    location,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+
+    use horned_owl::ontology::set::SetOntology;
+
+    use super::*;
+    use crate::sink::OntologySink;
+    use crate::swrl::DLSafeRule;
+    use crate::Backend;
+    use crate::FromFunctional;
+
+    const DOC: &str = r#"Prefix(ex:=<http://example.com/>)
+Prefix(:=<http://example.com/a#>)
+Prefix(ex:=<http://example.com/b#>)
+Prefix(:=<http://example.com/c#>)
+Ontology(
+Declaration(Class(ex:A))
+DLSafeRule(Body(ClassAtom(ex:A Variable(ex:x))) Head(ClassAtom(ex:B Variable(ex:x))))
+Declaration(Class(ex:A))
+DescriptionGraph(Nodes(NodeAssertion(ex:A ex:n)) Edges(EdgeAssertion(ex:r ex:n ex:n)) MainClasses(ex:A))
+)"#;
+
+    fn warnings(backend: Backend) -> Vec<Warning> {
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w| warnings.borrow_mut().push(w);
+        let ctx = Context::<String>::default()
+            .with_backend(backend)
+            .with_warnings(&callback);
+        <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(DOC, &ctx).unwrap();
+        warnings.into_inner()
+    }
+
+    #[test]
+    fn kinds() {
+        let warnings = warnings(Backend::Pest);
+        let kinds = warnings.iter().map(|w| w.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                WarningKind::DuplicatePrefix,
+                WarningKind::DuplicateDefaultPrefix,
+                WarningKind::DroppedRule,
+                WarningKind::DuplicateAxiom,
+                WarningKind::DroppedDescriptionGraph,
+            ]
+        );
+        let lines = warnings
+            .iter()
+            .map(|w| w.location.as_ref().unwrap().line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [3, 4, 7, 8, 9]);
+        assert_eq!(
+            warnings[0].to_string(),
+            "3:8: prefix `ex:` is declared more than once"
+        );
+    }
+
+    #[test]
+    fn backends() {
+        assert_eq!(warnings(Backend::Descent), warnings(Backend::Pest));
+    }
+
+    #[test]
+    fn kept() {
+        #[derive(Default)]
+        struct Rules(usize);
+
+        impl OntologySink<String> for Rules {
+            fn axiom(&mut self, _axiom: AnnotatedAxiom<String>) {}
+            fn rule(&mut self, _rule: DLSafeRule<String>) -> bool {
+                self.0 += 1;
+                true
+            }
+        }
+
+        let warnings = RefCell::new(Vec::new());
+        let callback = |w| warnings.borrow_mut().push(w);
+        let ctx = Context::<String>::default().with_warnings(&callback);
+        let mut rules = Rules::default();
+        crate::parse_into_sink_ctx(DOC, &mut rules, &ctx).unwrap();
+        assert_eq!(rules.0, 1);
+        assert!(warnings
+            .into_inner()
+            .iter()
+            .all(|w| w.kind != WarningKind::DroppedRule));
+    }
+}
@@ -1,9 +1,13 @@
 //! Test parser on OWL Functional files converted from OBO with ROBOT.
 
+extern crate curie;
 extern crate horned_functional;
 extern crate horned_owl;
 
 use curie::PrefixMapping;
+use horned_functional::Backend;
+use horned_functional::Context;
+use horned_functional::FromFunctional;
 use horned_owl::ontology::set::SetOntology;
 
 macro_rules! foundrytest {
@@ -17,8 +21,71 @@ macro_rules! foundrytest {
                 .join("data")
                 .join(stringify!($name))
                 .with_extension("ofn");
-            if let Err(e) = horned_functional::from_file::<String, SetOntology<String>, _>(&path) {
-                panic!("could not parse {}: {}", stringify!($name), e);
+            let expected = match horned_functional::from_file::<String, SetOntology<String>, _>(&path) {
+                Ok(expected) => expected,
+                Err(e) => panic!("could not parse {}: {}", stringify!($name), e),
+            };
+            let text = std::fs::read_to_string(&path).unwrap();
+            let ctx = Context::default().with_backend(Backend::Descent);
+            match <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(&text, &ctx) {
+                Ok(actual) => assert_eq!(actual, expected),
+                Err(e) => panic!("could not parse {} with descent: {}", stringify!($name), e),
+            }
+            match horned_functional::from_str_lenient::<String, SetOntology<String>, _>(&text) {
+                Ok((ontology, prefixes, errors)) => {
+                    assert_eq!((ontology, prefixes), expected);
+                    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+                }
+                Err(e) => panic!("could not parse {} leniently: {}", stringify!($name), e),
+            }
+            #[cfg(feature = "parallel")]
+            match horned_functional::from_str_parallel::<String, SetOntology<String>, _>(&text) {
+                Ok(actual) => assert_eq!(actual.0, expected.0),
+                Err(e) => panic!("could not parse {} in parallel: {}", stringify!($name), e),
+            }
+        }
+    );
+}
+
+macro_rules! stricttest {
+    ( $(#[$attr:meta])* $name:ident) => (
+        #[test]
+        $(#[$attr])*
+        fn $name() {
+            let path = std::path::PathBuf::from(file!())
+                .parent()
+                .unwrap()
+                .join("data")
+                .join(stringify!($name))
+                .with_extension("ofn");
+            let text = std::fs::read_to_string(&path).unwrap();
+            for backend in [Backend::Pest, Backend::Descent] {
+                let ctx = Context::default().with_backend(backend).with_strict(true);
+                assert!(<(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(&text, &ctx).is_err());
+            }
+        }
+    );
+}
+
+macro_rules! lenienttest {
+    ( $(#[$attr:meta])* $name:ident) => (
+        #[test]
+        $(#[$attr])*
+        fn $name() {
+            let path = std::path::PathBuf::from(file!())
+                .parent()
+                .unwrap()
+                .join("data")
+                .join(stringify!($name))
+                .with_extension("ofn");
+            let text = std::fs::read_to_string(&path).unwrap();
+            assert!(horned_functional::from_str::<String, SetOntology<String>, _>(&text).is_err());
+            match horned_functional::from_str_lenient::<String, SetOntology<String>, _>(&text) {
+                Ok((ontology, _, errors)) => {
+                    assert!(ontology.iter().next().is_some());
+                    assert!(!errors.is_empty());
+                }
+                Err(e) => panic!("could not parse {} leniently: {}", stringify!($name), e),
             }
         }
     );
@@ -34,7 +101,7 @@ foundrytest!(bila);
 foundrytest!(bspo);
 foundrytest!(cdao);
 foundrytest!(ceph);
-// foundrytest!(cheminf);  // anonymous annotation target
+foundrytest!(cheminf);
 foundrytest!(chiro);
 foundrytest!(cio);
 foundrytest!(clao);
@@ -47,7 +114,7 @@ foundrytest!(cto);
 foundrytest!(ddanat);
 foundrytest!(ddpheno);
 foundrytest!(depictions);
-// foundrytest!(dideo);  // anonymous annotation target
+foundrytest!(dideo);
 foundrytest!(disdriv);
 foundrytest!(duo);
 foundrytest!(ecao);
@@ -59,7 +126,7 @@ foundrytest!(fix);
 foundrytest!(gecko);
 foundrytest!(genepio);
 foundrytest!(geno);
-// foundrytest!(geo);  // anonymous annotation target
+foundrytest!(geo);
 foundrytest!(hancestro);
 foundrytest!(hom);
 foundrytest!(hsapdv);
@@ -86,9 +153,9 @@ foundrytest!(oarcs);
 foundrytest!(obi_core);
 foundrytest!(ogms);
 foundrytest!(ogsf);
-// foundrytest!(omiabis); // broken language tag
+// omiabis has an unescaped quote in a literal (line 1787), see `lenient::omiabis`
 foundrytest!(omo);
-// foundrytest!(omrse);  // anonymous annotation target
+foundrytest!(omrse);
 foundrytest!(one);
 foundrytest!(ontoavida);
 foundrytest!(ontoneo);
@@ -106,7 +173,7 @@ foundrytest!(rex);
 foundrytest!(ro);
 foundrytest!(rxno);
 foundrytest!(sbo);
-// foundrytest!(sepio); // anonymous annotation target
+foundrytest!(sepio);
 foundrytest!(sibo);
 foundrytest!(spd);
 foundrytest!(symp);
@@ -120,3 +187,19 @@ foundrytest!(wbls);
 foundrytest!(xlmod);
 foundrytest!(zeco);
 foundrytest!(zfs);
+
+mod lenient {
+    use super::*;
+
+    lenienttest!(omiabis);
+}
+
+mod strict {
+    use super::*;
+
+    stricttest!(cheminf);
+    stricttest!(dideo);
+    stricttest!(geo);
+    stricttest!(omrse);
+    stricttest!(sepio);
+}
@@ -0,0 +1,111 @@
+//! Run the parsers on the corpus of the fuzzing targets in `fuzz/corpus`.
+//!
+//! Inputs that made a parser panic are added to the corpus once fixed, so
+//! that the parsers keep returning an error on them.
+
+extern crate curie;
+extern crate horned_functional;
+extern crate horned_owl;
+
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::path::PathBuf;
+
+use curie::PrefixMapping;
+use horned_functional::AxiomReader;
+use horned_functional::Backend;
+use horned_functional::Context;
+use horned_functional::FromFunctional;
+use horned_owl::model::AnnotatedAxiom;
+use horned_owl::model::ClassExpression;
+use horned_owl::ontology::set::SetOntology;
+
+/// Get the contents of the corpus of the given fuzzing target.
+fn corpus(target: &str) -> Vec<(PathBuf, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz")
+        .join("corpus")
+        .join(target);
+    let mut inputs = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .map(|path| {
+            let text = std::fs::read_to_string(&path).unwrap();
+            (path, text)
+        })
+        .collect::<Vec<_>>();
+    inputs.sort();
+    assert!(!inputs.is_empty());
+    inputs
+}
+
+/// Run the parsers on an input, failing with its path if any of them panics.
+fn check<F: FnOnce()>(path: &Path, f: F) {
+    let result = std::panic::catch_unwind(AssertUnwindSafe(f));
+    assert!(result.is_ok(), "parser panicked on {}", path.display());
+}
+
+fn prefixes() -> PrefixMapping {
+    let mut prefixes = PrefixMapping::default();
+    prefixes.set_default("http://example.com/");
+    prefixes
+        .add_prefix("obo", "http://purl.obolibrary.org/obo/")
+        .unwrap();
+    prefixes
+        .add_prefix("xsd", "http://www.w3.org/2001/XMLSchema#")
+        .unwrap();
+    prefixes
+}
+
+#[test]
+fn axiom() {
+    let prefixes = prefixes();
+    for (path, text) in corpus("axiom") {
+        check(&path, || {
+            for backend in [Backend::Pest, Backend::Descent] {
+                let ctx = Context::new(None, &prefixes).with_backend(backend);
+                let _ = AnnotatedAxiom::<String>::from_ofn_ctx(&text, &ctx);
+            }
+        });
+    }
+}
+
+#[test]
+fn class_expression() {
+    let prefixes = prefixes();
+    for (path, text) in corpus("class_expression") {
+        check(&path, || {
+            for backend in [Backend::Pest, Backend::Descent] {
+                let ctx = Context::new(None, &prefixes).with_backend(backend);
+                let _ = ClassExpression::<String>::from_ofn_ctx(&text, &ctx);
+            }
+        });
+    }
+}
+
+#[test]
+fn ontology_document() {
+    for (path, text) in corpus("ontology_document") {
+        check(&path, || {
+            for backend in [Backend::Pest, Backend::Descent] {
+                let ctx = Context::default().with_backend(backend);
+                let _ = <(SetOntology<String>, PrefixMapping)>::from_ofn_ctx(&text, &ctx);
+            }
+            let _ = horned_functional::from_str_lenient::<String, SetOntology<String>, _>(&text);
+            if let Ok(reader) = AxiomReader::<String, _>::new(text.as_bytes()) {
+                reader.for_each(drop);
+            }
+        });
+    }
+}
+
+#[test]
+fn cardinality_overflow() {
+    let text = "ObjectMinCardinality(99999999999 <http://example.com/p>)";
+    for backend in [Backend::Pest, Backend::Descent] {
+        let ctx = Context::default().with_backend(backend);
+        let err = ClassExpression::<String>::from_ofn_ctx(text, &ctx).unwrap_err();
+        assert_eq!(err.message(), "integer is too large");
+        assert_eq!(err.location().unwrap().span, 21..32);
+    }
+}
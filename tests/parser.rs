@@ -0,0 +1,70 @@
+extern crate horned_functional;
+extern crate horned_owl;
+extern crate pest;
+
+use horned_functional::parser::FromPair;
+use horned_functional::parser::OwlFunctionalParser;
+use horned_functional::parser::Rule;
+use horned_functional::Context;
+use horned_functional::Error;
+use horned_functional::FromFunctional;
+use horned_functional::Limits;
+use horned_functional::Result;
+use horned_owl::model::ForIRI;
+use horned_owl::model::IRI;
+use pest::iterators::Pair;
+
+/// The IRIs of the classes of a disjoint union, in source order.
+#[derive(Debug, PartialEq)]
+struct DisjointClassNames<A: ForIRI>(Vec<IRI<A>>);
+
+impl<A: ForIRI> FromPair<A> for DisjointClassNames<A> {
+    const RULE: Rule = Rule::DisjointClasses;
+    fn from_pair_unchecked(pair: Pair<Rule>, ctx: &Context<'_, A>) -> Result<Self> {
+        pair.into_inner()
+            .filter(|p| p.as_rule() == Rule::ClassExpression)
+            .map(|p| {
+                IRI::from_pair(
+                    p.into_inner().next().unwrap().into_inner().next().unwrap(),
+                    ctx,
+                )
+            })
+            .collect::<Result<_>>()
+            .map(DisjointClassNames)
+    }
+}
+
+impl<A: ForIRI> FromFunctional<A> for DisjointClassNames<A> {
+    fn from_ofn_ctx(s: &str, ctx: &Context<'_, A>) -> Result<Self> {
+        OwlFunctionalParser::parse_exact(Self::RULE, s, ctx.limits())
+            .and_then(|pair| Self::from_pair(pair, ctx))
+    }
+}
+
+#[test]
+fn downstream_type() {
+    let names = DisjointClassNames::<String>::from_ofn(
+        "DisjointClasses(<http://example.com/B> <http://example.com/A>)",
+    )
+    .unwrap();
+    assert_eq!(names.0.len(), 2);
+    assert_eq!(names.0[0].as_ref(), "http://example.com/B");
+}
+
+#[test]
+fn remaining_input() {
+    let res = OwlFunctionalParser::parse_exact(
+        Rule::Class,
+        "<http://example.com/A> <http://example.com/B>",
+        &Limits::default(),
+    );
+    match res {
+        Err(Error::Pest(e)) => assert_eq!(
+            e.variant,
+            pest::error::ErrorVariant::CustomError {
+                message: "remaining input".to_string()
+            }
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}